@@ -6,8 +6,8 @@ extern crate nanovg;
 use chrono::prelude::*;
 use glutin::GlContext;
 use nanovg::{
-    Alignment, BasicCompositeOperation, Color, CompositeOperation, Font, Gradient, PathOptions, StrokeOptions,
-    TextOptions, Transform,
+    Alignment, BasicCompositeOperation, Color, CompositeOperation, Direction, Font, Gradient, PathData, PathOptions,
+    StrokeOptions, TextOptions, Transform, Winding,
 };
 use std::f32::consts::PI;
 use std::{thread, time};
@@ -33,6 +33,7 @@ fn main() {
         gl::ClearColor(0.0, 0.0, 0.0, 1.0);
     }
     let context = nanovg::ContextBuilder::new()
+        .gl_loader(|symbol| gl_window.get_proc_address(symbol) as *const _)
         .stencil_strokes()
         .build()
         .expect("Initialization of NanoVG failed!");
@@ -200,10 +201,13 @@ fn main() {
                     },
                 );
             }
-            // date-string
-            frame.text(
+            // date-string, curved along the bottom of the dial
+            let mut date_arc = PathData::new();
+            date_arc.arc(dial_center, dial_radius * 0.7, PI, 0.0, Winding::Direction(Direction::CounterClockwise));
+            frame.text_on_path(
                 roboto_font,
-                (dial_center.0, dial_center.1 + dial_radius * 0.7),
+                &date_arc,
+                date_arc.length() / 2.0,
                 format!("{:4}-{:02}-{:02}", year, month, day),
                 TextOptions {
                     color: silver,
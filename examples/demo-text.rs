@@ -26,6 +26,7 @@ fn main() {
     }
 
     let context = nanovg::ContextBuilder::new()
+        .gl_loader(|symbol| gl_window.get_proc_address(symbol) as *const _)
         .stencil_strokes()
         .build()
         .expect("Initialization of NanoVG failed!");
@@ -4,7 +4,7 @@ use std::env;
 use std::path::Path;
 use std::process::Command;
 
-fn build_library(backend_macro: &str) {
+fn build_library() {
     let target = env::var("TARGET").unwrap();
     let mut config = cc::Build::new();
 
@@ -14,36 +14,30 @@ fn build_library(backend_macro: &str) {
                                     // (msvc accepts /w or -w, gcc and clang only -w)
 
     config.include("nanovg/src");
+    config.include(".");
+    config.include("glad");
+    config.file("glad/glad.c");
     config.file("nanovg/src/nanovg.c");
     config.file("nanovg_shim.c");
-    config.define(backend_macro, None);
+    config.file("nanovg_headless.c");
+    // Every backend is compiled in, the context picks one at runtime.
+    for backend in &["gl2", "gl3", "gles2", "gles3"] {
+        config.file(format!("nanovg_{}.c", backend));
+    }
+    // OpenGL is loaded at runtime, glad's default loader only needs dlopen.
     if target.contains("linux") {
-        println!("cargo:rustc-link-lib=GL");
-    } else if target.contains("darwin") {
-        println!("cargo:rustc-link-lib=framework=OpenGL");
-    } else if target.contains("windows") {
-        config.file("glad/glad.c");
-        config.include("glad");
+        println!("cargo:rustc-link-lib=dl");
     }
 
     config.compile("nanovg");
 }
 
 fn main() {
-    let backend_macro = ["GL3", "GL2", "GLES3", "GLES2"]
-        .iter()
-        .filter(|f| env::var(format!("CARGO_FEATURE_{}", f)).is_ok())
-        .map(|f| format!("NANOVG_{}_IMPLEMENTATION", f))
-        .next()
-        .expect(
-            "Unable to determine the backend / implementation. Have you enabled one of the features?",
-        );
-
     // Initialize nanovg submodule if user forgot to clone parent repository with --recursive.
     if !Path::new("nanovg/.git").exists() {
         let _ = Command::new("git").args(&["submodule", "update", "--init"])
                                    .status();
     }
 
-    build_library(&backend_macro);
+    build_library();
 }
@@ -4,12 +4,23 @@
 #[macro_use]
 extern crate bitflags;
 
-use std::os::raw::{c_char, c_uchar, c_int, c_float, c_void};
+use std::os::raw::{c_char, c_uchar, c_int, c_uint, c_float, c_void};
 
 pub const FONS_INVALID: c_int = -1;
 
 pub type NVGcontext = c_void;
 
+/// A headless OpenGL context from `nanovg_headless.c`.
+pub type NVGheadless = c_void;
+
+pub const NVG_HEADLESS_GL2: c_int = 0;
+pub const NVG_HEADLESS_GL3: c_int = 1;
+pub const NVG_HEADLESS_GLES2: c_int = 2;
+pub const NVG_HEADLESS_GLES3: c_int = 3;
+
+/// Returns the address of an OpenGL function by name.
+pub type GLADloadproc = Option<unsafe extern "C" fn(name: *const c_char) -> *mut c_void>;
+
 // No reason to use a union here, since the nanovg guys
 // only used it for convenience.
 #[repr(C)]
@@ -80,6 +91,8 @@ bitflags! {
 		const NVG_IMAGE_FLIPY = 1 << 3;
 		const NVG_IMAGE_PREMULTIPLIED = 1 << 4;
 		const NVG_IMAGE_NEAREST = 1 << 5;
+		// From nanovg_gl.h: don't delete the GL texture together with the image.
+		const NVG_IMAGE_NODELETE = 1 << 16;
 	}
 }
 
@@ -119,7 +132,7 @@ pub enum NVGcompositeOperation {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct NVGcompositeOperationState {
     pub srcRGB: c_int,
     pub dstRGB: c_int,
@@ -127,6 +140,115 @@ pub struct NVGcompositeOperationState {
     pub dstAlpha: c_int,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NVGtexture {
+    NVG_TEXTURE_ALPHA = 0x01,
+    NVG_TEXTURE_RGBA = 0x02,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NVGscissor {
+    pub xform: [c_float; 6],
+    pub extent: [c_float; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NVGvertex {
+    pub x: c_float,
+    pub y: c_float,
+    pub u: c_float,
+    pub v: c_float,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NVGpath {
+    pub first: c_int,
+    pub count: c_int,
+    pub closed: c_uchar,
+    pub nbevel: c_int,
+    pub fill: *mut NVGvertex,
+    pub nfill: c_int,
+    pub stroke: *mut NVGvertex,
+    pub nstroke: c_int,
+    pub winding: c_int,
+    pub convex: c_int,
+}
+
+#[repr(C)]
+pub struct NVGparams {
+    pub userPtr: *mut c_void,
+    pub edgeAntiAlias: c_int,
+    pub renderCreate: Option<unsafe extern "C" fn(uptr: *mut c_void) -> c_int>,
+    pub renderCreateTexture: Option<
+        unsafe extern "C" fn(
+            uptr: *mut c_void,
+            type_: c_int,
+            w: c_int,
+            h: c_int,
+            imageFlags: c_int,
+            data: *const c_uchar,
+        ) -> c_int,
+    >,
+    pub renderDeleteTexture: Option<unsafe extern "C" fn(uptr: *mut c_void, image: c_int) -> c_int>,
+    pub renderUpdateTexture: Option<
+        unsafe extern "C" fn(
+            uptr: *mut c_void,
+            image: c_int,
+            x: c_int,
+            y: c_int,
+            w: c_int,
+            h: c_int,
+            data: *const c_uchar,
+        ) -> c_int,
+    >,
+    pub renderGetTextureSize:
+        Option<unsafe extern "C" fn(uptr: *mut c_void, image: c_int, w: *mut c_int, h: *mut c_int) -> c_int>,
+    pub renderViewport:
+        Option<unsafe extern "C" fn(uptr: *mut c_void, width: c_float, height: c_float, devicePixelRatio: c_float)>,
+    pub renderCancel: Option<unsafe extern "C" fn(uptr: *mut c_void)>,
+    pub renderFlush: Option<unsafe extern "C" fn(uptr: *mut c_void)>,
+    pub renderFill: Option<
+        unsafe extern "C" fn(
+            uptr: *mut c_void,
+            paint: *mut NVGpaint,
+            compositeOperation: NVGcompositeOperationState,
+            scissor: *mut NVGscissor,
+            fringe: c_float,
+            bounds: *const c_float,
+            paths: *const NVGpath,
+            npaths: c_int,
+        ),
+    >,
+    pub renderStroke: Option<
+        unsafe extern "C" fn(
+            uptr: *mut c_void,
+            paint: *mut NVGpaint,
+            compositeOperation: NVGcompositeOperationState,
+            scissor: *mut NVGscissor,
+            fringe: c_float,
+            strokeWidth: c_float,
+            paths: *const NVGpath,
+            npaths: c_int,
+        ),
+    >,
+    pub renderTriangles: Option<
+        unsafe extern "C" fn(
+            uptr: *mut c_void,
+            paint: *mut NVGpaint,
+            compositeOperation: NVGcompositeOperationState,
+            scissor: *mut NVGscissor,
+            verts: *const NVGvertex,
+            nverts: c_int,
+            fringe: c_float,
+        ),
+    >,
+    pub renderDelete: Option<unsafe extern "C" fn(uptr: *mut c_void)>,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct NVGglyphPosition {
@@ -136,6 +258,17 @@ pub struct NVGglyphPosition {
     pub maxx: c_float,
 }
 
+/// An offscreen framebuffer from `nanovg_gl_utils.h`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct NVGLUframebuffer {
+    pub ctx: *mut NVGcontext,
+    pub fbo: c_uint,
+    pub rbo: c_uint,
+    pub texture: c_uint,
+    pub image: c_int,
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct NVGtextRow {
@@ -148,8 +281,9 @@ pub struct NVGtextRow {
 }
 
 extern "C" {
-	#[cfg(target_os = "windows")]
-    pub fn gladLoadGL() -> i32;
+    pub fn gladLoadGL() -> c_int;
+    pub fn gladLoadGLLoader(load: GLADloadproc) -> c_int;
+    pub fn nvgShimLoadGL(load: GLADloadproc) -> c_int;
     pub fn nvgBeginFrame(
         ctx: *mut NVGcontext,
         windowWidth: c_float,
@@ -432,20 +566,43 @@ extern "C" {
         rows: *mut NVGtextRow,
         maxRows: c_int,
     ) -> c_int;
-	#[cfg(feature = "gl2")]
+    pub fn nvgCreateInternal(params: *mut NVGparams) -> *mut NVGcontext;
+    pub fn nvgDeleteInternal(ctx: *mut NVGcontext);
+    pub fn nvgInternalParams(ctx: *mut NVGcontext) -> *mut NVGparams;
     pub fn nvgCreateGL2(flags: c_int) -> *mut NVGcontext;
-	#[cfg(feature = "gl2")]
     pub fn nvgDeleteGL2(ctx: *mut NVGcontext);
-	#[cfg(feature = "gl3")]
+    pub fn nvglCreateImageFromHandleGL2(ctx: *mut NVGcontext, textureId: c_uint, w: c_int, h: c_int, flags: c_int) -> c_int;
+    pub fn nvglImageHandleGL2(ctx: *mut NVGcontext, image: c_int) -> c_uint;
     pub fn nvgCreateGL3(flags: c_int) -> *mut NVGcontext;
-	#[cfg(feature = "gl3")]
     pub fn nvgDeleteGL3(ctx: *mut NVGcontext);
-	#[cfg(feature = "gles2")]
+    pub fn nvglCreateImageFromHandleGL3(ctx: *mut NVGcontext, textureId: c_uint, w: c_int, h: c_int, flags: c_int) -> c_int;
+    pub fn nvglImageHandleGL3(ctx: *mut NVGcontext, image: c_int) -> c_uint;
     pub fn nvgCreateGLES2(flags: c_int) -> *mut NVGcontext;
-	#[cfg(feature = "gles2")]
     pub fn nvgDeleteGLES2(ctx: *mut NVGcontext);
-	#[cfg(feature = "gles3")]
+    pub fn nvglCreateImageFromHandleGLES2(ctx: *mut NVGcontext, textureId: c_uint, w: c_int, h: c_int, flags: c_int) -> c_int;
+    pub fn nvglImageHandleGLES2(ctx: *mut NVGcontext, image: c_int) -> c_uint;
     pub fn nvgCreateGLES3(flags: c_int) -> *mut NVGcontext;
-	#[cfg(feature = "gles3")]
     pub fn nvgDeleteGLES3(ctx: *mut NVGcontext);
+    pub fn nvglCreateImageFromHandleGLES3(ctx: *mut NVGcontext, textureId: c_uint, w: c_int, h: c_int, flags: c_int) -> c_int;
+    pub fn nvglImageHandleGLES3(ctx: *mut NVGcontext, image: c_int) -> c_uint;
+    // The functions of nanovg_gl_utils.h, suffixed with the backend they belong to.
+    pub fn nvgluCreateFramebufferGL2(ctx: *mut NVGcontext, w: c_int, h: c_int, imageFlags: c_int) -> *mut NVGLUframebuffer;
+    pub fn nvgluBindFramebufferGL2(fb: *mut NVGLUframebuffer);
+    pub fn nvgluDeleteFramebufferGL2(fb: *mut NVGLUframebuffer);
+    pub fn nvgluCreateFramebufferGL3(ctx: *mut NVGcontext, w: c_int, h: c_int, imageFlags: c_int) -> *mut NVGLUframebuffer;
+    pub fn nvgluBindFramebufferGL3(fb: *mut NVGLUframebuffer);
+    pub fn nvgluDeleteFramebufferGL3(fb: *mut NVGLUframebuffer);
+    pub fn nvgluCreateFramebufferGLES2(ctx: *mut NVGcontext, w: c_int, h: c_int, imageFlags: c_int) -> *mut NVGLUframebuffer;
+    pub fn nvgluBindFramebufferGLES2(fb: *mut NVGLUframebuffer);
+    pub fn nvgluDeleteFramebufferGLES2(fb: *mut NVGLUframebuffer);
+    pub fn nvgluCreateFramebufferGLES3(ctx: *mut NVGcontext, w: c_int, h: c_int, imageFlags: c_int) -> *mut NVGLUframebuffer;
+    pub fn nvgluBindFramebufferGLES3(fb: *mut NVGLUframebuffer);
+    pub fn nvgluDeleteFramebufferGLES3(fb: *mut NVGLUframebuffer);
+    pub fn nvgShimBeginFramebuffer(fb: *mut NVGLUframebuffer, width: c_int, height: c_int, state: *mut c_int);
+    pub fn nvgShimEndFramebuffer(state: *const c_int);
+    pub fn nvgShimReadPixels(fb: *mut NVGLUframebuffer, width: c_int, height: c_int, data: *mut c_uchar);
+    pub fn nvgShimCreateHeadless(backend: c_int, width: c_int, height: c_int) -> *mut NVGheadless;
+    pub fn nvgShimHeadlessMakeCurrent(headless: *mut NVGheadless) -> c_int;
+    pub fn nvgShimHeadlessGetProcAddress(headless: *mut NVGheadless, name: *const c_char) -> *mut c_void;
+    pub fn nvgShimDestroyHeadless(headless: *mut NVGheadless);
 }
@@ -0,0 +1,3 @@
+#define NANOVG_GL2_IMPLEMENTATION
+#define NVG_BACKEND_SUFFIX GL2
+#include "nanovg_gl_backend.h"
@@ -0,0 +1,3 @@
+#define NANOVG_GL3_IMPLEMENTATION
+#define NVG_BACKEND_SUFFIX GL3
+#include "nanovg_gl_backend.h"
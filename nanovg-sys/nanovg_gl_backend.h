@@ -0,0 +1,16 @@
+// Compiles one NanoVG GL backend. Include after defining one of the NANOVG_*_IMPLEMENTATION macros
+// and NVG_BACKEND_SUFFIX to the matching suffix, e.g. GL3.
+//
+// All backends are linked into the same library, so the functions of nanovg_gl_utils.h, which
+// don't carry a backend suffix, are renamed to e.g. nvgluCreateFramebufferGL3.
+
+#include "nanovg_gl_headers.h"
+
+#define NVG_BACKEND_CONCAT2(name, suffix) name##suffix
+#define NVG_BACKEND_CONCAT(name, suffix) NVG_BACKEND_CONCAT2(name, suffix)
+#define nvgluCreateFramebuffer NVG_BACKEND_CONCAT(nvgluCreateFramebuffer, NVG_BACKEND_SUFFIX)
+#define nvgluBindFramebuffer NVG_BACKEND_CONCAT(nvgluBindFramebuffer, NVG_BACKEND_SUFFIX)
+#define nvgluDeleteFramebuffer NVG_BACKEND_CONCAT(nvgluDeleteFramebuffer, NVG_BACKEND_SUFFIX)
+
+#include <nanovg_gl.h>
+#include <nanovg_gl_utils.h>
@@ -0,0 +1,9 @@
+#ifndef NANOVG_GL_HEADERS_H
+#define NANOVG_GL_HEADERS_H
+
+// OpenGL functions are loaded at runtime through glad on every platform, see nvgShimLoadGL.
+#include <glad.h>
+
+#include <nanovg.h>
+
+#endif
@@ -0,0 +1,3 @@
+#define NANOVG_GLES2_IMPLEMENTATION
+#define NVG_BACKEND_SUFFIX GLES2
+#include "nanovg_gl_backend.h"
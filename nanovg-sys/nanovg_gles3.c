@@ -0,0 +1,3 @@
+#define NANOVG_GLES3_IMPLEMENTATION
+#define NVG_BACKEND_SUFFIX GLES3
+#include "nanovg_gl_backend.h"
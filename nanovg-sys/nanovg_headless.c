@@ -0,0 +1,314 @@
+// Headless OpenGL contexts through EGL (pbuffer on the surfaceless or default display) or OSMesa.
+// Both libraries are loaded at runtime, so neither has to be installed to build or run nanovg.
+
+#include <stddef.h>
+#include <stdlib.h>
+
+typedef struct NVGheadless NVGheadless;
+
+enum NVGheadlessBackend {
+	NVG_HEADLESS_GL2,
+	NVG_HEADLESS_GL3,
+	NVG_HEADLESS_GLES2,
+	NVG_HEADLESS_GLES3,
+};
+
+#if defined(_WIN32)
+
+NVGheadless* nvgShimCreateHeadless(int backend, int width, int height) { return NULL; }
+int nvgShimHeadlessMakeCurrent(NVGheadless* headless) { return 0; }
+void* nvgShimHeadlessGetProcAddress(NVGheadless* headless, const char* name) { return NULL; }
+void nvgShimDestroyHeadless(NVGheadless* headless) {}
+
+#else
+
+#include <dlfcn.h>
+
+// The few EGL and OSMesa declarations needed, so their headers don't have to be installed.
+typedef void* EGLDisplay;
+typedef void* EGLConfig;
+typedef void* EGLSurface;
+typedef void* EGLContext;
+typedef int EGLint;
+typedef unsigned int EGLenum;
+typedef unsigned int EGLBoolean;
+
+#define EGL_NONE 0x3038
+#define EGL_ALPHA_SIZE 0x3021
+#define EGL_BLUE_SIZE 0x3022
+#define EGL_GREEN_SIZE 0x3023
+#define EGL_RED_SIZE 0x3024
+#define EGL_STENCIL_SIZE 0x3026
+#define EGL_SURFACE_TYPE 0x3033
+#define EGL_RENDERABLE_TYPE 0x3040
+#define EGL_PBUFFER_BIT 0x0001
+#define EGL_OPENGL_ES2_BIT 0x0004
+#define EGL_OPENGL_BIT 0x0008
+#define EGL_OPENGL_ES3_BIT 0x0040
+#define EGL_WIDTH 0x3057
+#define EGL_HEIGHT 0x3056
+#define EGL_OPENGL_ES_API 0x30A0
+#define EGL_OPENGL_API 0x30A2
+#define EGL_CONTEXT_CLIENT_VERSION 0x3098
+#define EGL_CONTEXT_MAJOR_VERSION 0x3098
+#define EGL_CONTEXT_MINOR_VERSION 0x30FB
+#define EGL_CONTEXT_OPENGL_PROFILE_MASK 0x30FD
+#define EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT 0x0001
+#define EGL_PLATFORM_SURFACELESS_MESA 0x31DD
+
+typedef EGLDisplay (*PFNEGLGETDISPLAY)(void* display);
+typedef EGLDisplay (*PFNEGLGETPLATFORMDISPLAYEXT)(EGLenum platform, void* display, const EGLint* attribs);
+typedef EGLBoolean (*PFNEGLINITIALIZE)(EGLDisplay display, EGLint* major, EGLint* minor);
+typedef EGLBoolean (*PFNEGLBINDAPI)(EGLenum api);
+typedef EGLBoolean (*PFNEGLCHOOSECONFIG)(EGLDisplay display, const EGLint* attribs, EGLConfig* configs, EGLint size, EGLint* count);
+typedef EGLSurface (*PFNEGLCREATEPBUFFERSURFACE)(EGLDisplay display, EGLConfig config, const EGLint* attribs);
+typedef EGLContext (*PFNEGLCREATECONTEXT)(EGLDisplay display, EGLConfig config, EGLContext share, const EGLint* attribs);
+typedef EGLBoolean (*PFNEGLMAKECURRENT)(EGLDisplay display, EGLSurface draw, EGLSurface read, EGLContext context);
+typedef EGLBoolean (*PFNEGLDESTROYSURFACE)(EGLDisplay display, EGLSurface surface);
+typedef EGLBoolean (*PFNEGLDESTROYCONTEXT)(EGLDisplay display, EGLContext context);
+typedef void* (*PFNEGLGETPROCADDRESS)(const char* name);
+
+typedef void* OSMesaContext;
+
+#define OSMESA_RGBA 0x1908
+#define OSMESA_FORMAT 0x22
+#define OSMESA_DEPTH_BITS 0x30
+#define OSMESA_STENCIL_BITS 0x31
+#define OSMESA_PROFILE 0x33
+#define OSMESA_CORE_PROFILE 0x34
+#define OSMESA_COMPAT_PROFILE 0x35
+#define OSMESA_CONTEXT_MAJOR_VERSION 0x36
+#define OSMESA_CONTEXT_MINOR_VERSION 0x37
+#define OSMESA_UNSIGNED_BYTE 0x1401
+
+typedef OSMesaContext (*PFNOSMESACREATECONTEXTATTRIBS)(const int* attribs, OSMesaContext share);
+typedef unsigned char (*PFNOSMESAMAKECURRENT)(OSMesaContext context, void* buffer, unsigned int type, int width, int height);
+typedef void (*PFNOSMESADESTROYCONTEXT)(OSMesaContext context);
+typedef void* (*PFNOSMESAGETPROCADDRESS)(const char* name);
+
+struct NVGheadless {
+	void* library;
+	int width;
+	int height;
+
+	EGLDisplay display;
+	EGLSurface surface;
+	EGLContext context;
+	PFNEGLMAKECURRENT eglMakeCurrent;
+	PFNEGLDESTROYSURFACE eglDestroySurface;
+	PFNEGLDESTROYCONTEXT eglDestroyContext;
+	PFNEGLGETPROCADDRESS eglGetProcAddress;
+
+	OSMesaContext osmesa;
+	unsigned char* buffer;
+	PFNOSMESAMAKECURRENT OSMesaMakeCurrent;
+	PFNOSMESADESTROYCONTEXT OSMesaDestroyContext;
+	PFNOSMESAGETPROCADDRESS OSMesaGetProcAddress;
+};
+
+static void* nvg__openLibrary(const char** names)
+{
+	for (; *names != NULL; names++) {
+		void* library = dlopen(*names, RTLD_NOW | RTLD_LOCAL);
+		if (library != NULL)
+			return library;
+	}
+	return NULL;
+}
+
+static int nvg__createEGL(NVGheadless* headless, int backend)
+{
+	static const char* names[] = { "libEGL.so.1", "libEGL.so", NULL };
+	PFNEGLGETDISPLAY getDisplay;
+	PFNEGLGETPLATFORMDISPLAYEXT getPlatformDisplay;
+	PFNEGLINITIALIZE initialize;
+	PFNEGLBINDAPI bindAPI;
+	PFNEGLCHOOSECONFIG chooseConfig;
+	PFNEGLCREATEPBUFFERSURFACE createPbufferSurface;
+	PFNEGLCREATECONTEXT createContext;
+	EGLConfig config = NULL;
+	EGLint count = 0;
+	int gles = backend == NVG_HEADLESS_GLES2 || backend == NVG_HEADLESS_GLES3;
+	EGLint renderable = backend == NVG_HEADLESS_GLES3 ? EGL_OPENGL_ES3_BIT
+		: backend == NVG_HEADLESS_GLES2 ? EGL_OPENGL_ES2_BIT : EGL_OPENGL_BIT;
+	EGLint configAttribs[] = {
+		EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+		EGL_RENDERABLE_TYPE, renderable,
+		EGL_RED_SIZE, 8,
+		EGL_GREEN_SIZE, 8,
+		EGL_BLUE_SIZE, 8,
+		EGL_ALPHA_SIZE, 8,
+		EGL_STENCIL_SIZE, 8,
+		EGL_NONE,
+	};
+	EGLint surfaceAttribs[] = { EGL_WIDTH, headless->width, EGL_HEIGHT, headless->height, EGL_NONE };
+	EGLint gl2Attribs[] = { EGL_NONE };
+	EGLint gl3Attribs[] = {
+		EGL_CONTEXT_MAJOR_VERSION, 3,
+		EGL_CONTEXT_MINOR_VERSION, 2,
+		EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+		EGL_NONE,
+	};
+	EGLint gles2Attribs[] = { EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE };
+	EGLint gles3Attribs[] = { EGL_CONTEXT_CLIENT_VERSION, 3, EGL_NONE };
+	EGLint* contextAttribs = backend == NVG_HEADLESS_GL3 ? gl3Attribs
+		: backend == NVG_HEADLESS_GLES2 ? gles2Attribs
+		: backend == NVG_HEADLESS_GLES3 ? gles3Attribs : gl2Attribs;
+
+	headless->library = nvg__openLibrary(names);
+	if (headless->library == NULL)
+		return 0;
+	getDisplay = (PFNEGLGETDISPLAY)dlsym(headless->library, "eglGetDisplay");
+	initialize = (PFNEGLINITIALIZE)dlsym(headless->library, "eglInitialize");
+	bindAPI = (PFNEGLBINDAPI)dlsym(headless->library, "eglBindAPI");
+	chooseConfig = (PFNEGLCHOOSECONFIG)dlsym(headless->library, "eglChooseConfig");
+	createPbufferSurface = (PFNEGLCREATEPBUFFERSURFACE)dlsym(headless->library, "eglCreatePbufferSurface");
+	createContext = (PFNEGLCREATECONTEXT)dlsym(headless->library, "eglCreateContext");
+	headless->eglMakeCurrent = (PFNEGLMAKECURRENT)dlsym(headless->library, "eglMakeCurrent");
+	headless->eglDestroySurface = (PFNEGLDESTROYSURFACE)dlsym(headless->library, "eglDestroySurface");
+	headless->eglDestroyContext = (PFNEGLDESTROYCONTEXT)dlsym(headless->library, "eglDestroyContext");
+	headless->eglGetProcAddress = (PFNEGLGETPROCADDRESS)dlsym(headless->library, "eglGetProcAddress");
+	if (getDisplay == NULL || initialize == NULL || bindAPI == NULL || chooseConfig == NULL
+		|| createPbufferSurface == NULL || createContext == NULL || headless->eglMakeCurrent == NULL
+		|| headless->eglDestroySurface == NULL || headless->eglDestroyContext == NULL
+		|| headless->eglGetProcAddress == NULL)
+		return 0;
+
+	// Prefer the surfaceless platform, which doesn't need a display server.
+	getPlatformDisplay = (PFNEGLGETPLATFORMDISPLAYEXT)headless->eglGetProcAddress("eglGetPlatformDisplayEXT");
+	if (getPlatformDisplay != NULL)
+		headless->display = getPlatformDisplay(EGL_PLATFORM_SURFACELESS_MESA, NULL, NULL);
+	if (headless->display == NULL || !initialize(headless->display, NULL, NULL)) {
+		headless->display = getDisplay(NULL);
+		if (headless->display == NULL || !initialize(headless->display, NULL, NULL))
+			return 0;
+	}
+
+	if (!bindAPI(gles ? EGL_OPENGL_ES_API : EGL_OPENGL_API))
+		return 0;
+	if (!chooseConfig(headless->display, configAttribs, &config, 1, &count) || count < 1)
+		return 0;
+	headless->surface = createPbufferSurface(headless->display, config, surfaceAttribs);
+	if (headless->surface == NULL)
+		return 0;
+	headless->context = createContext(headless->display, config, NULL, contextAttribs);
+	if (headless->context == NULL)
+		return 0;
+	return headless->eglMakeCurrent(headless->display, headless->surface, headless->surface, headless->context);
+}
+
+static int nvg__createOSMesa(NVGheadless* headless, int backend)
+{
+	static const char* names[] = { "libOSMesa.so.8", "libOSMesa.so.6", "libOSMesa.so", NULL };
+	PFNOSMESACREATECONTEXTATTRIBS createContext;
+	int gl3 = backend == NVG_HEADLESS_GL3;
+	int attribs[] = {
+		OSMESA_FORMAT, OSMESA_RGBA,
+		OSMESA_DEPTH_BITS, 0,
+		OSMESA_STENCIL_BITS, 8,
+		OSMESA_PROFILE, gl3 ? OSMESA_CORE_PROFILE : OSMESA_COMPAT_PROFILE,
+		OSMESA_CONTEXT_MAJOR_VERSION, gl3 ? 3 : 2,
+		OSMESA_CONTEXT_MINOR_VERSION, gl3 ? 2 : 0,
+		0,
+	};
+
+	// OSMesa only provides desktop OpenGL.
+	if (backend != NVG_HEADLESS_GL2 && backend != NVG_HEADLESS_GL3)
+		return 0;
+	headless->library = nvg__openLibrary(names);
+	if (headless->library == NULL)
+		return 0;
+	createContext = (PFNOSMESACREATECONTEXTATTRIBS)dlsym(headless->library, "OSMesaCreateContextAttribs");
+	headless->OSMesaMakeCurrent = (PFNOSMESAMAKECURRENT)dlsym(headless->library, "OSMesaMakeCurrent");
+	headless->OSMesaDestroyContext = (PFNOSMESADESTROYCONTEXT)dlsym(headless->library, "OSMesaDestroyContext");
+	headless->OSMesaGetProcAddress = (PFNOSMESAGETPROCADDRESS)dlsym(headless->library, "OSMesaGetProcAddress");
+	if (createContext == NULL || headless->OSMesaMakeCurrent == NULL || headless->OSMesaDestroyContext == NULL
+		|| headless->OSMesaGetProcAddress == NULL)
+		return 0;
+
+	headless->osmesa = createContext(attribs, NULL);
+	if (headless->osmesa == NULL)
+		return 0;
+	headless->buffer = (unsigned char*)malloc((size_t)headless->width * headless->height * 4);
+	if (headless->buffer == NULL)
+		return 0;
+	return headless->OSMesaMakeCurrent(headless->osmesa, headless->buffer, OSMESA_UNSIGNED_BYTE,
+		headless->width, headless->height);
+}
+
+static void nvg__destroyHeadless(NVGheadless* headless)
+{
+	if (headless->context != NULL) {
+		headless->eglMakeCurrent(headless->display, NULL, NULL, NULL);
+		headless->eglDestroyContext(headless->display, headless->context);
+	}
+	// The display is shared by every user of EGL in the process, so it isn't terminated.
+	if (headless->surface != NULL)
+		headless->eglDestroySurface(headless->display, headless->surface);
+	if (headless->osmesa != NULL)
+		headless->OSMesaDestroyContext(headless->osmesa);
+	free(headless->buffer);
+	if (headless->library != NULL)
+		dlclose(headless->library);
+	free(headless);
+}
+
+static NVGheadless* nvg__allocHeadless(int width, int height)
+{
+	NVGheadless* headless = (NVGheadless*)calloc(1, sizeof(NVGheadless));
+	if (headless != NULL) {
+		headless->width = width;
+		headless->height = height;
+	}
+	return headless;
+}
+
+// Create a headless OpenGL context for one of the NVG_HEADLESS_* backends and make it current,
+// trying EGL first and OSMesa second. The default framebuffer is width x height pixels with a
+// stencil buffer. Returns NULL if neither is available.
+NVGheadless* nvgShimCreateHeadless(int backend, int width, int height)
+{
+	NVGheadless* headless;
+	if (width <= 0 || height <= 0)
+		return NULL;
+
+	headless = nvg__allocHeadless(width, height);
+	if (headless == NULL)
+		return NULL;
+	if (nvg__createEGL(headless, backend))
+		return headless;
+	nvg__destroyHeadless(headless);
+
+	headless = nvg__allocHeadless(width, height);
+	if (headless == NULL)
+		return NULL;
+	if (nvg__createOSMesa(headless, backend))
+		return headless;
+	nvg__destroyHeadless(headless);
+	return NULL;
+}
+
+int nvgShimHeadlessMakeCurrent(NVGheadless* headless)
+{
+	if (headless->context != NULL)
+		return headless->eglMakeCurrent(headless->display, headless->surface, headless->surface, headless->context);
+	return headless->OSMesaMakeCurrent(headless->osmesa, headless->buffer, OSMESA_UNSIGNED_BYTE,
+		headless->width, headless->height);
+}
+
+void* nvgShimHeadlessGetProcAddress(NVGheadless* headless, const char* name)
+{
+	void* function = headless->context != NULL
+		? headless->eglGetProcAddress(name)
+		: headless->OSMesaGetProcAddress(name);
+	if (function == NULL)
+		function = dlsym(headless->library, name);
+	return function;
+}
+
+void nvgShimDestroyHeadless(NVGheadless* headless)
+{
+	nvg__destroyHeadless(headless);
+}
+
+#endif
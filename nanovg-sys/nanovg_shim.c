@@ -1,14 +1,64 @@
-#if defined(__APPLE__)
-#include <OpenGL/gl.h>
-#include <OpenGL/gl3.h>
-#include <OpenGL/glu.h>
-#include <OpenGL/glext.h>
-#include <GLUT/glut.h>
-#elif defined(_WIN32)
-#include <glad.h>
-#else
-#include <GL/gl.h>
-#endif
-
-#include <nanovg.h>
-#include <nanovg_gl.h>
\ No newline at end of file
+#include "nanovg_gl_headers.h"
+#include <nanovg_gl_utils.h>
+
+#define NVG_SHIM_LOAD_MISSING(name) \
+	if (glad_##name == NULL) \
+		*(void**)&glad_##name = load(#name)
+
+// Load the OpenGL functions through load, or through glad's own loader if load is NULL.
+// Returns 0 if no OpenGL context is current.
+int nvgShimLoadGL(GLADloadproc load)
+{
+	if (load == NULL)
+		return gladLoadGL();
+	if (!gladLoadGLLoader(load))
+		return 0;
+
+	// Framebuffer objects and mipmap generation are core in OpenGL ES 2.0, but glad only loads
+	// them from desktop OpenGL 3.0 on, so they are missing on ES 2.0 contexts.
+	NVG_SHIM_LOAD_MISSING(glGenerateMipmap);
+	NVG_SHIM_LOAD_MISSING(glGenFramebuffers);
+	NVG_SHIM_LOAD_MISSING(glBindFramebuffer);
+	NVG_SHIM_LOAD_MISSING(glDeleteFramebuffers);
+	NVG_SHIM_LOAD_MISSING(glFramebufferTexture2D);
+	NVG_SHIM_LOAD_MISSING(glFramebufferRenderbuffer);
+	NVG_SHIM_LOAD_MISSING(glCheckFramebufferStatus);
+	NVG_SHIM_LOAD_MISSING(glGenRenderbuffers);
+	NVG_SHIM_LOAD_MISSING(glBindRenderbuffer);
+	NVG_SHIM_LOAD_MISSING(glDeleteRenderbuffers);
+	NVG_SHIM_LOAD_MISSING(glRenderbufferStorage);
+	return 1;
+}
+
+// Bind a framebuffer created by nvgluCreateFramebuffer and clear it.
+// Saves the current viewport and framebuffer binding to state, which must hold 5 ints.
+void nvgShimBeginFramebuffer(NVGLUframebuffer* fb, int width, int height, int* state)
+{
+	glGetIntegerv(GL_VIEWPORT, state);
+	glGetIntegerv(GL_FRAMEBUFFER_BINDING, state + 4);
+	glBindFramebuffer(GL_FRAMEBUFFER, fb->fbo);
+	glViewport(0, 0, width, height);
+	glClearColor(0, 0, 0, 0);
+	glClear(GL_COLOR_BUFFER_BIT | GL_STENCIL_BUFFER_BIT);
+}
+
+// Restore the viewport and framebuffer binding saved by nvgShimBeginFramebuffer.
+void nvgShimEndFramebuffer(const int* state)
+{
+	glBindFramebuffer(GL_FRAMEBUFFER, state[4]);
+	glViewport(state[0], state[1], state[2], state[3]);
+}
+
+// Read the bottom left width x height pixels of a framebuffer as RGBA, or of the bound framebuffer if fb is NULL.
+void nvgShimReadPixels(NVGLUframebuffer* fb, int width, int height, unsigned char* data)
+{
+	GLint previous = 0;
+	if (fb != NULL) {
+		glGetIntegerv(GL_FRAMEBUFFER_BINDING, &previous);
+		glBindFramebuffer(GL_FRAMEBUFFER, fb->fbo);
+	}
+	glPixelStorei(GL_PACK_ALIGNMENT, 1);
+	glReadPixels(0, 0, width, height, GL_RGBA, GL_UNSIGNED_BYTE, data);
+	if (fb != NULL)
+		glBindFramebuffer(GL_FRAMEBUFFER, previous);
+}
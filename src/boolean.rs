@@ -0,0 +1,489 @@
+//! Boolean operations combining the filled areas of paths.
+
+use std::collections::HashMap;
+
+use path_data::polygon_area;
+use {PathData, Solidity, Winding};
+
+type Point = (f64, f64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+impl PathData {
+    /// The area filled by either path.
+    ///
+    /// Paths are filled like NanoVG fills them, with every subpath closed and turned to its winding.
+    /// Curves are flattened to within `tolerance`, so the result only has lines, with each subpath
+    /// marked solid or hole to be filled with `Path::append` or [PathData::fill].
+    pub fn union(&self, other: &PathData, tolerance: f32) -> PathData {
+        combine(self, other, Operation::Union, tolerance)
+    }
+
+    /// The area filled by both paths, see [PathData::union].
+    pub fn intersection(&self, other: &PathData, tolerance: f32) -> PathData {
+        combine(self, other, Operation::Intersection, tolerance)
+    }
+
+    /// The area filled by this path but not by `other`, see [PathData::union].
+    pub fn difference(&self, other: &PathData, tolerance: f32) -> PathData {
+        combine(self, other, Operation::Difference, tolerance)
+    }
+
+    /// The area filled by exactly one of the paths, see [PathData::union].
+    pub fn xor(&self, other: &PathData, tolerance: f32) -> PathData {
+        combine(self, other, Operation::Xor, tolerance)
+    }
+}
+
+/// The windings a piece of an edge adds left of it, for each of the two paths.
+type Windings = [i32; 2];
+
+/// Pieces of edges between two vertices, keyed by their vertices in increasing order,
+/// with the windings they add left of them going in that order.
+type Pieces = HashMap<(usize, usize), Windings>;
+
+/// The edges of the closed polygons of a flattened path, with the winding they add once NanoVG turned them,
+/// for the path `operand` of the two.
+fn edges(path: &PathData, tolerance: f32, operand: usize) -> Vec<(Point, Point, Windings)> {
+    let mut edges = Vec::new();
+    for (polyline, hole) in path.flatten_subpaths(tolerance) {
+        let points = &polyline.points;
+        if points.len() < 3 {
+            continue;
+        }
+        let mut windings = [0, 0];
+        windings[operand] = if (polygon_area(points) > 0.0) == hole { -1 } else { 1 };
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            edges.push(((x1 as f64, y1 as f64), (x2 as f64, y2 as f64), windings));
+        }
+    }
+    edges
+}
+
+fn add_piece(pieces: &mut Pieces, from: usize, to: usize, windings: Windings) {
+    if from == to {
+        return;
+    }
+    let (key, sign) = if from < to { ((from, to), 1) } else { ((to, from), -1) };
+    let entry = pieces.entry(key).or_insert([0, 0]);
+    entry[0] += sign * windings[0];
+    entry[1] += sign * windings[1];
+}
+
+/// The windings on the left of every piece, from the pieces crossing a ray from its middle.
+///
+/// As pieces only meet at vertices, none but the piece itself goes through the start of its ray, so the result
+/// holds however close the other pieces are. Rays go up from pieces that aren't vertical and right from those
+/// that are, sweeping along the other axis to only test the pieces overlapping each ray. That is `n log n`
+/// for `n` pieces plus the number of pieces overlapping the rays, which is up to `n²` for pieces stacked
+/// along a ray but much less for the outlines of shapes and strokes.
+fn left_windings(pieces: &[((usize, usize), Windings)], points: &[Point]) -> Vec<Windings> {
+    let mut left = vec![[0, 0]; pieces.len()];
+    for axis in 0..2 {
+        let ends = |i: usize| {
+            let (from, to) = pieces[i].0;
+            (points[from], points[to])
+        };
+        let along = |point: Point| if axis == 0 { point.0 } else { point.1 };
+        let low = |i: usize| {
+            let (p, q) = ends(i);
+            along(p).min(along(q))
+        };
+        let high = |i: usize| {
+            let (p, q) = ends(i);
+            along(p).max(along(q))
+        };
+        let direction = if axis == 0 { (0.0, 1.0) } else { (1.0, 0.0) };
+        let mut rays: Vec<usize> = (0..pieces.len())
+            .filter(|&i| {
+                let (p, q) = ends(i);
+                (p.0 == q.0) == (axis == 1)
+            })
+            .collect();
+        rays.sort_by(|&i, &j| (low(i) + high(i)).partial_cmp(&(low(j) + high(j))).unwrap());
+        let mut order: Vec<usize> = (0..pieces.len()).collect();
+        order.sort_by(|&i, &j| low(i).partial_cmp(&low(j)).unwrap());
+
+        let mut next = 0;
+        let mut active: Vec<usize> = Vec::new();
+        for &i in &rays {
+            let (p, q) = ends(i);
+            let (x, y) = ((p.0 + q.0) * 0.5, (p.1 + q.1) * 0.5);
+            let at = along((x, y));
+            while next < order.len() && low(order[next]) <= at {
+                active.push(order[next]);
+                next += 1;
+            }
+            active.retain(|&j| high(j) >= at);
+
+            let mut windings = [0, 0];
+            for &j in &active {
+                if j == i {
+                    continue;
+                }
+                let ((x1, y1), (x2, y2)) = ends(j);
+                let side = (x2 - x1) * (y - y1) - (x - x1) * (y2 - y1);
+                // Heights above the ray.
+                let h1 = direction.0 * (y1 - y) - direction.1 * (x1 - x);
+                let h2 = direction.0 * (y2 - y) - direction.1 * (x2 - x);
+                let sign = if h1 <= 0.0 && h2 > 0.0 && side > 0.0 {
+                    1
+                } else if h1 > 0.0 && h2 <= 0.0 && side < 0.0 {
+                    -1
+                } else {
+                    continue;
+                };
+                windings[0] += sign * pieces[j].1[0];
+                windings[1] += sign * pieces[j].1[1];
+            }
+            // A ray on the right of the piece misses what it adds to the left.
+            if (q.0 - p.0) * direction.1 - (q.1 - p.1) * direction.0 < 0.0 {
+                windings[0] += pieces[i].1[0];
+                windings[1] += pieces[i].1[1];
+            }
+            left[i] = windings;
+        }
+    }
+    left
+}
+
+/// Points closer than a tolerance are merged into one vertex, so edges split at the same intersection connect.
+struct Vertices {
+    points: Vec<Point>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    tolerance: f64,
+}
+
+impl Vertices {
+    fn index(&mut self, (x, y): Point) -> usize {
+        let cell = ((x / self.tolerance).floor() as i64, (y / self.tolerance).floor() as i64);
+        for dx in -1..2 {
+            for dy in -1..2 {
+                if let Some(indices) = self.cells.get(&(cell.0 + dx, cell.1 + dy)) {
+                    for &index in indices {
+                        let (px, py) = self.points[index];
+                        if (px - x).abs() <= self.tolerance && (py - y).abs() <= self.tolerance {
+                            return index;
+                        }
+                    }
+                }
+            }
+        }
+        self.points.push((x, y));
+        self.cells.entry(cell).or_default().push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+}
+
+/// Combine the areas of two paths: split all edges where they cross, keep the pieces between filled
+/// and empty parts of the result, and chain them into subpaths.
+fn combine(a: &PathData, b: &PathData, operation: Operation, tolerance: f32) -> PathData {
+    let edges: Vec<(Point, Point, Windings)> = edges(a, tolerance, 0)
+        .into_iter()
+        .chain(edges(b, tolerance, 1))
+        .collect();
+    let scale = edges
+        .iter()
+        .map(|&((x1, y1), (x2, y2), _)| x1.abs().max(y1.abs()).max(x2.abs()).max(y2.abs()))
+        .fold(1.0, f64::max);
+    let filled = |windings: Windings| {
+        let (in_a, in_b) = (windings[0] != 0, windings[1] != 0);
+        match operation {
+            Operation::Union => in_a || in_b,
+            Operation::Intersection => in_a && in_b,
+            Operation::Difference => in_a && !in_b,
+            Operation::Xor => in_a != in_b,
+        }
+    };
+
+    // Parameters along every edge where it has to be split, sweeping the edges from left to right
+    // to only test those that overlap horizontally.
+    let mut splits: Vec<Vec<f64>> = vec![vec![0.0, 1.0]; edges.len()];
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    let left = |i: usize| edges[i].0 .0.min(edges[i].1 .0);
+    order.sort_by(|&i, &j| left(i).partial_cmp(&left(j)).unwrap());
+    for (k, &i) in order.iter().enumerate() {
+        let (p, q, _) = edges[i];
+        let right = p.0.max(q.0) + scale * 1e-7;
+        for &j in &order[k + 1..] {
+            if left(j) > right {
+                break;
+            }
+            let (r, s, _) = edges[j];
+            if let Some((ti, tj)) = intersections((p, q), (r, s)) {
+                splits[i].extend(ti);
+                splits[j].extend(tj);
+            }
+        }
+    }
+
+    let mut vertices = Vertices {
+        points: Vec::new(),
+        cells: HashMap::new(),
+        tolerance: scale * 1e-7,
+    };
+    let mut pieces = Pieces::new();
+    for (&(p, q, windings), splits) in edges.iter().zip(&mut splits) {
+        splits.sort_by(|s, t| s.partial_cmp(t).unwrap());
+        let indices: Vec<usize> = splits
+            .iter()
+            .map(|&t| vertices.index((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t)))
+            .collect();
+        for pair in indices.windows(2) {
+            add_piece(&mut pieces, pair[0], pair[1], windings);
+        }
+    }
+    // Where more than two edges cross at about the same point, their intersections can be on other pieces.
+    let points = vertices.points;
+    let mut pieces: Vec<((usize, usize), Windings)> = split_at_vertices(pieces, &points, scale * 1e-7)
+        .into_iter()
+        .filter(|&(_, windings)| windings != [0, 0])
+        .collect();
+    pieces.sort_unstable_by_key(|&(key, _)| key);
+
+    // Keep the pieces with the result filled on one side only, directed with the filled side on their left.
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (&((from, to), piece), left) in pieces.iter().zip(left_windings(&pieces, &points)) {
+        let right = [left[0] - piece[0], left[1] - piece[1]];
+        match (filled(left), filled(right)) {
+            (true, false) => outgoing.entry(from).or_default().push(to),
+            (false, true) => outgoing.entry(to).or_default().push(from),
+            _ => {}
+        }
+    }
+
+    let mut result = PathData::new();
+    let mut starts: Vec<usize> = outgoing.keys().cloned().collect();
+    starts.sort_unstable();
+    for start in starts {
+        while let Some(next) = outgoing.get_mut(&start).and_then(|targets| targets.pop()) {
+            let mut chain = vec![points[start]];
+            let mut current = next;
+            let mut previous = start;
+            while current != start {
+                chain.push(points[current]);
+                let targets = match outgoing.get_mut(&current) {
+                    Some(targets) if !targets.is_empty() => targets,
+                    _ => break,
+                };
+                // Turn left as sharply as possible, so loops touching at a vertex stay apart.
+                let (from, at) = (points[previous], points[current]);
+                let incoming = (at.0 - from.0, at.1 - from.1);
+                let turn = |target: usize| {
+                    let to = points[target];
+                    let outgoing = (to.0 - at.0, to.1 - at.1);
+                    let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+                    let dot = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+                    cross.atan2(dot)
+                };
+                let (index, _) = targets
+                    .iter()
+                    .enumerate()
+                    .max_by(|&(_, &s), &(_, &t)| turn(s).partial_cmp(&turn(t)).unwrap())
+                    .unwrap();
+                previous = current;
+                current = targets.swap_remove(index);
+            }
+            add_loop(&mut result, &chain);
+        }
+    }
+    result
+}
+
+/// Split pieces wherever a vertex is within `tolerance` of them, so the pieces only meet at vertices.
+fn split_at_vertices(pieces: Pieces, points: &[Point], tolerance: f64) -> Pieces {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| points[a].0.partial_cmp(&points[b].0).unwrap());
+    let xs: Vec<f64> = order.iter().map(|&i| points[i].0).collect();
+    let mut stack: Vec<(usize, usize, Windings)> = pieces.into_iter().map(|((from, to), w)| (from, to, w)).collect();
+    let mut result = Pieces::new();
+    while let Some((from, to, windings)) = stack.pop() {
+        let (p, q) = (points[from], points[to]);
+        let (d, length) = ((q.0 - p.0, q.1 - p.1), (q.0 - p.0).hypot(q.1 - p.1));
+        let first = xs.partition_point(|&x| x < p.0.min(q.0) - tolerance);
+        let split = order[first..]
+            .iter()
+            .take_while(|&&i| points[i].0 <= p.0.max(q.0) + tolerance)
+            .find(|&&i| {
+                let (vx, vy) = (points[i].0 - p.0, points[i].1 - p.1);
+                let along = (vx * d.0 + vy * d.1) / length;
+                let across = (vx * d.1 - vy * d.0).abs() / length;
+                i != from && i != to && along > tolerance && along < length - tolerance && across <= tolerance
+            });
+        match split {
+            Some(&i) => {
+                stack.push((from, i, windings));
+                stack.push((i, to, windings));
+            }
+            None => add_piece(&mut result, from, to, windings),
+        }
+    }
+    result
+}
+
+/// The parameters along `a` and along `b` where they cross or touch, including the ends of overlapping parts.
+fn intersections((p, q): (Point, Point), (r, s): (Point, Point)) -> Option<(Vec<f64>, Vec<f64>)> {
+    // Reject edges whose boxes don't overlap.
+    if p.0.max(q.0) < r.0.min(s.0)
+        || r.0.max(s.0) < p.0.min(q.0)
+        || p.1.max(q.1) < r.1.min(s.1)
+        || r.1.max(s.1) < p.1.min(q.1)
+    {
+        return None;
+    }
+    let d1 = (q.0 - p.0, q.1 - p.1);
+    let d2 = (s.0 - r.0, s.1 - r.1);
+    let cross = d1.0 * d2.1 - d1.1 * d2.0;
+    let (ex, ey) = (r.0 - p.0, r.1 - p.1);
+    let lengths = (d1.0 * d1.0 + d1.1 * d1.1) * (d2.0 * d2.0 + d2.1 * d2.1);
+    if cross * cross > 1e-20 * lengths {
+        let t = (ex * d2.1 - ey * d2.0) / cross;
+        let u = (ex * d1.1 - ey * d1.0) / cross;
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            return Some((vec![t], vec![u]));
+        }
+        return None;
+    }
+
+    // Parallel edges only meet if they are on the same line.
+    let length1 = d1.0 * d1.0 + d1.1 * d1.1;
+    let distance = (ex * d1.1 - ey * d1.0).abs() / length1.sqrt();
+    if distance > 1e-9 * length1.sqrt().max(1.0) {
+        return None;
+    }
+    let along = |point: Point, origin: Point, direction: Point, length: f64| {
+        ((point.0 - origin.0) * direction.0 + (point.1 - origin.1) * direction.1) / length
+    };
+    let length2 = d2.0 * d2.0 + d2.1 * d2.1;
+    let on_a = [along(r, p, d1, length1), along(s, p, d1, length1)];
+    let on_b = [along(p, r, d2, length2), along(q, r, d2, length2)];
+    Some((
+        on_a.iter().cloned().filter(|t| (0.0..=1.0).contains(t)).collect(),
+        on_b.iter().cloned().filter(|t| (0.0..=1.0).contains(t)).collect(),
+    ))
+}
+
+/// Add a closed loop without its straight vertices, solid if it turns with the filled area on its left.
+fn add_loop(path: &mut PathData, points: &[Point]) {
+    let count = points.len();
+    let points: Vec<(f32, f32)> = (0..count)
+        .filter(|&i| {
+            let (a, b, c) = (points[(i + count - 1) % count], points[i], points[(i + 1) % count]);
+            let cross = (b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0);
+            let dot = (b.0 - a.0) * (c.0 - b.0) + (b.1 - a.1) * (c.1 - b.1);
+            cross.abs() > 1e-12 * (a.0 - c.0).hypot(a.1 - c.1).powi(2) || dot < 0.0
+        })
+        .map(|i| (points[i].0 as f32, points[i].1 as f32))
+        .collect();
+    if points.len() < 3 {
+        return;
+    }
+    path.move_to(points[0]);
+    for &point in &points[1..] {
+        path.line_to(point);
+    }
+    path.close();
+    let solidity = if polygon_area(&points) > 0.0 {
+        Solidity::Solid
+    } else {
+        Solidity::Hole
+    };
+    path.winding(Winding::Solidity(solidity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {FillRule, PathSegment, Transform};
+
+    fn square((x, y): (f32, f32), size: f32) -> PathData {
+        let mut path = PathData::new();
+        path.rect((x, y), (size, size));
+        path
+    }
+
+    fn area(path: &PathData) -> f32 {
+        path.flatten(0.1)
+            .iter()
+            .map(|polyline| polygon_area(&polyline.points).abs() * 0.5)
+            .zip(path.segments().iter().filter_map(|segment| match *segment {
+                PathSegment::Winding(Winding::Solidity(solidity)) => Some(solidity),
+                _ => None,
+            }))
+            .map(|(area, solidity)| if solidity == Solidity::Hole { -area } else { area })
+            .sum()
+    }
+
+    #[test]
+    fn test_operations() {
+        let (a, b) = (square((0.0, 0.0), 10.0), square((5.0, 5.0), 10.0));
+        assert_eq!(area(&a.union(&b, 0.1)), 175.0);
+        assert_eq!(area(&a.intersection(&b, 0.1)), 25.0);
+        assert_eq!(area(&a.difference(&b, 0.1)), 75.0);
+        assert_eq!(area(&a.xor(&b, 0.1)), 150.0);
+
+        // Cutting a hole out of the middle.
+        let cutout = a.difference(&square((3.0, 3.0), 4.0), 0.1);
+        assert_eq!(area(&cutout), 84.0);
+        let identity = Transform::new();
+        assert!(!cutout.contains_point((5.0, 5.0), FillRule::NonZero, &identity));
+        assert!(cutout.contains_point((1.0, 5.0), FillRule::NonZero, &identity));
+
+        // Squares sharing an edge merge into one rectangle, and touching corners stay apart.
+        let merged = a.union(&square((10.0, 0.0), 10.0), 0.1);
+        assert_eq!(merged.segments().len(), 6);
+        let corners = a.union(&square((10.0, 10.0), 10.0), 0.1);
+        assert_eq!(corners.flatten(0.1).len(), 2);
+        assert_eq!(area(&corners), 200.0);
+    }
+
+    #[test]
+    fn test_overlapping_edges() {
+        let a = square((0.0, 0.0), 10.0);
+        // Coincident edges add up or cancel out.
+        assert_eq!(area(&a.union(&a, 0.1)), 100.0);
+        assert_eq!(area(&a.intersection(&a, 0.1)), 100.0);
+        assert!(a.xor(&a, 0.1).segments().is_empty());
+
+        // Overlapping pieces, like those of a stroke, with edges on the same lines and slivers between them.
+        let mut pieces = PathData::new();
+        for i in 0..10 {
+            let offset = i as f32 * 1e-5;
+            pieces.rect((offset, offset), (10.0, 10.0));
+            pieces.rect((10.0 - offset, 0.0), (5.0, 10.0 - offset));
+        }
+        let merged = pieces.union(&PathData::new(), 0.1);
+        let identity = Transform::new();
+        assert_eq!(merged.flatten(0.1).len(), 1);
+        assert!((area(&merged) - 150.0).abs() < 0.01);
+        for &point in &[
+            (0.5, 0.5),
+            (9.9999, 5.0),
+            (10.0, 5.0),
+            (10.0001, 9.9999),
+            (14.9, 0.0001),
+        ] {
+            assert!(merged.contains_point(point, FillRule::NonZero, &identity));
+        }
+    }
+
+    #[test]
+    fn test_curves() {
+        let mut circle = PathData::new();
+        circle.circle((0.0, 0.0), 10.0);
+        let half = circle.intersection(&square((0.0, -20.0), 40.0), 0.01);
+        let expected = ::std::f32::consts::PI * 50.0;
+        assert!((area(&half) - expected).abs() < 0.5);
+        assert!(!half
+            .segments()
+            .iter()
+            .any(|segment| matches!(segment, PathSegment::CubicBezierTo { .. })));
+    }
+}
@@ -0,0 +1,289 @@
+//! Dashing paths into the separate pieces a dashed stroke draws, as NanoVG only strokes solid lines.
+
+use measure::{subpaths, Shape, Subpath};
+use path_data::DIST_TOL;
+use PathData;
+
+impl PathData {
+    /// Split the path into the dashes a stroke with `dash_array` and `dash_offset` draws, see
+    /// [StrokeOptions::dash_array](::StrokeOptions::dash_array).
+    ///
+    /// Like in SVG, the pattern starts over at every subpath, and a dash keeps going through the corners
+    /// and curves of its subpath, so it is joined rather than capped there. Dashes of closed subpaths also
+    /// continue through the point where they close, and a subpath that is not interrupted stays closed.
+    /// Curves stay curves, split at the ends of the dashes. Zero-length dashes become dots, a short line
+    /// along the path that only shows with round or square caps.
+    ///
+    /// Returns a copy of the path if `dash_array` is empty, has a negative length or only zeros.
+    /// Like in Skia, subpaths that would get more than a million dashes are left solid.
+    pub fn dashed(&self, dash_array: &[f32], dash_offset: f32) -> PathData {
+        let pattern = match pattern(dash_array) {
+            Some(pattern) => pattern,
+            None => return self.clone(),
+        };
+        let offset = if dash_offset.is_finite() { dash_offset } else { 0.0 };
+        let mut dashed = PathData::new();
+        for subpath in subpaths(self) {
+            dash_subpath(&mut dashed, &subpath, &pattern, offset);
+        }
+        dashed
+    }
+}
+
+/// The dashes and gaps to repeat, an even number of lengths to alternate between.
+fn pattern(dash_array: &[f32]) -> Option<Vec<f32>> {
+    let total: f32 = dash_array.iter().sum();
+    if dash_array.iter().any(|&length| length < 0.0) || !(total > 0.0 && total.is_finite()) {
+        return None;
+    }
+    let mut pattern = dash_array.to_vec();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_slice(dash_array);
+    }
+    Some(pattern)
+}
+
+/// The most dashes to split a subpath into, as Skia does, before drawing it solid instead.
+const MAX_DASHES: f32 = 1_000_000.0;
+
+/// A dash along a subpath, or a dot where `shapes` is empty.
+struct Dash {
+    start: (f32, f32),
+    direction: (f32, f32),
+    shapes: Vec<Shape>,
+}
+
+fn dash_subpath(dashed: &mut PathData, subpath: &Subpath, pattern: &[f32], offset: f32) {
+    let segments = &subpath.segments;
+    let total: f32 = pattern.iter().sum();
+    if subpath.length() / total * pattern.len() as f32 > MAX_DASHES {
+        add_dash(
+            dashed,
+            &Dash {
+                start: segments[0].point(0.0),
+                direction: segments[0].direction(0.0),
+                shapes: segments.iter().map(|segment| segment.shape).collect(),
+            },
+        );
+        if subpath.closed {
+            dashed.close();
+        }
+        return;
+    }
+
+    // Find where in the pattern the subpath starts.
+    let mut index = 0;
+    let mut position = offset.rem_euclid(total);
+    while position >= pattern[index] && position > 0.0 {
+        position -= pattern[index];
+        index = (index + 1) % pattern.len();
+    }
+    let mut remaining = pattern[index] - position;
+
+    let mut dashes: Vec<Dash> = Vec::new();
+    let starts_on = index % 2 == 0;
+    if starts_on {
+        dashes.push(Dash {
+            start: segments[0].point(0.0),
+            direction: segments[0].direction(0.0),
+            shapes: Vec::new(),
+        });
+    }
+    for segment in segments {
+        let length = segment.length();
+        let mut along = 0.0;
+        let mut stalled = 0;
+        loop {
+            let step = remaining.min(length - along);
+            // Dashes shorter than the precision of `along` don't move it, give up on the segment
+            // if a whole pattern doesn't.
+            if step > 0.0 && along + step == along {
+                stalled += 1;
+                if stalled > pattern.len() {
+                    break;
+                }
+            } else {
+                stalled = 0;
+            }
+            if index % 2 == 0 && step > 0.0 {
+                let (from, to) = (segment.parameter(along), segment.parameter(along + step));
+                dashes.last_mut().unwrap().shapes.push(segment.shape.part(from, to));
+            }
+            along += step;
+            remaining -= step;
+            if remaining > 0.0 {
+                break;
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+            if index % 2 == 0 {
+                let t = segment.parameter(along);
+                dashes.push(Dash {
+                    start: segment.point(t),
+                    direction: segment.direction(t),
+                    shapes: Vec::new(),
+                });
+            }
+        }
+    }
+    let ends_on = index % 2 == 0;
+
+    if subpath.closed && starts_on && ends_on {
+        if dashes.len() == 1 {
+            // Nothing interrupted the subpath.
+            let dash = dashes.pop().unwrap();
+            add_dash(dashed, &dash);
+            dashed.close();
+            return;
+        }
+        // The last dash goes on into the first one.
+        let first = dashes.remove(0);
+        dashes.last_mut().unwrap().shapes.extend(first.shapes);
+    } else if ends_on && dashes.last().map(|dash| dash.shapes.is_empty()) == Some(true) && remaining > 0.0 {
+        // A dash starting right at the end of an open subpath has nothing to draw.
+        dashes.pop();
+    }
+    for dash in &dashes {
+        add_dash(dashed, dash);
+    }
+}
+
+fn add_dash(dashed: &mut PathData, dash: &Dash) {
+    dashed.move_to(dash.start);
+    if dash.shapes.is_empty() {
+        // Long enough for NanoVG to keep both points.
+        let length = DIST_TOL * 2.0;
+        dashed.line_to((
+            dash.start.0 + dash.direction.0 * length,
+            dash.start.1 + dash.direction.1 * length,
+        ));
+    }
+    for shape in &dash.shapes {
+        shape.add_to(dashed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use path_data::distance;
+    use {PathSegment, Solidity, StrokeOptions, Transform, Winding};
+
+    fn dashes(path: &PathData) -> Vec<Vec<PathSegment>> {
+        let mut dashes: Vec<Vec<PathSegment>> = Vec::new();
+        for &segment in path.segments() {
+            if let PathSegment::MoveTo(_) = segment {
+                dashes.push(Vec::new());
+            }
+            dashes.last_mut().unwrap().push(segment);
+        }
+        dashes
+    }
+
+    #[test]
+    fn test_dash_lines() {
+        let mut corner = PathData::new();
+        corner.move_to((0.0, 0.0));
+        corner.line_to((10.0, 0.0));
+        corner.line_to((10.0, 10.0));
+        assert_eq!(
+            dashes(&corner.dashed(&[4.0, 2.0], 3.0)),
+            vec![
+                vec![PathSegment::MoveTo((0.0, 0.0)), PathSegment::LineTo((1.0, 0.0))],
+                vec![PathSegment::MoveTo((3.0, 0.0)), PathSegment::LineTo((7.0, 0.0))],
+                // Joined around the corner.
+                vec![
+                    PathSegment::MoveTo((9.0, 0.0)),
+                    PathSegment::LineTo((10.0, 0.0)),
+                    PathSegment::LineTo((10.0, 3.0)),
+                ],
+                vec![PathSegment::MoveTo((10.0, 5.0)), PathSegment::LineTo((10.0, 9.0))],
+            ]
+        );
+
+        // An odd pattern is repeated, and dots are short lines along the path.
+        let dots = corner.dashed(&[0.0, 5.0, 10.0], 0.0);
+        assert_eq!(
+            &dots.segments()[..2],
+            &[PathSegment::MoveTo((0.0, 0.0)), PathSegment::LineTo((0.02, 0.0))]
+        );
+        assert_eq!(dashes(&dots).len(), 3);
+
+        let options = StrokeOptions {
+            dash_array: vec![4.0, 2.0],
+            dash_offset: 3.0,
+            ..Default::default()
+        };
+        assert!(corner.stroke_contains_point((0.5, 0.0), &options, &Transform::new()));
+        assert!(!corner.stroke_contains_point((2.0, 0.0), &options, &Transform::new()));
+
+        assert_eq!(corner.dashed(&[], 0.0), corner);
+        assert_eq!(corner.dashed(&[0.0, 0.0], 0.0), corner);
+        assert_eq!(corner.dashed(&[1.0, -1.0], 0.0), corner);
+    }
+
+    #[test]
+    fn test_dash_closed() {
+        let mut square = PathData::new();
+        square.rect((0.0, 0.0), (10.0, 10.0));
+        square.winding(Winding::Solidity(Solidity::Hole));
+
+        // The dash over the start continues into the first one.
+        assert_eq!(
+            dashes(&square.dashed(&[12.0, 8.0], 4.0)),
+            vec![
+                vec![
+                    PathSegment::MoveTo((6.0, 10.0)),
+                    PathSegment::LineTo((10.0, 10.0)),
+                    PathSegment::LineTo((10.0, 2.0)),
+                ],
+                vec![
+                    PathSegment::MoveTo((4.0, 0.0)),
+                    PathSegment::LineTo((0.0, 0.0)),
+                    PathSegment::LineTo((0.0, 8.0)),
+                ],
+            ]
+        );
+        let solid = square.dashed(&[50.0, 10.0], 0.0);
+        assert_eq!(dashes(&solid).len(), 1);
+        assert_eq!(solid.segments().last(), Some(&PathSegment::Close));
+    }
+
+    #[test]
+    fn test_dash_curves() {
+        let mut circle = PathData::new();
+        circle.circle((0.0, 0.0), 10.0);
+        let length = 2.0 * ::std::f32::consts::PI * 10.0;
+        let dashed = circle.dashed(&[length / 8.0], 0.0);
+        let dashes = dashes(&dashed);
+        assert_eq!(dashes.len(), 4);
+        for dash in dashes {
+            let start = match dash[0] {
+                PathSegment::MoveTo(point) => point,
+                other => panic!("expected a move, got {:?}", other),
+            };
+            let end = match *dash.last().unwrap() {
+                PathSegment::CubicBezierTo { point, .. } => point,
+                other => panic!("expected a curve, got {:?}", other),
+            };
+            // Every dash is an eighth of the circle, which bezier curves approximate to within 0.03%.
+            assert!((distance(start, (0.0, 0.0)) - 10.0).abs() < 1e-3);
+            assert!((distance(start, end) - 2.0 * 10.0 * (::std::f32::consts::PI / 8.0).sin()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_dash_too_fine() {
+        let mut line = PathData::new();
+        line.move_to((0.0, 0.0));
+        line.line_to((1000.0, 0.0));
+        assert_eq!(line.dashed(&[1e-5, 1e-5], 0.0), line);
+
+        // Far along the line, the short dashes are smaller than the precision of their position.
+        let mut long = PathData::new();
+        long.move_to((0.0, 0.0));
+        long.line_to((1e5, 0.0));
+        let dashed = long.dashed(&[1e-5, 1e-5, 1000.0, 1000.0], 0.0);
+        assert_eq!(dashes(&dashed).len(), 100);
+    }
+}
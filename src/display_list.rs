@@ -0,0 +1,444 @@
+//! Display lists record drawing commands without a context, so they can be built on any thread.
+
+use std::ffi::{CString, NulError};
+
+use {
+    Color, Context, FillOptions, Font, Frame, Gradient, Paint, PathData, PathOptions, PathSegment, StrokeOptions,
+    TextOptions, Transform, Winding,
+};
+
+/// The paint of a fill or stroke in a [DisplayList].
+/// Image patterns refer to images of a context, so they can't be recorded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayPaint {
+    Color(Color),
+    Gradient(Gradient),
+}
+
+impl From<Color> for DisplayPaint {
+    fn from(color: Color) -> Self {
+        DisplayPaint::Color(color)
+    }
+}
+
+impl From<Gradient> for DisplayPaint {
+    fn from(gradient: Gradient) -> Self {
+        DisplayPaint::Gradient(gradient)
+    }
+}
+
+impl Paint for DisplayPaint {
+    fn fill(&self, context: &Context) {
+        match *self {
+            DisplayPaint::Color(ref color) => color.fill(context),
+            DisplayPaint::Gradient(ref gradient) => gradient.fill(context),
+        }
+    }
+
+    fn stroke(&self, context: &Context) {
+        match *self {
+            DisplayPaint::Color(ref color) => color.stroke(context),
+            DisplayPaint::Gradient(ref gradient) => gradient.stroke(context),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum PathCommand {
+    Fill(DisplayPaint, FillOptions),
+    Stroke(DisplayPaint, StrokeOptions),
+    Arc((f32, f32), f32, f32, f32, Winding),
+    Rect((f32, f32), (f32, f32)),
+    RoundedRect((f32, f32), (f32, f32), f32),
+    RoundedRectVarying((f32, f32), (f32, f32), (f32, f32), (f32, f32)),
+    Ellipse((f32, f32), f32, f32),
+    Circle((f32, f32), f32),
+    LineTo((f32, f32)),
+    CubicBezierTo((f32, f32), (f32, f32), (f32, f32)),
+    QuadBezierTo((f32, f32), (f32, f32)),
+    ArcTo((f32, f32), (f32, f32), f32),
+    Winding(Winding),
+    MoveTo((f32, f32)),
+    Close,
+}
+
+#[derive(Clone, Debug)]
+enum Command {
+    Path {
+        transform: Transform,
+        commands: Vec<PathCommand>,
+        options: PathOptions,
+    },
+    Text {
+        transform: Transform,
+        font: CString,
+        position: (f32, f32),
+        text: CString,
+        options: TextOptions,
+        boxed: bool,
+    },
+}
+
+/// A list of drawing commands mirroring the [Frame] and [Path](::Path) API, replayed into a frame later.
+///
+/// Display lists don't need a context, and are `Send`, so heavy geometry can be prepared on worker threads
+/// and drawn on the thread owning the context with [DisplayList::replay].
+/// Fonts are referred to by name and looked up when replaying.
+#[derive(Clone, Debug)]
+pub struct DisplayList {
+    commands: Vec<Command>,
+    transform: Transform,
+}
+
+impl DisplayList {
+    /// Create an empty display list.
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            transform: Transform::new(),
+        }
+    }
+
+    /// Whether nothing has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Remove all recorded commands.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Get the transform which recorded commands are transformed by, see [Frame::transform].
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// Record the commands of `handler` transformed by `transform`, see [Frame::transformed].
+    pub fn transformed<F: FnOnce(&mut DisplayList)>(&mut self, transform: Transform, handler: F) {
+        let previous = self.transform;
+        self.transform = transform * previous;
+        handler(self);
+        self.transform = previous;
+    }
+
+    /// Record a new path, see [Frame::path].
+    pub fn path<F: FnOnce(&mut DisplayPath)>(&mut self, handler: F, options: PathOptions) {
+        let mut path = DisplayPath { commands: Vec::new() };
+        handler(&mut path);
+        self.commands.push(Command::Path {
+            transform: self.transform,
+            commands: path.commands,
+            options,
+        });
+    }
+
+    /// Record a single line of text with the font called `font`, see [Frame::text].
+    ///
+    /// Fails without recording anything if `font` or `text` contain a nul character.
+    pub fn text<S: AsRef<str>, T: AsRef<str>>(
+        &mut self,
+        font: S,
+        position: (f32, f32),
+        text: T,
+        options: TextOptions,
+    ) -> Result<(), NulError> {
+        self.push_text(font.as_ref(), position, text.as_ref(), options, false)
+    }
+
+    /// Record multiline text with the font called `font`, see [Frame::text_box].
+    ///
+    /// Fails without recording anything if `font` or `text` contain a nul character.
+    pub fn text_box<S: AsRef<str>, T: AsRef<str>>(
+        &mut self,
+        font: S,
+        position: (f32, f32),
+        text: T,
+        options: TextOptions,
+    ) -> Result<(), NulError> {
+        self.push_text(font.as_ref(), position, text.as_ref(), options, true)
+    }
+
+    fn push_text(
+        &mut self,
+        font: &str,
+        position: (f32, f32),
+        text: &str,
+        options: TextOptions,
+        boxed: bool,
+    ) -> Result<(), NulError> {
+        self.commands.push(Command::Text {
+            transform: self.transform,
+            font: CString::new(font)?,
+            position,
+            text: CString::new(text)?,
+            options,
+            boxed,
+        });
+        Ok(())
+    }
+
+    /// Move the commands of `other` to the end of this list, transformed by the current transform.
+    pub fn append(&mut self, other: DisplayList) {
+        let current = self.transform;
+        self.commands.extend(other.commands.into_iter().map(|mut command| {
+            match command {
+                Command::Path { ref mut transform, .. } | Command::Text { ref mut transform, .. } => {
+                    *transform = *transform * current;
+                }
+            }
+            command
+        }));
+    }
+
+    /// Draw the recorded commands into `frame`, relative to its transform.
+    /// Text with a font that isn't loaded into the frame's context is skipped.
+    pub fn replay(&self, frame: &Frame) {
+        for command in &self.commands {
+            match *command {
+                Command::Path {
+                    transform,
+                    ref commands,
+                    options,
+                } => {
+                    let frame = Frame::new(frame.context(), transform * *frame.transform());
+                    frame.path(
+                        |path| {
+                            for command in commands {
+                                match *command {
+                                    PathCommand::Fill(paint, options) => path.fill(paint, options),
+                                    PathCommand::Stroke(paint, ref options) => path.stroke(paint, options.clone()),
+                                    PathCommand::Arc(center, radius, start, end, winding) => {
+                                        path.arc(center, radius, start, end, winding)
+                                    }
+                                    PathCommand::Rect(position, size) => path.rect(position, size),
+                                    PathCommand::RoundedRect(position, size, radius) => {
+                                        path.rounded_rect(position, size, radius)
+                                    }
+                                    PathCommand::RoundedRectVarying(position, size, top_radii, bottom_radii) => {
+                                        path.rounded_rect_varying(position, size, top_radii, bottom_radii)
+                                    }
+                                    PathCommand::Ellipse(center, radius_x, radius_y) => {
+                                        path.ellipse(center, radius_x, radius_y)
+                                    }
+                                    PathCommand::Circle(center, radius) => path.circle(center, radius),
+                                    PathCommand::LineTo(point) => path.line_to(point),
+                                    PathCommand::CubicBezierTo(point, control1, control2) => {
+                                        path.cubic_bezier_to(point, control1, control2)
+                                    }
+                                    PathCommand::QuadBezierTo(point, control) => path.quad_bezier_to(point, control),
+                                    PathCommand::ArcTo(p1, p2, radius) => path.arc_to(p1, p2, radius),
+                                    PathCommand::Winding(winding) => path.winding(winding),
+                                    PathCommand::MoveTo(point) => path.move_to(point),
+                                    PathCommand::Close => path.close(),
+                                }
+                            }
+                        },
+                        options,
+                    );
+                }
+                Command::Text {
+                    transform,
+                    ref font,
+                    position,
+                    ref text,
+                    options,
+                    boxed,
+                } => {
+                    // Both were created from a `&str` by `push_text`, so they are valid UTF-8.
+                    let font = font.to_str().expect("font name is valid UTF-8");
+                    let text = text.to_str().expect("text is valid UTF-8");
+                    let font = match Font::find(frame.context(), font) {
+                        Ok(font) => font,
+                        Err(_) => continue,
+                    };
+                    let frame = Frame::new(frame.context(), transform * *frame.transform());
+                    if boxed {
+                        frame.text_box(font, position, text, options);
+                    } else {
+                        frame.text(font, position, text, options);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for DisplayList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A path recorded into a [DisplayList], mirroring [Path](::Path).
+#[derive(Debug)]
+pub struct DisplayPath {
+    commands: Vec<PathCommand>,
+}
+
+impl DisplayPath {
+    /// Fill the path recorded so far, see [Path::fill](::Path::fill).
+    pub fn fill<T: Into<DisplayPaint>>(&mut self, paint: T, options: FillOptions) {
+        self.commands.push(PathCommand::Fill(paint.into(), options));
+    }
+
+    /// Stroke the path recorded so far, see [Path::stroke](::Path::stroke).
+    pub fn stroke<T: Into<DisplayPaint>>(&mut self, paint: T, options: StrokeOptions) {
+        self.commands.push(PathCommand::Stroke(paint.into(), options));
+    }
+
+    /// Add an arc to the path.
+    pub fn arc(&mut self, center: (f32, f32), radius: f32, start_angle: f32, end_angle: f32, winding: Winding) {
+        self.commands
+            .push(PathCommand::Arc(center, radius, start_angle, end_angle, winding));
+    }
+
+    /// Add a rectangle to the path.
+    pub fn rect(&mut self, position: (f32, f32), size: (f32, f32)) {
+        self.commands.push(PathCommand::Rect(position, size));
+    }
+
+    /// Add a rounded rectangle to the path.
+    pub fn rounded_rect(&mut self, position: (f32, f32), size: (f32, f32), radius: f32) {
+        self.commands.push(PathCommand::RoundedRect(position, size, radius));
+    }
+
+    /// Add a rounded rectangle with varying corners to the path.
+    /// `top_radii` and `bottom_radii` are both tuples in the form (left, right).
+    pub fn rounded_rect_varying(
+        &mut self,
+        position: (f32, f32),
+        size: (f32, f32),
+        top_radii: (f32, f32),
+        bottom_radii: (f32, f32),
+    ) {
+        self.commands
+            .push(PathCommand::RoundedRectVarying(position, size, top_radii, bottom_radii));
+    }
+
+    /// Add an ellipse to the path.
+    pub fn ellipse(&mut self, center: (f32, f32), radius_x: f32, radius_y: f32) {
+        self.commands.push(PathCommand::Ellipse(center, radius_x, radius_y));
+    }
+
+    /// Add a circle to the path.
+    pub fn circle(&mut self, center: (f32, f32), radius: f32) {
+        self.commands.push(PathCommand::Circle(center, radius));
+    }
+
+    /// Add a line to the subpath.
+    pub fn line_to(&mut self, point: (f32, f32)) {
+        self.commands.push(PathCommand::LineTo(point));
+    }
+
+    /// Add a cubic bezier curve to the subpath.
+    pub fn cubic_bezier_to(&mut self, point: (f32, f32), control1: (f32, f32), control2: (f32, f32)) {
+        self.commands
+            .push(PathCommand::CubicBezierTo(point, control1, control2));
+    }
+
+    /// Add a quadratic bezier curve to the subpath.
+    pub fn quad_bezier_to(&mut self, point: (f32, f32), control: (f32, f32)) {
+        self.commands.push(PathCommand::QuadBezierTo(point, control));
+    }
+
+    /// Add a arc to the subpath.
+    pub fn arc_to(&mut self, p1: (f32, f32), p2: (f32, f32), radius: f32) {
+        self.commands.push(PathCommand::ArcTo(p1, p2, radius));
+    }
+
+    /// Set the winding of the subpath.
+    pub fn winding(&mut self, winding: Winding) {
+        self.commands.push(PathCommand::Winding(winding));
+    }
+
+    /// Start new sub-path with specified coordinates as the first point.
+    pub fn move_to(&mut self, point: (f32, f32)) {
+        self.commands.push(PathCommand::MoveTo(point));
+    }
+
+    /// Close the path, ie. connect the first point and last point with a line.
+    pub fn close(&mut self) {
+        self.commands.push(PathCommand::Close);
+    }
+
+    /// Add the segments of retained path data to the path, see [Path::append](::Path::append).
+    pub fn append(&mut self, data: &PathData) {
+        for segment in data.segments() {
+            self.commands.push(match *segment {
+                PathSegment::MoveTo(point) => PathCommand::MoveTo(point),
+                PathSegment::LineTo(point) => PathCommand::LineTo(point),
+                PathSegment::CubicBezierTo {
+                    point,
+                    control1,
+                    control2,
+                } => PathCommand::CubicBezierTo(point, control1, control2),
+                PathSegment::Winding(winding) => PathCommand::Winding(winding),
+                PathSegment::Close => PathCommand::Close,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use renderer::RecordingRenderer;
+    use std::thread;
+    use {ContextBuilder, Direction};
+
+    fn build() -> DisplayList {
+        let mut list = DisplayList::new();
+        list.transformed(Transform::new().with_translation(10.0, 0.0), |list| {
+            list.path(
+                |path| {
+                    path.rect((0.0, 0.0), (20.0, 20.0));
+                    path.winding(Winding::Direction(Direction::Clockwise));
+                    path.fill(Color::new(1.0, 0.0, 0.0, 1.0), FillOptions::default());
+                    path.stroke(Color::new(0.0, 0.0, 1.0, 1.0), StrokeOptions::default());
+                },
+                PathOptions::default(),
+            );
+        });
+        list
+    }
+
+    #[test]
+    fn test_build_on_thread() {
+        let list = thread::spawn(build).join().unwrap();
+        assert_eq!(list.commands.len(), 1);
+        assert_eq!(list.transform(), &Transform::new());
+
+        let mut outer = DisplayList::new();
+        outer.transformed(Transform::new().with_translation(0.0, 5.0), |outer| outer.append(list));
+        match outer.commands[0] {
+            Command::Path { transform, .. } => assert_eq!(transform.matrix, [1.0, 0.0, 0.0, 1.0, 10.0, 5.0]),
+            _ => panic!("expected a path"),
+        }
+    }
+
+    #[test]
+    fn test_text_rejects_nul() {
+        let mut list = DisplayList::new();
+        assert!(list.text("sans", (0.0, 0.0), "a\0b", TextOptions::default()).is_err());
+        assert!(list
+            .text_box("sa\0ns", (0.0, 0.0), "ab", TextOptions::default())
+            .is_err());
+        assert!(list.commands.is_empty());
+        assert!(list.text("sans", (0.0, 0.0), "ab", TextOptions::default()).is_ok());
+        assert_eq!(list.commands.len(), 1);
+    }
+
+    #[test]
+    fn test_context_replays_list() {
+        let list = build();
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new())
+            .unwrap();
+        context.frame((100.0, 100.0), 1.0, |frame| list.replay(&frame));
+
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        let frame = recorder.last_frame().unwrap();
+        assert_eq!(frame.calls.len(), 2);
+    }
+}
@@ -1,54 +1,236 @@
 extern crate nanovg_sys as ffi;
 
-use std::ffi::{CString, NulError};
-use std::ops::Drop;
-use std::os::raw::{c_char, c_float, c_int, c_uchar};
+use std::cell::{Cell, RefCell, RefMut};
+use std::ffi::{CStr, CString, NulError};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Drop, Range};
+use std::os::raw::{c_char, c_float, c_int, c_uchar, c_void};
 use std::path::Path as IoPath;
 use std::{mem, ptr};
 
-#[cfg(target_os = "windows")]
+mod boolean;
+mod dash;
+mod display_list;
+mod measure;
+mod path_data;
+mod png;
+pub mod renderer;
+pub mod svg;
+
+pub use display_list::{DisplayList, DisplayPaint, DisplayPath};
+pub use measure::PathMeasure;
+pub use path_data::{Bounds, FillRule, PathData, PathSegment, Polyline};
+use renderer::{CustomRenderer, RenderShape, RenderText, Renderer, SoftwareRenderer};
+
+thread_local! {
+    /// The loader passed to [ContextBuilder::gl_loader], while glad calls into it.
+    static GL_LOADER: Cell<*mut c_void> = const { Cell::new(ptr::null_mut()) };
+}
+
+unsafe extern "C" fn load_gl_function<F: FnMut(&str) -> *const c_void>(name: *const c_char) -> *mut c_void {
+    let loader = GL_LOADER.with(|loader| loader.get()) as *mut F;
+    match (loader.as_mut(), CStr::from_ptr(name).to_str()) {
+        (Some(loader), Ok(name)) => loader(name) as *mut c_void,
+        _ => ptr::null_mut(),
+    }
+}
+
+fn load_gl<F: FnMut(&str) -> *const c_void>(mut loader: F) -> bool {
+    GL_LOADER.with(|current| current.set(&mut loader as *mut F as *mut c_void));
+    let loaded = unsafe { ffi::nvgShimLoadGL(Some(load_gl_function::<F>)) } == 1;
+    GL_LOADER.with(|current| current.set(ptr::null_mut()));
+    loaded
+}
+
 fn init_gl() -> Result<(), ()> {
-    if unsafe { ffi::gladLoadGL() } == 1 {
+    if unsafe { ffi::nvgShimLoadGL(None) } == 1 {
         Ok(())
     } else {
         Err(())
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-fn init_gl() -> Result<(), ()> {
-    Ok(())
+/// An OpenGL context without a window, created by [ContextBuilder::build_headless].
+#[derive(Debug)]
+struct Headless {
+    raw: *mut ffi::NVGheadless,
 }
 
-#[cfg(feature = "gl3")]
-fn create_gl(flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
-    unsafe { ffi::nvgCreateGL3(flags.bits()) }
+impl Headless {
+    fn new(backend: Backend, width: usize, height: usize) -> Option<Headless> {
+        let backend = match backend {
+            Backend::Gl2 => ffi::NVG_HEADLESS_GL2,
+            Backend::Gl3 => ffi::NVG_HEADLESS_GL3,
+            Backend::Gles2 => ffi::NVG_HEADLESS_GLES2,
+            Backend::Gles3 => ffi::NVG_HEADLESS_GLES3,
+        };
+        let raw = unsafe { ffi::nvgShimCreateHeadless(backend, width as c_int, height as c_int) };
+        if raw.is_null() {
+            None
+        } else {
+            Some(Headless { raw })
+        }
+    }
+
+    fn proc_address(&self, name: &str) -> *const c_void {
+        match CString::new(name) {
+            Ok(name) => unsafe { ffi::nvgShimHeadlessGetProcAddress(self.raw, name.as_ptr()) },
+            Err(_) => ptr::null(),
+        }
+    }
+
+    fn make_current(&self) -> bool {
+        unsafe { ffi::nvgShimHeadlessMakeCurrent(self.raw) == 1 }
+    }
 }
 
-#[cfg(feature = "gl2")]
-fn create_gl(flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
-    unsafe { ffi::nvgCreateGL2(flags.bits()) }
+impl Drop for Headless {
+    fn drop(&mut self) {
+        unsafe { ffi::nvgShimDestroyHeadless(self.raw) }
+    }
 }
 
-#[cfg(feature = "gles3")]
-fn create_gl(flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
-    unsafe { ffi::nvgCreateGLES3(flags.bits()) }
+/// The OpenGL flavour a context renders with.
+///
+/// All backends are compiled in, so a program can fall back to another one if creating a context fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// OpenGL 2.0
+    Gl2,
+    /// OpenGL 3.2 core profile
+    Gl3,
+    /// OpenGL ES 2.0
+    Gles2,
+    /// OpenGL ES 3.0
+    Gles3,
+}
+
+impl Default for Backend {
+    /// The backend selected by the `gl2`, `gl3`, `gles2` and `gles3` features, `Gl3` if none is enabled.
+    fn default() -> Self {
+        if cfg!(feature = "gl3") {
+            Backend::Gl3
+        } else if cfg!(feature = "gl2") {
+            Backend::Gl2
+        } else if cfg!(feature = "gles3") {
+            Backend::Gles3
+        } else if cfg!(feature = "gles2") {
+            Backend::Gles2
+        } else {
+            Backend::Gl3
+        }
+    }
 }
 
-#[cfg(feature = "gles2")]
-fn create_gl(flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
-    unsafe { ffi::nvgCreateGLES2(flags.bits()) }
+fn delete_gl2(ctx: *mut ffi::NVGcontext) {
+    unsafe { ffi::nvgDeleteGL2(ctx) }
 }
 
-#[cfg(not(any(feature = "gl3", feature = "gl2", feature = "gles3", feature = "gles2")))]
-fn create_gl(flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
-    panic!("Unable to determine the backend / implementation. Have you enabled one of the features?")
+fn delete_gl3(ctx: *mut ffi::NVGcontext) {
+    unsafe { ffi::nvgDeleteGL3(ctx) }
+}
+
+fn delete_gles2(ctx: *mut ffi::NVGcontext) {
+    unsafe { ffi::nvgDeleteGLES2(ctx) }
+}
+
+fn delete_gles3(ctx: *mut ffi::NVGcontext) {
+    unsafe { ffi::nvgDeleteGLES3(ctx) }
+}
+
+impl Backend {
+    fn create(self, flags: ffi::NVGcreateFlags) -> *mut ffi::NVGcontext {
+        unsafe {
+            match self {
+                Backend::Gl2 => ffi::nvgCreateGL2(flags.bits()),
+                Backend::Gl3 => ffi::nvgCreateGL3(flags.bits()),
+                Backend::Gles2 => ffi::nvgCreateGLES2(flags.bits()),
+                Backend::Gles3 => ffi::nvgCreateGLES3(flags.bits()),
+            }
+        }
+    }
+
+    fn delete_fn(self) -> fn(*mut ffi::NVGcontext) {
+        match self {
+            Backend::Gl2 => delete_gl2,
+            Backend::Gl3 => delete_gl3,
+            Backend::Gles2 => delete_gles2,
+            Backend::Gles3 => delete_gles3,
+        }
+    }
+
+    fn create_framebuffer(
+        self,
+        ctx: *mut ffi::NVGcontext,
+        width: c_int,
+        height: c_int,
+        flags: c_int,
+    ) -> *mut ffi::NVGLUframebuffer {
+        unsafe {
+            match self {
+                Backend::Gl2 => ffi::nvgluCreateFramebufferGL2(ctx, width, height, flags),
+                Backend::Gl3 => ffi::nvgluCreateFramebufferGL3(ctx, width, height, flags),
+                Backend::Gles2 => ffi::nvgluCreateFramebufferGLES2(ctx, width, height, flags),
+                Backend::Gles3 => ffi::nvgluCreateFramebufferGLES3(ctx, width, height, flags),
+            }
+        }
+    }
+
+    fn create_image_from_texture(
+        self,
+        ctx: *mut ffi::NVGcontext,
+        texture: u32,
+        width: c_int,
+        height: c_int,
+        flags: c_int,
+    ) -> c_int {
+        unsafe {
+            match self {
+                Backend::Gl2 => ffi::nvglCreateImageFromHandleGL2(ctx, texture, width, height, flags),
+                Backend::Gl3 => ffi::nvglCreateImageFromHandleGL3(ctx, texture, width, height, flags),
+                Backend::Gles2 => ffi::nvglCreateImageFromHandleGLES2(ctx, texture, width, height, flags),
+                Backend::Gles3 => ffi::nvglCreateImageFromHandleGLES3(ctx, texture, width, height, flags),
+            }
+        }
+    }
+
+    fn image_texture(self, ctx: *mut ffi::NVGcontext, image: c_int) -> u32 {
+        unsafe {
+            match self {
+                Backend::Gl2 => ffi::nvglImageHandleGL2(ctx, image),
+                Backend::Gl3 => ffi::nvglImageHandleGL3(ctx, image),
+                Backend::Gles2 => ffi::nvglImageHandleGLES2(ctx, image),
+                Backend::Gles3 => ffi::nvglImageHandleGLES3(ctx, image),
+            }
+        }
+    }
+
+    fn delete_framebuffer(self, framebuffer: *mut ffi::NVGLUframebuffer) {
+        unsafe {
+            match self {
+                Backend::Gl2 => ffi::nvgluDeleteFramebufferGL2(framebuffer),
+                Backend::Gl3 => ffi::nvgluDeleteFramebufferGL3(framebuffer),
+                Backend::Gles2 => ffi::nvgluDeleteFramebufferGLES2(framebuffer),
+                Backend::Gles3 => ffi::nvgluDeleteFramebufferGLES3(framebuffer),
+            }
+        }
+    }
+}
+
+fn delete_internal(ctx: *mut ffi::NVGcontext) {
+    unsafe { ffi::nvgDeleteInternal(ctx) }
 }
 
 /// A builder that configures and constructs a NanoVG context.
 #[derive(Debug)]
 pub struct ContextBuilder {
     flags: ffi::NVGcreateFlags,
+    backend: Backend,
+    /// Whether loading the OpenGL functions through [ContextBuilder::gl_loader] succeeded.
+    gl_loaded: Option<bool>,
 }
 
 impl ContextBuilder {
@@ -56,6 +238,8 @@ impl ContextBuilder {
     pub fn new() -> Self {
         Self {
             flags: ffi::NVGcreateFlags::empty(),
+            backend: Backend::default(),
+            gl_loaded: None,
         }
     }
 
@@ -77,27 +261,131 @@ impl ContextBuilder {
         self
     }
 
+    /// Select the OpenGL backend to render with.
+    /// Defaults to the backend enabled by the crate features, or [Backend::Gl3].
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Load the OpenGL functions with `loader`, which returns the address of a function by name.
+    /// Use the `get_proc_address` of your windowing library, like for `gl::load_with`:
+    ///
+    /// ```ignore
+    /// let context = nanovg::ContextBuilder::new()
+    ///     .gl_loader(|symbol| gl_window.get_proc_address(symbol) as *const _)
+    ///     .build()?;
+    /// ```
+    ///
+    /// The functions are loaded right away, so the OpenGL context has to be current.
+    /// Without a loader, [ContextBuilder::build] looks them up in the system's OpenGL library,
+    /// which won't find the functions of EGL or OpenGL ES contexts on every platform.
+    pub fn gl_loader<F: FnMut(&str) -> *const c_void>(mut self, loader: F) -> Self {
+        self.gl_loaded = Some(load_gl(loader));
+        self
+    }
+
     /// Construct the context.
-    /// Make sure you have enabled one of the 4 OpenGL features, or this function will panic.
+    /// Requires a current OpenGL context matching the selected backend.
     pub fn build(self) -> Result<Context, ()> {
-        init_gl()?;
-        let raw = create_gl(self.flags);
+        match self.gl_loaded {
+            Some(true) => {}
+            Some(false) => return Err(()),
+            None => init_gl()?,
+        }
+        let raw = self.backend.create(self.flags);
         if !raw.is_null() {
-            Ok(Context(raw))
+            Ok(Context {
+                raw,
+                delete: self.backend.delete_fn(),
+                backend: Some(self.backend),
+                renderer: None,
+                last_frame: Cell::new(None),
+                headless: None,
+                path: RefCell::new(PathData::new()),
+            })
         } else {
             Err(())
         }
     }
+
+    /// Construct the context on a new OpenGL context of `width` x `height` pixels without a window.
+    /// This uses EGL (the surfaceless platform, or the default display) or else OSMesa,
+    /// which are loaded at runtime, so it works with software implementations like Mesa's llvmpipe
+    /// on machines without a GPU or display, for example in tests.
+    /// OSMesa only provides [Backend::Gl2] and [Backend::Gl3].
+    ///
+    /// The OpenGL context is made current on this thread and lives as long as the returned context.
+    /// Draw frames of the same size and read them back with [Context::screenshot].
+    pub fn build_headless(self, width: usize, height: usize) -> Result<Context, ()> {
+        let headless = Headless::new(self.backend, width, height).ok_or(())?;
+        let mut context = self.gl_loader(|name| headless.proc_address(name)).build()?;
+        context.headless = Some(headless);
+        Ok(context)
+    }
+
+    /// Construct a context which draws through `renderer` instead of OpenGL.
+    /// Only the `antialias` flag applies to custom renderers.
+    /// The renderer can be accessed again with [Context::renderer].
+    pub fn build_with_renderer<R: Renderer + 'static>(self, renderer: R) -> Result<Context, ()> {
+        let antialias = self.flags.contains(ffi::NVGcreateFlags::NVG_ANTIALIAS);
+        match renderer::create_internal(renderer, antialias) {
+            Some((raw, renderer)) => Ok(Context {
+                raw,
+                delete: delete_internal,
+                backend: None,
+                renderer: Some(renderer),
+                last_frame: Cell::new(None),
+                headless: None,
+                path: RefCell::new(PathData::new()),
+            }),
+            None => Err(()),
+        }
+    }
 }
 
 /// A initialized NanoVG context - the central type which all operations rely on.
 #[derive(Debug)]
-pub struct Context(*mut ffi::NVGcontext);
+pub struct Context {
+    raw: *mut ffi::NVGcontext,
+    delete: fn(*mut ffi::NVGcontext),
+    backend: Option<Backend>,
+    renderer: Option<CustomRenderer>,
+    /// Size and device pixel ratio of the last frame drawn on screen, for screenshots.
+    last_frame: Cell<Option<((f32, f32), f32)>>,
+    /// The OpenGL context of [ContextBuilder::build_headless], dropped after the NanoVG context.
+    headless: Option<Headless>,
+    /// The commands of the current path, to dash strokes with. Reused by every path.
+    path: RefCell<PathData>,
+}
 
 impl Context {
     /// Return the raw FFI C-struct pointer to the context.
     pub fn raw(&self) -> *mut ffi::NVGcontext {
-        self.0
+        self.raw
+    }
+
+    /// The OpenGL backend of the context, `None` if it was built with a custom renderer.
+    pub fn backend(&self) -> Option<Backend> {
+        self.backend
+    }
+
+    /// Make the OpenGL context of a context built with [ContextBuilder::build_headless] current on this thread.
+    /// Returns `false` for other contexts, whose OpenGL context is managed by the caller.
+    pub fn make_headless_current(&self) -> bool {
+        match self.headless {
+            Some(ref headless) => headless.make_current(),
+            None => false,
+        }
+    }
+
+    /// Borrow the renderer this context was built with by [ContextBuilder::build_with_renderer].
+    /// Returns `None` if the context uses a different renderer or the renderer is already borrowed.
+    ///
+    /// NanoVG calls into the renderer while drawing and creating images, and those calls fail while it is
+    /// borrowed: textures aren't created or updated and draw calls are skipped. Drop the borrow before drawing.
+    pub fn renderer<R: Renderer + 'static>(&self) -> Option<RefMut<'_, R>> {
+        self.renderer.and_then(|renderer| renderer.borrow())
     }
 
     /// Begin drawing a frame.
@@ -107,7 +395,27 @@ impl Context {
     /// `device_pixel_ratio` defines the pixel ratio. NanoVG doesn't guess this automatically to allow for Hi-DPI devices.
     /// Basically, this is your hidpi factor.
     /// `handler` is the callback in which you draw your paths. You cannot draw paths outside of this callback.
-    pub fn frame<'a, F: FnOnce(Frame<'a>)>(&'a self, (width, height): (f32, f32), device_pixel_ratio: f32, handler: F) {
+    ///
+    /// If `handler` panics, the frame is cancelled and nothing of it is rendered.
+    pub fn frame<'a, F: FnOnce(Frame<'a>)>(&'a self, size: (f32, f32), device_pixel_ratio: f32, handler: F) {
+        let result: Result<(), ()> = self.try_frame(size, device_pixel_ratio, |frame| {
+            handler(frame);
+            Ok(())
+        });
+        debug_assert!(result.is_ok());
+    }
+
+    /// Begin drawing a frame, like [Context::frame], with a fallible `handler`.
+    ///
+    /// If `handler` returns `Ok`, the frame is rendered. If it returns `Err` or panics,
+    /// the frame is cancelled instead, so the next frame starts from a clean state.
+    /// Returns the result of `handler`.
+    pub fn try_frame<'a, T, E, F: FnOnce(Frame<'a>) -> Result<T, E>>(
+        &'a self,
+        (width, height): (f32, f32),
+        device_pixel_ratio: f32,
+        handler: F,
+    ) -> Result<T, E> {
         unsafe {
             ffi::nvgBeginFrame(
                 self.raw(),
@@ -116,12 +424,46 @@ impl Context {
                 device_pixel_ratio as c_float,
             );
         }
-        {
-            let frame = Frame::new(self, Transform::new());
-            handler(frame);
+        let guard = FrameGuard { context: self };
+        let result = handler(Frame::new(self, Transform::new()));
+        if result.is_ok() {
+            mem::forget(guard);
+            unsafe {
+                ffi::nvgEndFrame(self.raw());
+            }
+            self.last_frame.set(Some(((width, height), device_pixel_ratio)));
+        } else {
+            drop(guard);
         }
-        unsafe {
-            ffi::nvgEndFrame(self.raw());
+        // A custom renderer that panicked while NanoVG called it.
+        renderer::resume_panic();
+        result
+    }
+
+    /// Read back the pixels of the last frame drawn with [Context::frame].
+    ///
+    /// Call this after the frame returned and before swapping buffers. The screenshot covers
+    /// the frame size multiplied by its device pixel ratio, starting at the bottom left corner of
+    /// the framebuffer. Contexts built with a [SoftwareRenderer] return its framebuffer.
+    /// Returns `None` if no frame has been drawn yet, or the context uses another custom renderer.
+    pub fn screenshot(&self) -> Option<Screenshot> {
+        let ((width, height), device_pixel_ratio) = self.last_frame.get()?;
+        if self.renderer.is_some() {
+            return self
+                .renderer::<SoftwareRenderer>()
+                .map(|renderer| renderer.screenshot());
+        }
+        let width = (width * device_pixel_ratio).ceil().max(0.0) as usize;
+        let height = (height * device_pixel_ratio).ceil().max(0.0) as usize;
+        Some(Screenshot::read(ptr::null_mut(), width, height, device_pixel_ratio))
+    }
+
+    /// Pass a font created on the context to its custom renderer, if it has one.
+    fn register_font(&self, font: c_int, name: &str, data: &[u8]) {
+        if let Some(renderer) = self.renderer {
+            renderer.call((), |renderer| renderer.create_font(font as usize, name, data));
+            // Not called from NanoVG, so there is no frame to resume the panic at.
+            renderer::resume_panic();
         }
     }
 
@@ -230,36 +572,22 @@ impl Context {
 }
 
 impl Drop for Context {
-    #[cfg(feature = "gl3")]
-    fn drop(&mut self) {
-        unsafe {
-            ffi::nvgDeleteGL3(self.0);
-        }
-    }
-
-    #[cfg(feature = "gl2")]
     fn drop(&mut self) {
-        unsafe {
-            ffi::nvgDeleteGL2(self.0);
-        }
+        (self.delete)(self.raw);
     }
+}
 
-    #[cfg(feature = "gles3")]
-    fn drop(&mut self) {
-        unsafe {
-            ffi::nvgDeleteGLES3(self.0);
-        }
-    }
+/// Cancels the frame in progress when dropped, unless it is forgotten after ending the frame.
+struct FrameGuard<'a> {
+    context: &'a Context,
+}
 
-    #[cfg(feature = "gles2")]
+impl<'a> Drop for FrameGuard<'a> {
     fn drop(&mut self) {
         unsafe {
-            ffi::nvgDeleteGLES2(self.0);
+            ffi::nvgCancelFrame(self.context.raw());
         }
     }
-
-    #[cfg(not(any(feature = "gl3", feature = "gl2", feature = "gles3", feature = "gles2")))]
-    fn drop(&mut self) {}
 }
 
 /// A scissor defines a region on the screen in which drawing operations are allowed.
@@ -365,6 +693,7 @@ impl<'a> Frame<'a> {
         unsafe {
             ffi::nvgBeginPath(self.context.raw());
         }
+        self.context.path.borrow_mut().clear();
         handler(Path::new(self));
     }
 
@@ -401,14 +730,71 @@ impl<'a> Frame<'a> {
     /// `text` the string to draw.
     /// `options` optional (`Default::default`) options that control the visual appearance of the text.
     pub fn text<S: AsRef<str>>(&self, font: Font, (x, y): (f32, f32), text: S, options: TextOptions) {
-        let text = CString::new(text.as_ref()).unwrap();
         self.text_prepare(font, options);
 
         self.draw_prepare(options.clip, options.transform);
 
+        self.draw_text(font, (x, y), text.as_ref(), options);
+    }
+
+    /// Draw a line of text prepared with `options` by `text_prepare` and `draw_prepare`.
+    /// A custom renderer gets to draw the glyphs from the font instead, see [Renderer::begin_text].
+    fn draw_text(&self, font: Font, (x, y): (f32, f32), text: &str, options: TextOptions) {
+        let renderer = match self.context.renderer {
+            Some(renderer) if self.begin_text(renderer, font, (x, y), text, options) => Some(renderer),
+            _ => None,
+        };
+        let text = CString::new(text).unwrap();
         unsafe {
             ffi::nvgText(self.context.raw(), x, y, text.as_ptr(), ptr::null());
         }
+        if let Some(renderer) = renderer {
+            renderer.call((), |renderer| renderer.end_text());
+        }
+    }
+
+    /// Lay out a line of text like NanoVG does and pass the glyphs to `renderer`.
+    fn begin_text(
+        &self,
+        renderer: CustomRenderer,
+        font: Font,
+        (x, y): (f32, f32),
+        text: &str,
+        options: TextOptions,
+    ) -> bool {
+        let (mut ascender, mut descender) = (0.0, 0.0);
+        unsafe {
+            ffi::nvgTextMetrics(self.context.raw(), &mut ascender, &mut descender, ptr::null_mut());
+        }
+        // The glyph positions of NanoVG only account for the horizontal alignment.
+        let align = options.align.into_raw();
+        let baseline = if align.contains(ffi::NVGalign::NVG_ALIGN_TOP) {
+            ascender
+        } else if align.contains(ffi::NVGalign::NVG_ALIGN_MIDDLE) {
+            (ascender + descender) * 0.5
+        } else if align.contains(ffi::NVGalign::NVG_ALIGN_BOTTOM) && !align.contains(ffi::NVGalign::NVG_ALIGN_BASELINE)
+        {
+            descender
+        } else {
+            0.0
+        };
+        // Positions are taken before the letter spacing NanoVG adds in front of every glyph but the first.
+        let glyphs: Vec<_> = self
+            .text_glyph_positions((x, y), text)
+            .enumerate()
+            .map(|(i, glyph)| {
+                let spacing = if i > 0 { options.letter_spacing } else { 0.0 };
+                (&text[glyph.bytes], (glyph.x + spacing, y + baseline))
+            })
+            .collect();
+        let text = RenderText {
+            font: font.id() as usize,
+            size: options.size,
+            blur: options.blur,
+            transform: self.context.current_transform(),
+            glyphs: &glyphs,
+        };
+        renderer.call(false, |renderer| renderer.begin_text(&text))
     }
 
     /// Draw multiline text on the screen.
@@ -417,6 +803,10 @@ impl<'a> Frame<'a> {
     /// `text` the string to draw.
     /// `options` optional (`Default::default`) options that control the visual appearance of the text.
     pub fn text_box<S: AsRef<str>>(&self, font: Font, (x, y): (f32, f32), text: S, options: TextOptions) {
+        if self.context.renderer.is_some() {
+            self.text_box_rows(font, (x, y), text.as_ref(), options);
+            return;
+        }
         let text = CString::new(text.as_ref()).unwrap();
         self.text_prepare(font, options);
 
@@ -434,6 +824,80 @@ impl<'a> Frame<'a> {
         }
     }
 
+    /// Draw multiline text row by row like `nvgTextBox`, so every row goes through `draw_text`.
+    fn text_box_rows(&self, font: Font, (x, mut y): (f32, f32), text: &str, options: TextOptions) {
+        let align = options.align.into_raw();
+        let row_options = TextOptions {
+            align: options.align.left(),
+            ..options
+        };
+        let line_height = self.text_metrics(font, row_options).line_height * options.line_height;
+        let rows: Vec<_> = self
+            .text_break_lines(font, text, options.line_max_width, row_options)
+            .collect();
+        self.draw_prepare(options.clip, options.transform);
+        for row in rows {
+            let row_x = if align.contains(ffi::NVGalign::NVG_ALIGN_CENTER) {
+                x + options.line_max_width * 0.5 - row.width * 0.5
+            } else if align.contains(ffi::NVGalign::NVG_ALIGN_RIGHT) {
+                x + options.line_max_width - row.width
+            } else {
+                x
+            };
+            self.draw_text(font, (row_x, y), row.text, row_options);
+            y += line_height;
+        }
+    }
+
+    /// Draw a single line along a path, like a label following a curve. Newline characters are ignored.
+    /// Each glyph is placed by its advance along the path and rotated to the tangent there, and glyphs
+    /// that fall before the start or past the end of the path are left out.
+    /// `font` the font face to use.
+    /// `path` the path to follow, its subpaths are laid end to end.
+    /// `offset` the distance along the path to draw the text at. The horizontal alignment of `options` sets
+    /// whether the text starts, is centered or ends there, the vertical alignment places it against the path.
+    /// `text` the string to draw.
+    /// `options` optional (`Default::default`) options that control the visual appearance of the text.
+    pub fn text_on_path<S: AsRef<str>>(&self, font: Font, path: &PathData, offset: f32, text: S, options: TextOptions) {
+        let text = text.as_ref();
+        let left = TextOptions {
+            align: options.align.left(),
+            ..options
+        };
+        self.draw_prepare(options.clip, options.transform);
+        let (width, _) = self.text_bounds(font, (0.0, 0.0), text, left);
+        let glyphs: Vec<GlyphPosition> = self.text_glyph_positions((0.0, 0.0), text).collect();
+
+        let start = if options.align.0.contains(ffi::NVGalign::NVG_ALIGN_RIGHT) {
+            offset - width
+        } else if options.align.0.contains(ffi::NVGalign::NVG_ALIGN_CENTER) {
+            offset - width * 0.5
+        } else {
+            offset
+        };
+        let measure = path.measure();
+        let centered = TextOptions {
+            align: options.align.center(),
+            ..options
+        };
+        self.text_prepare(font, centered);
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let advance = glyphs.get(i + 1).map_or(width, |next| next.x) - glyph.x;
+            let distance = start + glyph.x + advance * 0.5;
+            let (point, tangent) = match (measure.point_at(distance), measure.tangent_at(distance)) {
+                (Some(point), Some(tangent)) if distance >= 0.0 && distance <= measure.length() => (point, tangent),
+                _ => continue,
+            };
+            self.draw_prepare(options.clip, options.transform);
+            self.context.transform(Some(
+                Transform::new()
+                    .translate(point.0, point.1)
+                    .rotate(tangent.1.atan2(tangent.0)),
+            ));
+            self.draw_text(font, (0.0, 0.0), &text[glyph.bytes.clone()], centered);
+        }
+    }
+
     /// Measures specified text string.
     /// Returns tuple (f32, TextBounds) where the first element specifies horizontal advance of measured text
     /// and the second element specifies the bounding box of measured text.
@@ -541,6 +1005,10 @@ impl<'a> Frame<'a> {
 }
 
 /// A path, the main type for most NanoVG drawing operations.
+///
+/// Whether the path will get a dashed stroke isn't known while it is built, so besides handing them to NanoVG,
+/// every command is kept in a [PathData] of the context. It is reused by the next path, so drawing doesn't
+/// allocate once it has grown to the largest path.
 #[derive(Debug)]
 pub struct Path<'a, 'b>
 where
@@ -554,6 +1022,11 @@ impl<'a, 'b> Path<'a, 'b> {
         Self { frame }
     }
 
+    /// The commands added so far.
+    fn data(&self) -> RefMut<'_, PathData> {
+        self.frame.context.path.borrow_mut()
+    }
+
     fn ctx(&self) -> *mut ffi::NVGcontext {
         self.frame.context.raw()
     }
@@ -573,8 +1046,10 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgShapeAntiAlias(ctx, options.antialias as c_int);
             paint.fill(self.context());
-            ffi::nvgFill(ctx);
         }
+        self.draw_shape(None, || unsafe {
+            ffi::nvgFill(ctx);
+        });
     }
 
     /// Draw the current path by stroking it's perimeter.
@@ -582,8 +1057,20 @@ impl<'a, 'b> Path<'a, 'b> {
     ///         pass variables that implement Paint trait
     ///         for now these are: Color, Gradient, ImagePattern
     /// 'options' specifies how stroking should be done.
+    ///         dashed strokes draw the dashes of the path instead, see [PathData::dashed].
     pub fn stroke<T: Paint>(&self, paint: T, options: StrokeOptions) {
         let ctx = self.ctx();
+        // NanoVG has no dashes, so the path is replaced by its dashes while stroking, and restored after.
+        let solid = if options.dash_array.is_empty() {
+            None
+        } else {
+            let solid = mem::replace(&mut *self.data(), PathData::new());
+            unsafe {
+                ffi::nvgBeginPath(ctx);
+            }
+            self.append(&solid.dashed(&options.dash_array, options.dash_offset));
+            Some(solid)
+        };
         unsafe {
             ffi::nvgShapeAntiAlias(ctx, options.antialias as c_int);
             ffi::nvgStrokeWidth(ctx, options.width as c_float);
@@ -591,15 +1078,45 @@ impl<'a, 'b> Path<'a, 'b> {
             ffi::nvgLineJoin(ctx, options.line_join.into_raw() as c_int);
             ffi::nvgMiterLimit(ctx, options.miter_limit as c_float);
             paint.stroke(self.context());
+        }
+        self.draw_shape(Some(&options), || unsafe {
             ffi::nvgStroke(ctx);
+        });
+        if let Some(solid) = solid {
+            self.data().clear();
+            unsafe {
+                ffi::nvgBeginPath(ctx);
+            }
+            self.append(&solid);
         }
     }
 
+    /// Fill or stroke the path with `draw`, telling a custom renderer the commands it is drawn from,
+    /// see [Renderer::begin_shape].
+    fn draw_shape<F: FnOnce()>(&self, stroke: Option<&StrokeOptions>, draw: F) {
+        let renderer = match self.frame.context.renderer {
+            Some(renderer) => renderer,
+            None => return draw(),
+        };
+        {
+            let data = self.data();
+            let shape = RenderShape {
+                path: &data,
+                transform: self.frame.context.current_transform(),
+                stroke,
+            };
+            renderer.call((), |renderer| renderer.begin_shape(&shape));
+        }
+        draw();
+        renderer.call((), |renderer| renderer.end_shape());
+    }
+
     /// Add an arc to the path.
     pub fn arc(&self, (cx, cy): (f32, f32), radius: f32, start_angle: f32, end_angle: f32, winding: Winding) {
         unsafe {
             ffi::nvgArc(self.ctx(), cx, cy, radius, start_angle, end_angle, winding.into_raw());
         }
+        self.data().arc((cx, cy), radius, start_angle, end_angle, winding);
     }
 
     /// Add a rectangle to the path.
@@ -607,6 +1124,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgRect(self.ctx(), x as c_float, y as c_float, w as c_float, h as c_float);
         }
+        self.data().rect((x, y), (w, h));
     }
 
     /// Add a rounded rectangle to the path.
@@ -614,6 +1132,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgRoundedRect(self.ctx(), x, y, w, h, radius);
         }
+        self.data().rounded_rect((x, y), (w, h), radius);
     }
 
     /// Add a rounded rectangle with varying corners to the path.
@@ -638,6 +1157,8 @@ impl<'a, 'b> Path<'a, 'b> {
                 bottom_radii.0,
             );
         }
+        self.data()
+            .rounded_rect_varying((x, y), (w, h), top_radii, bottom_radii);
     }
 
     /// Add an ellipse to the path.
@@ -645,6 +1166,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgEllipse(self.ctx(), cx, cy, radius_x, radius_y);
         }
+        self.data().ellipse((cx, cy), radius_x, radius_y);
     }
 
     /// Add a circle to the path.
@@ -652,6 +1174,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgCircle(self.ctx(), cx, cy, radius);
         }
+        self.data().circle((cx, cy), radius);
     }
 
     /// Add a line to the subpath.
@@ -659,6 +1182,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgLineTo(self.ctx(), x, y);
         }
+        self.data().line_to((x, y));
     }
 
     /// Add a cubic bezier curve to the subpath.
@@ -666,6 +1190,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgBezierTo(self.ctx(), control1.0, control1.1, control2.0, control2.1, x, y);
         }
+        self.data().cubic_bezier_to((x, y), control1, control2);
     }
 
     /// Add a quadratic bezier curve to the subpath.
@@ -673,6 +1198,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgQuadTo(self.ctx(), control.0, control.1, x, y);
         }
+        self.data().quad_bezier_to((x, y), control);
     }
 
     /// Add a arc to the subpath.
@@ -680,6 +1206,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgArcTo(self.ctx(), p1.0, p1.1, p2.0, p2.1, radius);
         }
+        self.data().arc_to(p1, p2, radius);
     }
 
     /// Set the winding of the subpath.
@@ -688,6 +1215,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgPathWinding(self.ctx(), winding.into_raw());
         }
+        self.data().winding(winding);
     }
 
     /// Start new sub-path with specified coordinates as the first point.
@@ -695,6 +1223,7 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgMoveTo(self.ctx(), x, y);
         }
+        self.data().move_to((x, y));
     }
 
     /// Close the path, ie. connect the first point and last point with a line.
@@ -702,11 +1231,17 @@ impl<'a, 'b> Path<'a, 'b> {
         unsafe {
             ffi::nvgClosePath(self.ctx());
         }
+        self.data().close();
+    }
+
+    /// Add the segments of retained path data to the path.
+    pub fn append(&self, data: &PathData) {
+        data.add_to(self);
     }
 }
 
 /// Controls how filling in a path should look.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct FillOptions {
     pub antialias: bool,
 }
@@ -718,13 +1253,18 @@ impl Default for FillOptions {
 }
 
 /// Controls how stroking a path should look.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StrokeOptions {
     pub width: f32,
     pub line_cap: LineCap,
     pub line_join: LineJoin,
     pub miter_limit: f32,
     pub antialias: bool,
+    /// Lengths of the dashes and the gaps between them, repeated along every subpath.
+    /// An odd number of lengths is repeated twice. Empty for a solid stroke, see [PathData::dashed].
+    pub dash_array: Vec<f32>,
+    /// How far into `dash_array` every subpath starts.
+    pub dash_offset: f32,
 }
 
 impl Default for StrokeOptions {
@@ -735,6 +1275,8 @@ impl Default for StrokeOptions {
             line_join: LineJoin::Miter,
             miter_limit: 10.0,
             antialias: true,
+            dash_array: Vec::new(),
+            dash_offset: 0.0,
         }
     }
 }
@@ -1138,6 +1680,82 @@ impl<'a> ImageBuilder<'a> {
             Err(ImageBuilderError::CreateImageFailed)
         }
     }
+
+    /// Construct the image by rasterizing an SVG document, stretched to `width` x `height` pixels.
+    /// Pick the size from [svg::Document::size] and the device pixel ratio for sharp icons.
+    ///
+    /// The document is drawn with a [SoftwareRenderer], so this works on any context, even during a frame.
+    pub fn build_from_svg(self, document: &svg::Document, width: usize, height: usize) -> ImageBuilderResult<'a> {
+        let premultiplied = self.flags.contains(ffi::NVGimageFlags::NVG_IMAGE_PREMULTIPLIED);
+        match document.rasterize((width, height), premultiplied) {
+            Some(data) => self.build_from_rgba(width, height, &data),
+            None => Err(ImageBuilderError::CreateImageFailed),
+        }
+    }
+
+    /// Construct the image by rasterizing an SVG file on the file system, see [ImageBuilder::build_from_svg].
+    pub fn build_from_svg_file<P: AsRef<IoPath>>(self, file: P, width: usize, height: usize) -> ImageBuilderResult<'a> {
+        match svg::Document::from_file(file) {
+            Ok(document) => self.build_from_svg(&document, width, height),
+            Err(_) => Err(ImageBuilderError::InvalidSvg),
+        }
+    }
+
+    /// Construct the image by rasterizing an SVG file in memory, see [ImageBuilder::build_from_svg].
+    pub fn build_from_svg_memory(self, data: &[u8], width: usize, height: usize) -> ImageBuilderResult<'a> {
+        match svg::Document::from_bytes(data) {
+            Ok(document) => self.build_from_svg(&document, width, height),
+            Err(_) => Err(ImageBuilderError::InvalidSvg),
+        }
+    }
+
+    /// Construct the image from an existing OpenGL texture, e.g. the output of another renderer.
+    /// Only available on contexts using the GL backend.
+    ///
+    /// The texture stays owned by the caller: it is not deleted together with the image, and must
+    /// outlive it. The texture should hold premultiplied alpha, or be opaque.
+    pub fn build_from_gl_texture(self, texture: u32, width: usize, height: usize) -> ImageBuilderResult<'a> {
+        let backend = match self.context.backend {
+            Some(backend) => backend,
+            None => return Err(ImageBuilderError::GlTextureNotSupported),
+        };
+
+        let flags = self.flags | ffi::NVGimageFlags::NVG_IMAGE_NODELETE;
+        let handle = backend.create_image_from_texture(
+            self.context.raw(),
+            texture,
+            width as c_int,
+            height as c_int,
+            flags.bits(),
+        );
+        if handle > 0 {
+            Ok(Image(self.context, handle))
+        } else {
+            Err(ImageBuilderError::CreateImageFailed)
+        }
+    }
+
+    /// Construct an offscreen framebuffer of the given size in pixels, which can be drawn into
+    /// with [Framebuffer::frame] and used as an image afterwards.
+    /// Only available on contexts using the GL backend.
+    pub fn build_framebuffer(self, width: usize, height: usize) -> Result<Framebuffer<'a>, ImageBuilderError> {
+        let backend = match self.context.backend {
+            Some(backend) => backend,
+            None => return Err(ImageBuilderError::FramebufferNotSupported),
+        };
+
+        let raw = backend.create_framebuffer(self.context.raw(), width as c_int, height as c_int, self.flags.bits());
+        if raw.is_null() {
+            return Err(ImageBuilderError::CreateImageFailed);
+        }
+        let handle = unsafe { (*raw).image };
+        Ok(Framebuffer {
+            raw,
+            backend,
+            image: ManuallyDrop::new(Image(self.context, handle)),
+            device_pixel_ratio: Cell::new(1.0),
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -1148,6 +1766,12 @@ pub enum ImageBuilderError {
     CreateImageFailed,
     /// For `from_rgba`, the passed data slice does not contain enough data for the specified image size.
     NotEnoughData,
+    /// Framebuffers can't be created on contexts using a custom [Renderer](renderer/trait.Renderer.html).
+    FramebufferNotSupported,
+    /// OpenGL textures can't be used on contexts using a custom [Renderer](renderer/trait.Renderer.html).
+    GlTextureNotSupported,
+    /// The SVG file for `build_from_svg_file` or `build_from_svg_memory` could not be read or parsed.
+    InvalidSvg,
 }
 
 impl From<NulError> for ImageBuilderError {
@@ -1180,6 +1804,12 @@ impl<'a> Image<'a> {
         (w as usize, h as usize)
     }
 
+    /// The OpenGL texture backing the image, `None` if the context uses a custom renderer.
+    pub fn gl_texture(&self) -> Option<u32> {
+        let backend = self.ctx().backend?;
+        Some(backend.image_texture(self.ctx().raw(), self.raw()))
+    }
+
     pub fn update(&mut self, data: &[u32]) {
         unsafe {
             ffi::nvgUpdateImage(self.ctx().raw(), self.raw(), data.as_ptr() as *const _);
@@ -1204,6 +1834,233 @@ impl<'a> Drop for Image<'a> {
     }
 }
 
+/// An offscreen framebuffer, whose contents can be used as an image.
+///
+/// The framebuffer owns its image, which gets deleted together with it.
+#[derive(Debug)]
+pub struct Framebuffer<'a> {
+    raw: *mut ffi::NVGLUframebuffer,
+    backend: Backend,
+    image: ManuallyDrop<Image<'a>>,
+    device_pixel_ratio: Cell<f32>,
+}
+
+impl<'a> Framebuffer<'a> {
+    /// Get the underlying context this framebuffer was created on.
+    pub fn context(&self) -> &'a Context {
+        self.image.context()
+    }
+
+    /// The image holding the contents of the framebuffer, for use in an [ImagePattern].
+    /// The image is flipped and premultiplied as NanoVG expects for framebuffers.
+    pub fn image(&self) -> &Image<'a> {
+        &self.image
+    }
+
+    /// The size of the framebuffer in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        self.image.size()
+    }
+
+    /// Clear the framebuffer and draw a frame into it, see [Context::frame].
+    /// The frame size is the framebuffer size divided by `device_pixel_ratio`.
+    ///
+    /// Must not be called while another frame is being drawn on the same context.
+    pub fn frame<F: FnOnce(Frame<'a>)>(&self, device_pixel_ratio: f32, handler: F) {
+        let result: Result<(), ()> = self.try_frame(device_pixel_ratio, |frame| {
+            handler(frame);
+            Ok(())
+        });
+        debug_assert!(result.is_ok());
+    }
+
+    /// Clear the framebuffer and draw a frame into it with a fallible `handler`, see [Context::try_frame].
+    /// The framebuffer is cleared even if the frame is cancelled.
+    pub fn try_frame<T, E, F: FnOnce(Frame<'a>) -> Result<T, E>>(
+        &self,
+        device_pixel_ratio: f32,
+        handler: F,
+    ) -> Result<T, E> {
+        let (width, height) = self.size();
+        let mut state: [c_int; 5] = [0; 5];
+        unsafe {
+            ffi::nvgShimBeginFramebuffer(self.raw, width as c_int, height as c_int, state.as_mut_ptr());
+        }
+        // Screenshots of the context should still refer to the last frame drawn on screen.
+        let _restore = FramebufferRestore {
+            context: self.context(),
+            state,
+            last_frame: self.context().last_frame.get(),
+        };
+        let result = self.context().try_frame(
+            (width as f32 / device_pixel_ratio, height as f32 / device_pixel_ratio),
+            device_pixel_ratio,
+            handler,
+        );
+        if result.is_ok() {
+            self.device_pixel_ratio.set(device_pixel_ratio);
+        }
+        result
+    }
+
+    /// Read back the contents of the framebuffer.
+    pub fn screenshot(&self) -> Screenshot {
+        let (width, height) = self.size();
+        Screenshot::read(self.raw, width, height, self.device_pixel_ratio.get())
+    }
+}
+
+/// Rebinds the previous framebuffer and viewport when a framebuffer frame is done, even by panicking.
+struct FramebufferRestore<'a> {
+    context: &'a Context,
+    state: [c_int; 5],
+    last_frame: Option<((f32, f32), f32)>,
+}
+
+impl<'a> Drop for FramebufferRestore<'a> {
+    fn drop(&mut self) {
+        self.context.last_frame.set(self.last_frame);
+        unsafe {
+            ffi::nvgShimEndFramebuffer(self.state.as_ptr());
+        }
+    }
+}
+
+impl<'a> Drop for Framebuffer<'a> {
+    fn drop(&mut self) {
+        // Deleting the framebuffer deletes its image as well.
+        self.backend.delete_framebuffer(self.raw);
+    }
+}
+
+/// Pixels read back from a framebuffer, as straight alpha RGBA rows from top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Screenshot {
+    width: usize,
+    height: usize,
+    device_pixel_ratio: f32,
+    data: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Create a screenshot from straight alpha RGBA rows from top to bottom.
+    pub(crate) fn new(width: usize, height: usize, device_pixel_ratio: f32, data: Vec<u8>) -> Self {
+        Screenshot {
+            width,
+            height,
+            device_pixel_ratio,
+            data,
+        }
+    }
+
+    /// Read the bottom left `width` x `height` pixels of `framebuffer`, or of the bound framebuffer if null.
+    fn read(framebuffer: *mut ffi::NVGLUframebuffer, width: usize, height: usize, device_pixel_ratio: f32) -> Self {
+        let stride = width * 4;
+        let mut flipped = vec![0u8; stride * height];
+        unsafe {
+            ffi::nvgShimReadPixels(framebuffer, width as c_int, height as c_int, flipped.as_mut_ptr());
+        }
+        // OpenGL returns rows from the bottom and NanoVG renders premultiplied colors.
+        let mut data = Vec::with_capacity(flipped.len());
+        for row in flipped.chunks(stride.max(1)).rev() {
+            data.extend_from_slice(row);
+        }
+        renderer::unpremultiply(&mut data);
+        Screenshot::new(width, height, device_pixel_ratio, data)
+    }
+
+    /// The width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The device pixel ratio of the frame the screenshot was taken of.
+    pub fn device_pixel_ratio(&self) -> f32 {
+        self.device_pixel_ratio
+    }
+
+    /// The pixels as straight alpha RGBA, rows from top to bottom.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the screenshot, returning its pixels.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Get the color of a pixel, `(0, 0)` being the top left corner.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let p = &self.data[(y * self.width + x) * 4..][..4];
+        Some(Color::from_rgba(p[0], p[1], p[2], p[3]))
+    }
+
+    /// Scale the screenshot down by its device pixel ratio, so one pixel corresponds to one unit
+    /// of the frame size. Pixels are averaged, weighted by their alpha.
+    pub fn to_logical_size(&self) -> Screenshot {
+        let ratio = self.device_pixel_ratio;
+        if ratio <= 1.0 {
+            return Screenshot::new(self.width, self.height, 1.0, self.data.clone());
+        }
+        let width = ((self.width as f32 / ratio).round() as usize).max(1);
+        let height = ((self.height as f32 / ratio).round() as usize).max(1);
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let (y0, y1) = (
+                y * self.height / height,
+                ((y + 1) * self.height / height).max(y * self.height / height + 1),
+            );
+            for x in 0..width {
+                let (x0, x1) = (
+                    x * self.width / width,
+                    ((x + 1) * self.width / width).max(x * self.width / width + 1),
+                );
+                let mut sum = [0u32; 4];
+                for sy in y0..y1.min(self.height) {
+                    for sx in x0..x1.min(self.width) {
+                        let p = &self.data[(sy * self.width + sx) * 4..][..4];
+                        let a = p[3] as u32;
+                        sum[0] += p[0] as u32 * a;
+                        sum[1] += p[1] as u32 * a;
+                        sum[2] += p[2] as u32 * a;
+                        sum[3] += a;
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as u32;
+                match sum[3] {
+                    0 => data.extend_from_slice(&[0, 0, 0, 0]),
+                    alpha => {
+                        data.push((sum[0] / alpha) as u8);
+                        data.push((sum[1] / alpha) as u8);
+                        data.push((sum[2] / alpha) as u8);
+                        data.push((alpha / count) as u8);
+                    }
+                }
+            }
+        }
+        Screenshot::new(width, height, 1.0, data)
+    }
+
+    /// Encode the screenshot as PNG.
+    pub fn write_png<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        png::write_rgba(out, self.width, self.height, &self.data)
+    }
+
+    /// Save the screenshot as a PNG file.
+    pub fn save_png<P: AsRef<IoPath>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        self.write_png(&mut file)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Direction {
     Clockwise,
@@ -1217,6 +2074,14 @@ impl Direction {
             Direction::CounterClockwise => ffi::NVGwinding::NVG_CCW,
         }
     }
+
+    fn from_raw(raw: c_int) -> Direction {
+        if raw == ffi::NVGwinding::NVG_CW.bits() {
+            Direction::Clockwise
+        } else {
+            Direction::CounterClockwise
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -1237,7 +2102,7 @@ impl Solidity {
 /// Winding enum that holds either Direction or Solidity enum
 /// These two are identical aliases.
 /// They are here for different meanings in different contexts
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Winding {
     Direction(Direction),
     Solidity(Solidity),
@@ -1334,6 +2199,23 @@ impl BlendFactor {
             SourceAlphaSaturate => ffi::NVGblendFactor::NVG_SRC_ALPHA_SATURATE,
         }
     }
+
+    fn from_raw(raw: c_int) -> BlendFactor {
+        use crate::BlendFactor::*;
+        match ffi::NVGblendFactor::from_bits_truncate(raw) {
+            ffi::NVGblendFactor::NVG_ZERO => Zero,
+            ffi::NVGblendFactor::NVG_SRC_COLOR => SourceColor,
+            ffi::NVGblendFactor::NVG_ONE_MINUS_SRC_COLOR => OneMinusSourceColor,
+            ffi::NVGblendFactor::NVG_DST_COLOR => DestinationColor,
+            ffi::NVGblendFactor::NVG_ONE_MINUS_DST_COLOR => OneMinusDestinationColor,
+            ffi::NVGblendFactor::NVG_SRC_ALPHA => SourceAlpha,
+            ffi::NVGblendFactor::NVG_ONE_MINUS_SRC_ALPHA => OneMinusSourceAlpha,
+            ffi::NVGblendFactor::NVG_DST_ALPHA => DestinationAlpha,
+            ffi::NVGblendFactor::NVG_ONE_MINUS_DST_ALPHA => OneMinusDestinationAlpha,
+            ffi::NVGblendFactor::NVG_SRC_ALPHA_SATURATE => SourceAlphaSaturate,
+            _ => One,
+        }
+    }
 }
 
 /// A handle to a font.
@@ -1376,10 +2258,15 @@ impl<'a> Font<'a> {
     /// Attempt to load a font from the file at `path`.
     /// Fonts are always named (specified with `name`).
     pub fn from_file<S: AsRef<str>, P: AsRef<IoPath>>(context: &'a Context, name: S, path: P) -> CreateFontResult {
-        let name = CString::new(name.as_ref())?;
-        let path = CString::new(path.as_ref().to_str().ok_or(CreateFontError::InvalidPath)?)?;
-        let handle = unsafe { ffi::nvgCreateFont(context.raw(), name.as_ptr(), path.as_ptr()) };
+        let raw_name = CString::new(name.as_ref())?;
+        let raw_path = CString::new(path.as_ref().to_str().ok_or(CreateFontError::InvalidPath)?)?;
+        let handle = unsafe { ffi::nvgCreateFont(context.raw(), raw_name.as_ptr(), raw_path.as_ptr()) };
         if handle > ffi::FONS_INVALID {
+            if context.renderer.is_some() {
+                if let Ok(data) = fs::read(path) {
+                    context.register_font(handle, name.as_ref(), &data);
+                }
+            }
             Ok(Font(context, handle))
         } else {
             Err(CreateFontError::InvalidHandle)
@@ -1389,17 +2276,18 @@ impl<'a> Font<'a> {
     /// Attempt to load a font from memory.
     /// Fonts are always named (specified with `name`).
     pub fn from_memory<'b, S: AsRef<str>>(context: &'a Context, name: S, memory: &'b [u8]) -> CreateFontResult<'a> {
-        let name = CString::new(name.as_ref())?;
+        let raw_name = CString::new(name.as_ref())?;
         let handle = unsafe {
             ffi::nvgCreateFontMem(
                 context.raw(),
-                name.as_ptr(),
+                raw_name.as_ptr(),
                 memory.as_ptr() as *mut _,
                 memory.len() as c_int,
                 0,
             )
         };
         if handle > ffi::FONS_INVALID {
+            context.register_font(handle, name.as_ref(), memory);
             Ok(Font(context, handle))
         } else {
             Err(CreateFontError::InvalidHandle)
@@ -1491,22 +2379,42 @@ impl TextBounds {
 
 /// Iterator over text glyph positions, calculated by Context::text_glyph_positions
 pub struct TextGlyphPositions<'a> {
-    context: &'a Context,
-    x: f32,
-    y: f32,
-    start: *const c_char,
-    glyphs: [ffi::NVGglyphPosition; 2],
+    glyphs: std::vec::IntoIter<GlyphPosition>,
+    context: PhantomData<&'a Context>,
 }
 
 impl<'a> TextGlyphPositions<'a> {
-    /// Creates new TextGlyphPositions iterator with needed variables for iterating over glyphs in text
+    /// Creates new TextGlyphPositions iterator over the positions of all glyphs in text.
+    /// They are calculated at once, NanoVG aligns every part of the text it is given on its own.
     fn new(context: &'a Context, x: f32, y: f32, text: CString) -> TextGlyphPositions<'a> {
+        // Every glyph takes at least one byte.
+        let length = text.as_bytes().len();
+        let mut raw: Vec<ffi::NVGglyphPosition> = (0..length).map(|_| unsafe { mem::zeroed() }).collect();
+        let count = unsafe {
+            ffi::nvgTextGlyphPositions(
+                context.raw(),
+                x,
+                y,
+                text.as_ptr(),
+                ptr::null(),
+                raw.as_mut_ptr(),
+                length as c_int,
+            )
+        };
+        raw.truncate(count.max(0) as usize);
+
+        let offset = |glyph: &ffi::NVGglyphPosition| glyph.s as usize - text.as_ptr() as usize;
+        let glyphs: Vec<_> = raw
+            .iter()
+            .enumerate()
+            .map(|(i, glyph)| {
+                let end = raw.get(i + 1).map_or(length, &offset);
+                GlyphPosition::new(glyph, offset(glyph)..end)
+            })
+            .collect();
         TextGlyphPositions {
-            context: context,
-            x: x,
-            y: y,
-            start: text.into_raw(),
-            glyphs: [unsafe { mem::zeroed() }; 2],
+            glyphs: glyphs.into_iter(),
+            context: PhantomData,
         }
     }
 }
@@ -1516,31 +2424,7 @@ impl<'a> Iterator for TextGlyphPositions<'a> {
 
     /// Returns next glyph in text
     fn next(&mut self) -> Option<Self::Item> {
-        let num_glyphs = unsafe {
-            ffi::nvgTextGlyphPositions(
-                self.context.raw(),
-                self.x,
-                self.y,
-                self.start,
-                ptr::null(),
-                self.glyphs.as_mut_ptr(),
-                2,
-            )
-        };
-
-        match num_glyphs {
-            1 => {
-                self.start = &('\0' as c_char);
-                Some(GlyphPosition::new(&self.glyphs[0]))
-            }
-            2 => {
-                self.x = self.glyphs[1].x;
-                self.start = self.glyphs[1].s;
-
-                Some(GlyphPosition::new(&self.glyphs[0]))
-            }
-            _ => None,
-        }
+        self.glyphs.next()
     }
 }
 
@@ -1623,15 +2507,18 @@ pub struct GlyphPosition {
     pub x: f32,
     pub min_x: f32,
     pub max_x: f32,
+    /// The byte range of the glyph in the text.
+    pub bytes: Range<usize>,
 }
 
 impl GlyphPosition {
     /// Creates new GlyphPosition from raw nanovg glyph position.
-    fn new(glyph: &ffi::NVGglyphPosition) -> GlyphPosition {
+    fn new(glyph: &ffi::NVGglyphPosition, bytes: Range<usize>) -> GlyphPosition {
         GlyphPosition {
             x: glyph.x,
             min_x: glyph.minx,
             max_x: glyph.maxx,
+            bytes,
         }
     }
 }
@@ -1976,4 +2863,72 @@ mod tests {
         let b = Transform::new().with_skew(66.6, 1337.2);
         trans_not_eq!(a * b, b * a);
     }
+
+    #[test]
+    fn test_screenshot_to_logical_size() {
+        let data = vec![
+            255, 0, 0, 255, 0, 0, 255, 255, 0, 255, 0, 255, 0, 255, 0, 0, //
+            255, 0, 0, 255, 0, 0, 255, 255, 0, 255, 0, 255, 0, 255, 0, 0,
+        ];
+        let screenshot = Screenshot::new(4, 2, 2.0, data).to_logical_size();
+        assert_eq!((screenshot.width(), screenshot.height()), (2, 1));
+        assert_eq!(screenshot.device_pixel_ratio(), 1.0);
+        // Fully transparent pixels don't contribute their color.
+        assert_eq!(screenshot.data(), &[127, 0, 127, 255, 0, 255, 0, 127]);
+
+        let mut png = Vec::new();
+        screenshot.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_headless_context_draws() {
+        let mut drawn = Vec::new();
+        for &backend in &[Backend::Gl2, Backend::Gl3, Backend::Gles2, Backend::Gles3] {
+            // Not every platform provides every backend.
+            let context = match ContextBuilder::new().backend(backend).build_headless(16, 16) {
+                Ok(context) => context,
+                Err(()) => continue,
+            };
+            context.frame((16.0, 16.0), 1.0, |frame| {
+                frame.path(
+                    |path| {
+                        path.rect((0.0, 0.0), (16.0, 8.0));
+                        path.fill(Color::from_rgb(255, 0, 0), Default::default());
+                    },
+                    Default::default(),
+                );
+                frame.path(
+                    |path| {
+                        path.rect((0.0, 8.0), (16.0, 8.0));
+                        path.fill(Color::from_rgb(0, 0, 255), Default::default());
+                    },
+                    Default::default(),
+                );
+            });
+
+            let screenshot = context.screenshot().unwrap();
+            assert_eq!((screenshot.width(), screenshot.height()), (16, 16));
+            assert_eq!(
+                screenshot.pixel(8, 4),
+                Some(Color::from_rgb(255, 0, 0)),
+                "{:?}",
+                backend
+            );
+            assert_eq!(
+                screenshot.pixel(8, 12),
+                Some(Color::from_rgb(0, 0, 255)),
+                "{:?}",
+                backend
+            );
+            drawn.push(backend);
+        }
+        // Without EGL or OSMesa there is nothing to test, unless the backends are required to be tested.
+        if ::std::env::var_os("NANOVG_REQUIRE_HEADLESS").is_some() {
+            assert!(
+                !drawn.is_empty(),
+                "no headless backend could be created, install Mesa's EGL or OSMesa"
+            );
+        }
+    }
 }
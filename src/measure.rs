@@ -0,0 +1,409 @@
+//! Measuring paths by length, to find points along them and cut out their parts.
+
+use path_data::{cubic_point, distance, normalize, points_equal, split_cubic, Cubic};
+use {PathData, PathSegment};
+
+/// Number of parts each curve is measured in, before finding points in between with Newton's method.
+const CURVE_PARTS: usize = 16;
+
+/// A path measured by length, to find points along it and cut out its parts, see [PathData::measure].
+///
+/// Distances run along every subpath in turn, including the lines closing them but not the moves between them,
+/// so the end of one subpath is at the same distance as the start of the next.
+#[derive(Clone, Debug)]
+pub struct PathMeasure {
+    subpaths: Vec<Subpath>,
+    length: f32,
+}
+
+impl PathData {
+    /// Measure the path by length, with curves measured within a fraction of a percent.
+    pub fn measure(&self) -> PathMeasure {
+        let subpaths = subpaths(self);
+        let length = subpaths.iter().map(Subpath::length).sum();
+        PathMeasure { subpaths, length }
+    }
+
+    /// The length of the path, see [PathData::measure].
+    pub fn length(&self) -> f32 {
+        self.measure().length()
+    }
+}
+
+impl PathMeasure {
+    /// The length of all subpaths together.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// The point `distance` along the path, clamped to its ends. `None` if the path has no length.
+    pub fn point_at(&self, distance: f32) -> Option<(f32, f32)> {
+        self.locate(distance).map(|(segment, t)| segment.point(t))
+    }
+
+    /// The unit vector in the direction the path goes at `distance` along it, clamped to its ends.
+    /// `None` if the path has no length.
+    pub fn tangent_at(&self, distance: f32) -> Option<(f32, f32)> {
+        self.locate(distance).map(|(segment, t)| segment.direction(t))
+    }
+
+    /// The part of the path between the distances `from` and `to` along it, clamped to its ends.
+    ///
+    /// Lines and curves are cut where the part starts and ends, and every subpath the part goes through
+    /// becomes a subpath of its own. Closed subpaths stay closed if the part covers all of them.
+    pub fn sub_path(&self, from: f32, to: f32) -> PathData {
+        let mut path = PathData::new();
+        let mut start = 0.0;
+        for subpath in &self.subpaths {
+            let length = subpath.length();
+            let (low, high) = ((from - start).max(0.0), (to - start).min(length));
+            if low < high {
+                subpath.add_part(&mut path, low, high);
+            }
+            start += length;
+        }
+        path
+    }
+
+    /// The segment at `distance` along the path, and the parameter of the point there.
+    fn locate(&self, distance: f32) -> Option<(&Segment, f32)> {
+        let mut along = distance.max(0.0);
+        for subpath in &self.subpaths {
+            if along < subpath.length() {
+                return Some(subpath.locate(along));
+            }
+            along -= subpath.length();
+        }
+        self.subpaths.last().map(|subpath| subpath.locate(subpath.length()))
+    }
+}
+
+/// The lines and curves of a subpath, including the line closing it.
+#[derive(Clone, Debug)]
+pub(crate) struct Subpath {
+    pub(crate) segments: Vec<Segment>,
+    pub(crate) closed: bool,
+    /// The length of the subpath up to the end of each segment.
+    ends: Vec<f32>,
+}
+
+impl Subpath {
+    pub(crate) fn length(&self) -> f32 {
+        self.ends.last().cloned().unwrap_or(0.0)
+    }
+
+    /// The segment `along` the subpath from its start, and the parameter of the point there.
+    fn locate(&self, along: f32) -> (&Segment, f32) {
+        let index = self
+            .ends
+            .partition_point(|&end| end <= along)
+            .min(self.segments.len() - 1);
+        let start = if index == 0 { 0.0 } else { self.ends[index - 1] };
+        let segment = &self.segments[index];
+        (segment, segment.parameter(along - start))
+    }
+
+    /// Add the part of the subpath between `from` and `to` along it to `path`, as a subpath of its own.
+    fn add_part(&self, path: &mut PathData, from: f32, to: f32) {
+        let mut start = 0.0;
+        let mut started = false;
+        for (segment, &end) in self.segments.iter().zip(&self.ends) {
+            let (low, high) = (from.max(start), to.min(end));
+            if low < high {
+                let (t0, t1) = (segment.parameter(low - start), segment.parameter(high - start));
+                if !started {
+                    path.move_to(segment.point(t0));
+                    started = true;
+                }
+                segment.shape.part(t0, t1).add_to(path);
+            }
+            start = end;
+        }
+        if self.closed && from <= 0.0 && to >= self.length() {
+            path.close();
+        }
+    }
+}
+
+/// The subpaths of a path which have a length.
+pub(crate) fn subpaths(path: &PathData) -> Vec<Subpath> {
+    let mut subpaths: Vec<(Vec<Segment>, bool)> = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    let mut open = false;
+    for segment in path.segments() {
+        let shape = match *segment {
+            PathSegment::MoveTo(point) => {
+                subpaths.push((Vec::new(), false));
+                current = point;
+                start = point;
+                open = true;
+                continue;
+            }
+            PathSegment::LineTo(point) => Shape::Line(current, point),
+            PathSegment::CubicBezierTo {
+                point,
+                control1,
+                control2,
+            } => Shape::Curve((current, control1, control2, point)),
+            PathSegment::Close => {
+                if open {
+                    if !points_equal(current, start) {
+                        subpaths
+                            .last_mut()
+                            .unwrap()
+                            .0
+                            .push(Segment::new(Shape::Line(current, start)));
+                    }
+                    subpaths.last_mut().unwrap().1 = true;
+                    current = start;
+                    open = false;
+                }
+                continue;
+            }
+            PathSegment::Winding(_) => continue,
+        };
+        // Lines after `close` continue from the start of the closed subpath, like in NanoVG.
+        if !open {
+            subpaths.push((Vec::new(), false));
+            open = true;
+        }
+        current = shape.end();
+        let segment = Segment::new(shape);
+        if segment.length() > 0.0 {
+            subpaths.last_mut().unwrap().0.push(segment);
+        }
+    }
+    subpaths
+        .into_iter()
+        .filter(|(segments, _)| !segments.is_empty())
+        .map(|(segments, closed)| {
+            let mut length = 0.0;
+            let ends = segments
+                .iter()
+                .map(|segment| {
+                    length += segment.length();
+                    length
+                })
+                .collect();
+            Subpath { segments, closed, ends }
+        })
+        .collect()
+}
+
+/// A line or cubic bezier curve of a subpath, from its start to its end point.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Shape {
+    Line((f32, f32), (f32, f32)),
+    Curve(Cubic),
+}
+
+impl Shape {
+    fn end(&self) -> (f32, f32) {
+        match *self {
+            Shape::Line(_, end) | Shape::Curve((_, _, _, end)) => end,
+        }
+    }
+
+    /// Add the shape to `path`, which is at its start.
+    pub(crate) fn add_to(&self, path: &mut PathData) {
+        match *self {
+            Shape::Line(_, end) => path.line_to(end),
+            Shape::Curve((_, control1, control2, end)) => path.cubic_bezier_to(end, control1, control2),
+        }
+    }
+
+    /// The part between the parameters `from` and `to`.
+    pub(crate) fn part(&self, from: f32, to: f32) -> Shape {
+        match *self {
+            Shape::Line(start, end) => {
+                let lerp = |t: f32| (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+                Shape::Line(lerp(from), lerp(to))
+            }
+            Shape::Curve(curve) => {
+                let (head, _) = split_cubic(curve, to);
+                let (_, part) = split_cubic(head, if to > 0.0 { from / to } else { 0.0 });
+                Shape::Curve(part)
+            }
+        }
+    }
+}
+
+/// A line or curve with the lengths along it, to find points by their distance from the start.
+#[derive(Clone, Debug)]
+pub(crate) struct Segment {
+    pub(crate) shape: Shape,
+    /// The length up to the end of each of the [CURVE_PARTS] parts of a curve, or the length of a line.
+    lengths: Vec<f32>,
+}
+
+impl Segment {
+    fn new(shape: Shape) -> Self {
+        let lengths = match shape {
+            Shape::Line(start, end) => vec![distance(start, end)],
+            Shape::Curve(curve) => {
+                let mut length = 0.0;
+                (0..CURVE_PARTS)
+                    .map(|i| {
+                        let step = 1.0 / CURVE_PARTS as f32;
+                        length += curve_length(curve, i as f32 * step, (i + 1) as f32 * step);
+                        length
+                    })
+                    .collect()
+            }
+        };
+        Segment { shape, lengths }
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    /// The parameter of the point `along` the segment from its start.
+    pub(crate) fn parameter(&self, along: f32) -> f32 {
+        let curve = match self.shape {
+            Shape::Line(..) => return (along / self.length()).clamp(0.0, 1.0),
+            Shape::Curve(curve) => curve,
+        };
+        if along >= self.length() {
+            return 1.0;
+        }
+        let part = self.lengths.iter().position(|&length| length > along).unwrap();
+        let before = if part == 0 { 0.0 } else { self.lengths[part - 1] };
+        let step = 1.0 / CURVE_PARTS as f32;
+        let (low, high) = (part as f32 * step, (part + 1) as f32 * step);
+        let mut t = low + step * (along - before) / (self.lengths[part] - before);
+        for _ in 0..4 {
+            let speed = curve_speed(curve, t);
+            if speed < 1e-6 {
+                break;
+            }
+            t = (t - (before + curve_length(curve, low, t) - along) / speed).clamp(low, high);
+        }
+        t
+    }
+
+    pub(crate) fn point(&self, t: f32) -> (f32, f32) {
+        match self.shape {
+            Shape::Line(start, end) => (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t),
+            Shape::Curve((p0, p1, p2, p3)) => cubic_point(p0, p1, p2, p3, t),
+        }
+    }
+
+    /// The unit tangent at the parameter `t`.
+    pub(crate) fn direction(&self, t: f32) -> (f32, f32) {
+        match self.shape {
+            Shape::Line(start, end) => normalize((end.0 - start.0, end.1 - start.1)),
+            Shape::Curve(curve) => {
+                // Where a control point is on the end point, the curve leaves in the direction of the next one.
+                let t = if curve_speed(curve, t) < 1e-6 {
+                    t.clamp(1e-3, 1.0 - 1e-3)
+                } else {
+                    t
+                };
+                normalize(curve_derivative(curve, t))
+            }
+        }
+    }
+}
+
+fn curve_derivative((p0, p1, p2, p3): Cubic, t: f32) -> (f32, f32) {
+    let u = 1.0 - t;
+    let (w0, w1, w2) = (3.0 * u * u, 6.0 * u * t, 3.0 * t * t);
+    (
+        w0 * (p1.0 - p0.0) + w1 * (p2.0 - p1.0) + w2 * (p3.0 - p2.0),
+        w0 * (p1.1 - p0.1) + w1 * (p2.1 - p1.1) + w2 * (p3.1 - p2.1),
+    )
+}
+
+fn curve_speed(curve: Cubic, t: f32) -> f32 {
+    let (dx, dy) = curve_derivative(curve, t);
+    dx.hypot(dy)
+}
+
+/// The length of a curve between two parameters, with 5 point Gauss-Legendre quadrature.
+fn curve_length(curve: Cubic, from: f32, to: f32) -> f32 {
+    const NODES: [(f32, f32); 5] = [
+        (0.0, 0.568_888_9),
+        (-0.538_469_3, 0.478_628_67),
+        (0.538_469_3, 0.478_628_67),
+        (-0.906_179_8, 0.236_926_89),
+        (0.906_179_8, 0.236_926_89),
+    ];
+    let (middle, half) = ((from + to) * 0.5, (to - from) * 0.5);
+    NODES
+        .iter()
+        .map(|&(x, weight)| weight * curve_speed(curve, middle + half * x))
+        .sum::<f32>()
+        * half
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+    use {Direction, Winding};
+
+    #[test]
+    fn test_measure_lines() {
+        let mut path = PathData::new();
+        path.move_to((0.0, 0.0));
+        path.line_to((10.0, 0.0));
+        path.line_to((10.0, 5.0));
+        path.rect((20.0, 0.0), (2.0, 2.0));
+        let measure = path.measure();
+        assert_eq!(measure.length(), 23.0);
+        assert_eq!(measure.point_at(12.0), Some((10.0, 2.0)));
+        assert_eq!(measure.tangent_at(12.0), Some((0.0, 1.0)));
+        // The next subpath starts where the last one ends, and distances are clamped to the path.
+        assert_eq!(measure.point_at(15.0), Some((20.0, 0.0)));
+        assert_eq!(measure.point_at(-1.0), Some((0.0, 0.0)));
+        assert_eq!(measure.point_at(30.0), Some((20.0, 0.0)));
+        assert_eq!(measure.tangent_at(30.0), Some((-1.0, 0.0)));
+        assert_eq!(PathData::new().measure().point_at(0.0), None);
+
+        assert_eq!(
+            measure.sub_path(5.0, 16.0).segments(),
+            &[
+                PathSegment::MoveTo((5.0, 0.0)),
+                PathSegment::LineTo((10.0, 0.0)),
+                PathSegment::LineTo((10.0, 5.0)),
+                PathSegment::MoveTo((20.0, 0.0)),
+                PathSegment::LineTo((20.0, 1.0)),
+            ]
+        );
+        // A closed subpath stays closed if all of it is kept, with the line closing it.
+        let square = measure.sub_path(15.0, 23.0);
+        assert_eq!(
+            square.segments()[4..],
+            [PathSegment::LineTo((20.0, 0.0)), PathSegment::Close]
+        );
+        assert!(measure.sub_path(5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_measure_curves() {
+        let mut arc = PathData::new();
+        arc.arc((0.0, 0.0), 10.0, 0.0, PI, Winding::Direction(Direction::Clockwise));
+        let measure = arc.measure();
+        assert!((measure.length() - PI * 10.0).abs() < 1e-2);
+
+        // Points are spread evenly along the curves.
+        for i in 0..=8 {
+            let angle = PI * i as f32 / 8.0;
+            let (x, y) = measure.point_at(measure.length() * i as f32 / 8.0).unwrap();
+            assert!((x - 10.0 * angle.cos()).abs() < 1e-2 && (y - 10.0 * angle.sin()).abs() < 1e-2);
+            let (dx, dy) = measure.tangent_at(measure.length() * i as f32 / 8.0).unwrap();
+            assert!((dx + angle.sin()).abs() < 1e-2 && (dy - angle.cos()).abs() < 1e-2);
+        }
+
+        // The part is cut out of the curves, and keeps their shape.
+        let part = measure
+            .sub_path(measure.length() / 4.0, measure.length() / 2.0)
+            .measure();
+        assert!((part.length() - measure.length() / 4.0).abs() < 1e-3);
+        let (x, y) = part.point_at(part.length() / 2.0).unwrap();
+        let angle = PI * 3.0 / 8.0;
+        assert!((x - 10.0 * angle.cos()).abs() < 1e-2 && (y - 10.0 * angle.sin()).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,1209 @@
+//! Owned paths which can be stored, transformed and drawn into any frame.
+
+use std::borrow::Cow;
+use std::f32::consts::PI;
+
+use {
+    ffi, svg, Direction, FillOptions, Frame, LineCap, LineJoin, Paint, Path, PathOptions, Solidity, StrokeOptions,
+    Transform, Winding,
+};
+
+/// Length proportional to radius of a cubic bezier handle for 90deg arcs, like `NVG_KAPPA90`.
+const KAPPA90: f32 = 0.552_284_8;
+/// Distance under which points are considered equal, like NanoVG's `distTol` at a device pixel ratio of 1.
+pub(crate) const DIST_TOL: f32 = 0.01;
+/// Flattening tolerance for hit testing, like NanoVG's `tessTol` at a device pixel ratio of 1.
+const TESS_TOL: f32 = 0.25;
+
+/// A segment of a [PathData].
+///
+/// Arcs, rectangles and ellipses are stored as the bezier curves NanoVG draws them with,
+/// so every segment stays exact under any transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+    /// Start a new subpath at a point.
+    MoveTo((f32, f32)),
+    /// A line from the current point.
+    LineTo((f32, f32)),
+    /// A cubic bezier curve from the current point.
+    CubicBezierTo {
+        point: (f32, f32),
+        control1: (f32, f32),
+        control2: (f32, f32),
+    },
+    /// Set the winding of the current subpath.
+    Winding(Winding),
+    /// Close the current subpath.
+    Close,
+}
+
+/// How overlapping subpaths decide which points are inside a path, see [PathData::contains_point].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// How NanoVG fills paths: subpaths are turned to their [Winding], solid unless set otherwise,
+    /// and points are inside if the subpaths around them don't cancel out.
+    NonZero,
+    /// Points are inside if an odd number of subpaths surround them, whatever their winding.
+    EvenOdd,
+}
+
+/// An axis-aligned bounding box, see [PathData::bounds].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Bounds {
+    /// The smallest bounds containing all `points`, `None` if there are none.
+    fn from_points<I: IntoIterator<Item = (f32, f32)>>(points: I) -> Option<Bounds> {
+        points.into_iter().fold(None, |bounds, (x, y)| {
+            Some(match bounds {
+                Some(Bounds {
+                    min_x,
+                    min_y,
+                    max_x,
+                    max_y,
+                }) => Bounds {
+                    min_x: min_x.min(x),
+                    min_y: min_y.min(y),
+                    max_x: max_x.max(x),
+                    max_y: max_y.max(y),
+                },
+                None => Bounds {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                },
+            })
+        })
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+}
+
+/// A subpath of a [PathData] approximated with straight lines, see [PathData::flatten].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polyline {
+    /// The points in the order of the path.
+    pub points: Vec<(f32, f32)>,
+    /// Whether the last point connects back to the first one.
+    pub closed: bool,
+}
+
+/// An owned path, recorded with the same commands as [Path].
+///
+/// Unlike [Path], which only exists while drawing a frame, path data can be kept around, cloned and
+/// transformed, and drawn into any [Frame] with [PathData::fill], [PathData::stroke] or [Path::append].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathData {
+    segments: Vec<PathSegment>,
+}
+
+impl PathData {
+    /// Create an empty path.
+    pub fn new() -> Self {
+        Self { segments: Vec::new() }
+    }
+
+    /// Parse SVG path data, like the `d` attribute of `<path>` elements, see [svg::parse_path].
+    pub fn from_svg(d: &str) -> Result<PathData, svg::PathError> {
+        svg::parse_path(d)
+    }
+
+    /// The segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Whether the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Remove all segments.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    /// The end point of the last segment, where the next segment starts.
+    pub fn current_point(&self) -> Option<(f32, f32)> {
+        self.segments.iter().rev().filter_map(segment_point).next()
+    }
+
+    /// Add a segment to the path.
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Add the segments of `other` to the path.
+    pub fn append(&mut self, other: &PathData) {
+        self.segments.extend_from_slice(&other.segments);
+    }
+
+    /// Start new sub-path with specified coordinates as the first point.
+    pub fn move_to(&mut self, point: (f32, f32)) {
+        self.segments.push(PathSegment::MoveTo(point));
+    }
+
+    /// Add a line to the subpath.
+    pub fn line_to(&mut self, point: (f32, f32)) {
+        self.segments.push(PathSegment::LineTo(point));
+    }
+
+    /// Add a cubic bezier curve to the subpath.
+    pub fn cubic_bezier_to(&mut self, point: (f32, f32), control1: (f32, f32), control2: (f32, f32)) {
+        self.segments.push(PathSegment::CubicBezierTo {
+            point,
+            control1,
+            control2,
+        });
+    }
+
+    /// Add a quadratic bezier curve to the subpath.
+    pub fn quad_bezier_to(&mut self, (x, y): (f32, f32), (cx, cy): (f32, f32)) {
+        let (x0, y0) = self.current_point().unwrap_or((0.0, 0.0));
+        self.cubic_bezier_to(
+            (x, y),
+            (x0 + 2.0 / 3.0 * (cx - x0), y0 + 2.0 / 3.0 * (cy - y0)),
+            (x + 2.0 / 3.0 * (cx - x), y + 2.0 / 3.0 * (cy - y)),
+        );
+    }
+
+    /// Add an arc to the subpath, tangent to the lines from the current point to `p1` and from `p1` to `p2`.
+    pub fn arc_to(&mut self, (x1, y1): (f32, f32), (x2, y2): (f32, f32), radius: f32) {
+        let (x0, y0) = match self.current_point() {
+            Some(point) => point,
+            None => return,
+        };
+        if points_equal((x0, y0), (x1, y1))
+            || points_equal((x1, y1), (x2, y2))
+            || distance_to_segment_squared((x1, y1), (x0, y0), (x2, y2)) < DIST_TOL * DIST_TOL
+            || radius < DIST_TOL
+        {
+            self.line_to((x1, y1));
+            return;
+        }
+
+        let (dx0, dy0) = normalize((x0 - x1, y0 - y1));
+        let (dx1, dy1) = normalize((x2 - x1, y2 - y1));
+        let a = (dx0 * dx1 + dy0 * dy1).acos();
+        let d = radius / (a / 2.0).tan();
+        if d > 10000.0 {
+            self.line_to((x1, y1));
+            return;
+        }
+
+        if dx1 * dy0 - dx0 * dy1 > 0.0 {
+            let center = (x1 + dx0 * d + dy0 * radius, y1 + dy0 * d - dx0 * radius);
+            let a0 = dx0.atan2(-dy0);
+            let a1 = (-dx1).atan2(dy1);
+            self.arc_with_direction(center, radius, a0, a1, true);
+        } else {
+            let center = (x1 + dx0 * d - dy0 * radius, y1 + dy0 * d + dx0 * radius);
+            let a0 = (-dx0).atan2(dy0);
+            let a1 = dx1.atan2(-dy1);
+            self.arc_with_direction(center, radius, a0, a1, false);
+        }
+    }
+
+    /// Add an arc to the path.
+    /// It starts a new subpath if the path is empty, and is connected to the current point with a line otherwise.
+    pub fn arc(&mut self, center: (f32, f32), radius: f32, start_angle: f32, end_angle: f32, winding: Winding) {
+        let clockwise = winding.into_raw() == ffi::NVGwinding::NVG_CW.bits();
+        self.arc_with_direction(center, radius, start_angle, end_angle, clockwise);
+    }
+
+    fn arc_with_direction(&mut self, (cx, cy): (f32, f32), r: f32, a0: f32, a1: f32, clockwise: bool) {
+        let mut da = a1 - a0;
+        if clockwise {
+            if da.abs() >= PI * 2.0 {
+                da = PI * 2.0;
+            } else {
+                while da < 0.0 {
+                    da += PI * 2.0;
+                }
+            }
+        } else if da.abs() >= PI * 2.0 {
+            da = -PI * 2.0;
+        } else {
+            while da > 0.0 {
+                da -= PI * 2.0;
+            }
+        }
+
+        // Split the arc into up to 5 bezier segments of at most 90 degrees.
+        let divisions = ((da.abs() / (PI * 0.5) + 0.5) as i32).clamp(1, 5);
+        let half = da / divisions as f32 / 2.0;
+        let mut kappa = (4.0 / 3.0 * (1.0 - half.cos()) / half.sin()).abs();
+        if !clockwise {
+            kappa = -kappa;
+        }
+
+        let mut previous = (0.0, 0.0);
+        let mut previous_tangent = (0.0, 0.0);
+        for i in 0..=divisions {
+            let a = a0 + da * (i as f32 / divisions as f32);
+            let (dx, dy) = (a.cos(), a.sin());
+            let point = (cx + dx * r, cy + dy * r);
+            let tangent = (-dy * r * kappa, dx * r * kappa);
+            if i == 0 {
+                if self.segments.is_empty() {
+                    self.move_to(point);
+                } else {
+                    self.line_to(point);
+                }
+            } else {
+                self.cubic_bezier_to(
+                    point,
+                    (previous.0 + previous_tangent.0, previous.1 + previous_tangent.1),
+                    (point.0 - tangent.0, point.1 - tangent.1),
+                );
+            }
+            previous = point;
+            previous_tangent = tangent;
+        }
+    }
+
+    /// Add a rectangle to the path.
+    pub fn rect(&mut self, (x, y): (f32, f32), (w, h): (f32, f32)) {
+        self.move_to((x, y));
+        self.line_to((x, y + h));
+        self.line_to((x + w, y + h));
+        self.line_to((x + w, y));
+        self.close();
+    }
+
+    /// Add a rounded rectangle to the path.
+    pub fn rounded_rect(&mut self, position: (f32, f32), size: (f32, f32), radius: f32) {
+        self.rounded_rect_varying(position, size, (radius, radius), (radius, radius));
+    }
+
+    /// Add a rounded rectangle with varying corners to the path.
+    /// `top_radii` and `bottom_radii` are both tuples in the form (left, right).
+    pub fn rounded_rect_varying(
+        &mut self,
+        (x, y): (f32, f32),
+        (w, h): (f32, f32),
+        (top_left, top_right): (f32, f32),
+        (bottom_left, bottom_right): (f32, f32),
+    ) {
+        if top_left < 0.1 && top_right < 0.1 && bottom_left < 0.1 && bottom_right < 0.1 {
+            self.rect((x, y), (w, h));
+            return;
+        }
+
+        let half_w = w.abs() * 0.5;
+        let half_h = h.abs() * 0.5;
+        let radii = |r: f32| (r.min(half_w) * sign(w), r.min(half_h) * sign(h));
+        let (rx_bl, ry_bl) = radii(bottom_left);
+        let (rx_br, ry_br) = radii(bottom_right);
+        let (rx_tr, ry_tr) = radii(top_right);
+        let (rx_tl, ry_tl) = radii(top_left);
+        let k = 1.0 - KAPPA90;
+
+        self.move_to((x, y + ry_tl));
+        self.line_to((x, y + h - ry_bl));
+        self.cubic_bezier_to((x + rx_bl, y + h), (x, y + h - ry_bl * k), (x + rx_bl * k, y + h));
+        self.line_to((x + w - rx_br, y + h));
+        self.cubic_bezier_to(
+            (x + w, y + h - ry_br),
+            (x + w - rx_br * k, y + h),
+            (x + w, y + h - ry_br * k),
+        );
+        self.line_to((x + w, y + ry_tr));
+        self.cubic_bezier_to((x + w - rx_tr, y), (x + w, y + ry_tr * k), (x + w - rx_tr * k, y));
+        self.line_to((x + rx_tl, y));
+        self.cubic_bezier_to((x, y + ry_tl), (x + rx_tl * k, y), (x, y + ry_tl * k));
+        self.close();
+    }
+
+    /// Add an ellipse to the path.
+    pub fn ellipse(&mut self, (cx, cy): (f32, f32), radius_x: f32, radius_y: f32) {
+        let (rx, ry) = (radius_x, radius_y);
+        self.move_to((cx - rx, cy));
+        self.cubic_bezier_to(
+            (cx, cy + ry),
+            (cx - rx, cy + ry * KAPPA90),
+            (cx - rx * KAPPA90, cy + ry),
+        );
+        self.cubic_bezier_to(
+            (cx + rx, cy),
+            (cx + rx * KAPPA90, cy + ry),
+            (cx + rx, cy + ry * KAPPA90),
+        );
+        self.cubic_bezier_to(
+            (cx, cy - ry),
+            (cx + rx, cy - ry * KAPPA90),
+            (cx + rx * KAPPA90, cy - ry),
+        );
+        self.cubic_bezier_to(
+            (cx - rx, cy),
+            (cx - rx * KAPPA90, cy - ry),
+            (cx - rx, cy - ry * KAPPA90),
+        );
+        self.close();
+    }
+
+    /// Add a circle to the path.
+    pub fn circle(&mut self, center: (f32, f32), radius: f32) {
+        self.ellipse(center, radius, radius);
+    }
+
+    /// Set the winding of the subpath.
+    /// The winding defines which parts of the subpath are 'inside' and which are 'outside'.
+    pub fn winding(&mut self, winding: Winding) {
+        self.segments.push(PathSegment::Winding(winding));
+    }
+
+    /// Close the path, ie. connect the first point and last point with a line.
+    pub fn close(&mut self) {
+        self.segments.push(PathSegment::Close);
+    }
+
+    /// Transform every point of the path by `transform`.
+    pub fn transform(&mut self, transform: &Transform) {
+        for segment in &mut self.segments {
+            *segment = match *segment {
+                PathSegment::MoveTo(point) => PathSegment::MoveTo(transform.transform_point(point)),
+                PathSegment::LineTo(point) => PathSegment::LineTo(transform.transform_point(point)),
+                PathSegment::CubicBezierTo {
+                    point,
+                    control1,
+                    control2,
+                } => PathSegment::CubicBezierTo {
+                    point: transform.transform_point(point),
+                    control1: transform.transform_point(control1),
+                    control2: transform.transform_point(control2),
+                },
+                other => other,
+            };
+        }
+    }
+
+    /// Return a copy of the path with every point transformed by `transform`.
+    pub fn transformed(&self, transform: &Transform) -> PathData {
+        let mut path = self.clone();
+        path.transform(transform);
+        path
+    }
+
+    /// Fill the path into `frame`, see [Path::fill].
+    pub fn fill<T: Paint>(&self, frame: &Frame, paint: T, fill_options: FillOptions, options: PathOptions) {
+        frame.path(
+            |path| {
+                path.append(self);
+                path.fill(paint, fill_options);
+            },
+            options,
+        );
+    }
+
+    /// Stroke the path into `frame`, see [Path::stroke].
+    pub fn stroke<T: Paint>(&self, frame: &Frame, paint: T, stroke_options: StrokeOptions, options: PathOptions) {
+        frame.path(
+            |path| {
+                path.append(self);
+                path.stroke(paint, stroke_options);
+            },
+            options,
+        );
+    }
+
+    /// Approximate every subpath with a polyline whose points are at most `tolerance` away from the curves,
+    /// for example to hit test, snap to or export the geometry NanoVG would tessellate.
+    ///
+    /// Every `move_to` starts a new polyline, and lines after `close` continue from the start of the closed one.
+    /// Repeated points are removed, and closed polylines don't repeat their first point at the end.
+    ///
+    /// Like in NanoVG, curves are split in half at most 10 times, so a curve becomes at most 1024 lines.
+    /// A `tolerance` of zero or less always splits curves that often.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        self.flatten_subpaths(tolerance)
+            .into_iter()
+            .map(|(polyline, _)| polyline)
+            .collect()
+    }
+
+    /// Whether `point` is inside the filled path, with `fill_rule` deciding how overlapping subpaths combine.
+    ///
+    /// The path is drawn with `transform`, and `point` is in the coordinates after it,
+    /// like a mouse position when the transform is that of the frame.
+    pub fn contains_point(&self, point: (f32, f32), fill_rule: FillRule, transform: &Transform) -> bool {
+        let mut winding = 0;
+        for (polyline, hole) in self.transformed(transform).flatten_subpaths(TESS_TOL) {
+            let points = &polyline.points;
+            if points.len() < 3 {
+                continue;
+            }
+            let around = winding_number(points, point);
+            winding += match fill_rule {
+                // NanoVG turns solid subpaths one way and holes the other before filling.
+                FillRule::NonZero if (polygon_area(points) > 0.0) == hole => -around,
+                _ => around,
+            };
+        }
+        match fill_rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// Whether `point` is on the stroke of the path, with the width, caps, joins and dashes of `options`.
+    ///
+    /// Like [PathData::contains_point], `point` is in the coordinates after `transform`,
+    /// which scales the stroke width like NanoVG does.
+    pub fn stroke_contains_point(&self, point: (f32, f32), options: &StrokeOptions, transform: &Transform) -> bool {
+        let half_width = half_stroke_width(options, transform);
+        self.stroked(options)
+            .transformed(transform)
+            .flatten(TESS_TOL)
+            .iter()
+            .any(|polyline| {
+                stroke_pieces(polyline, half_width, options, 0.0)
+                    .iter()
+                    .any(|piece| match *piece {
+                        StrokePiece::Polygon(ref polygon) => convex_polygon_contains(polygon, point),
+                        StrokePiece::Disk(center) => distance(center, point) <= half_width,
+                    })
+            })
+    }
+
+    /// The tight bounds of the path drawn with `transform`, including the extremes of curves.
+    /// Pass `Transform::new()` for the bounds in the coordinates of the path. `None` if the path has no points.
+    pub fn bounds(&self, transform: &Transform) -> Option<Bounds> {
+        let mut points = Vec::new();
+        let mut current = (0.0, 0.0);
+        let mut start = (0.0, 0.0);
+        for segment in &self.transformed(transform).segments {
+            match *segment {
+                PathSegment::MoveTo(point) => {
+                    points.push(point);
+                    current = point;
+                    start = point;
+                }
+                PathSegment::LineTo(point) => {
+                    points.push(point);
+                    current = point;
+                }
+                PathSegment::CubicBezierTo {
+                    point,
+                    control1,
+                    control2,
+                } => {
+                    for t in cubic_extremes(current, control1, control2, point) {
+                        points.push(cubic_point(current, control1, control2, point, t));
+                    }
+                    points.push(point);
+                    current = point;
+                }
+                PathSegment::Close => current = start,
+                PathSegment::Winding(_) => {}
+            }
+        }
+        Bounds::from_points(points)
+    }
+
+    /// The bounds of the outline stroked with `options` and drawn with `transform`, like [PathData::bounds].
+    /// `None` if the stroke is empty.
+    pub fn stroke_bounds(&self, options: &StrokeOptions, transform: &Transform) -> Option<Bounds> {
+        let half_width = half_stroke_width(options, transform);
+        let mut points = Vec::new();
+        // Curves are flattened at their extremes, where the stroke reaches furthest.
+        for polyline in self
+            .stroked(options)
+            .transformed(transform)
+            .split_at_extremes()
+            .flatten(TESS_TOL)
+        {
+            for piece in stroke_pieces(&polyline, half_width, options, 0.0) {
+                match piece {
+                    StrokePiece::Polygon(polygon) => points.extend(polygon),
+                    StrokePiece::Disk((x, y)) => {
+                        points.push((x - half_width, y - half_width));
+                        points.push((x + half_width, y + half_width));
+                    }
+                }
+            }
+        }
+        Bounds::from_points(points)
+    }
+
+    /// The outline of the stroke with `options` as a path to fill, like NanoVG strokes it.
+    ///
+    /// Curves and round caps and joins are flattened to within `tolerance`, and the parts of the stroke are merged
+    /// with [PathData::union], so the outline has no overlaps and fills the same with any paint or fill rule.
+    /// Unlike drawing, the width isn't limited to 200.
+    pub fn stroke_outline(&self, options: &StrokeOptions, tolerance: f32) -> PathData {
+        let half_width = options.width.max(0.0) * 0.5;
+        let mut pieces = PathData::new();
+        for polyline in self.stroked(options).flatten(tolerance) {
+            for piece in stroke_pieces(&polyline, half_width, options, tolerance) {
+                match piece {
+                    StrokePiece::Polygon(polygon) => {
+                        pieces.move_to(polygon[0]);
+                        for &point in &polygon[1..] {
+                            pieces.line_to(point);
+                        }
+                        pieces.close();
+                    }
+                    StrokePiece::Disk(center) => pieces.circle(center, half_width),
+                }
+            }
+        }
+        pieces.union(&PathData::new(), tolerance)
+    }
+
+    /// The path a stroke with `options` draws, its dashes if it is dashed.
+    fn stroked(&self, options: &StrokeOptions) -> Cow<'_, PathData> {
+        if options.dash_array.is_empty() {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.dashed(&options.dash_array, options.dash_offset))
+        }
+    }
+
+    /// Split every curve where it turns horizontally or vertically.
+    fn split_at_extremes(&self) -> PathData {
+        let mut path = PathData::new();
+        let mut current = (0.0, 0.0);
+        let mut start = (0.0, 0.0);
+        for segment in &self.segments {
+            if let PathSegment::CubicBezierTo {
+                point,
+                control1,
+                control2,
+            } = *segment
+            {
+                let mut curve = (current, control1, control2, point);
+                let mut split = 0.0;
+                for t in cubic_extremes(current, control1, control2, point) {
+                    // Split the rest of the curve at the same point of the original one.
+                    let (first, rest) = split_cubic(curve, (t - split) / (1.0 - split));
+                    path.cubic_bezier_to(first.3, first.1, first.2);
+                    curve = rest;
+                    split = t;
+                }
+                path.cubic_bezier_to(curve.3, curve.1, curve.2);
+            } else {
+                path.push(*segment);
+            }
+            match *segment {
+                PathSegment::MoveTo(point) => {
+                    current = point;
+                    start = point;
+                }
+                PathSegment::LineTo(point) | PathSegment::CubicBezierTo { point, .. } => current = point,
+                PathSegment::Close => current = start,
+                PathSegment::Winding(_) => {}
+            }
+        }
+        path
+    }
+
+    /// Like [PathData::flatten], along with whether each subpath was made a hole with [PathData::winding].
+    pub(crate) fn flatten_subpaths(&self, tolerance: f32) -> Vec<(Polyline, bool)> {
+        // Only the square of the tolerance is used, so don't let a negative one pass for a positive one.
+        let tolerance = tolerance.max(0.0);
+        let mut polylines: Vec<(Polyline, bool)> = Vec::new();
+        let mut current = (0.0, 0.0);
+        let mut start = (0.0, 0.0);
+        let mut open = false;
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo(point) => {
+                    polylines.push((
+                        Polyline {
+                            points: vec![point],
+                            closed: false,
+                        },
+                        false,
+                    ));
+                    current = point;
+                    start = point;
+                    open = true;
+                }
+                PathSegment::LineTo(point) | PathSegment::CubicBezierTo { point, .. } => {
+                    if !open {
+                        polylines.push((
+                            Polyline {
+                                points: vec![start],
+                                closed: false,
+                            },
+                            false,
+                        ));
+                        current = start;
+                        open = true;
+                    }
+                    let points = &mut polylines.last_mut().unwrap().0.points;
+                    if let PathSegment::CubicBezierTo { control1, control2, .. } = *segment {
+                        flatten_bezier(points, current, control1, control2, point, tolerance, 0);
+                    } else {
+                        points.push(point);
+                    }
+                    current = point;
+                }
+                PathSegment::Close => {
+                    if open {
+                        polylines.last_mut().unwrap().0.closed = true;
+                        current = start;
+                        open = false;
+                    }
+                }
+                PathSegment::Winding(winding) => {
+                    if let Some(last) = polylines.last_mut() {
+                        last.1 = is_hole(winding);
+                    }
+                }
+            }
+        }
+
+        for &mut (ref mut polyline, _) in &mut polylines {
+            let points = &mut polyline.points;
+            points.dedup_by(|a, b| points_equal(*a, *b));
+            if polyline.closed && points.len() > 1 && points_equal(points[0], points[points.len() - 1]) {
+                points.pop();
+            }
+        }
+        polylines
+    }
+
+    /// Add the segments to the current NanoVG path of `path`.
+    pub(crate) fn add_to(&self, path: &Path) {
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo(point) => path.move_to(point),
+                PathSegment::LineTo(point) => path.line_to(point),
+                PathSegment::CubicBezierTo {
+                    point,
+                    control1,
+                    control2,
+                } => path.cubic_bezier_to(point, control1, control2),
+                PathSegment::Winding(winding) => path.winding(winding),
+                PathSegment::Close => path.close(),
+            }
+        }
+    }
+}
+
+/// Subdivide a cubic bezier curve until it is flat, with NanoVG's flatness test:
+/// the distances of the control points from the chord add up to less than `tolerance`.
+/// Parts still curved after 10 subdivisions are taken as flat.
+fn flatten_bezier(
+    points: &mut Vec<(f32, f32)>,
+    (x1, y1): (f32, f32),
+    (x2, y2): (f32, f32),
+    (x3, y3): (f32, f32),
+    (x4, y4): (f32, f32),
+    tolerance: f32,
+    level: u32,
+) {
+    let (dx, dy) = (x4 - x1, y4 - y1);
+    let d2 = ((x2 - x4) * dy - (y2 - y4) * dx).abs();
+    let d3 = ((x3 - x4) * dy - (y3 - y4) * dx).abs();
+    if level >= 10 || (d2 + d3) * (d2 + d3) <= tolerance * tolerance * (dx * dx + dy * dy) {
+        points.push((x4, y4));
+        return;
+    }
+
+    let (x12, y12) = ((x1 + x2) * 0.5, (y1 + y2) * 0.5);
+    let (x23, y23) = ((x2 + x3) * 0.5, (y2 + y3) * 0.5);
+    let (x34, y34) = ((x3 + x4) * 0.5, (y3 + y4) * 0.5);
+    let (x123, y123) = ((x12 + x23) * 0.5, (y12 + y23) * 0.5);
+    let (x234, y234) = ((x23 + x34) * 0.5, (y23 + y34) * 0.5);
+    let (x1234, y1234) = ((x123 + x234) * 0.5, (y123 + y234) * 0.5);
+    flatten_bezier(
+        points,
+        (x1, y1),
+        (x12, y12),
+        (x123, y123),
+        (x1234, y1234),
+        tolerance,
+        level + 1,
+    );
+    flatten_bezier(
+        points,
+        (x1234, y1234),
+        (x234, y234),
+        (x34, y34),
+        (x4, y4),
+        tolerance,
+        level + 1,
+    );
+}
+
+/// The parameters in `0..1` where a cubic bezier curve turns horizontally or vertically, in increasing order.
+fn cubic_extremes(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) -> Vec<f32> {
+    let mut extremes = Vec::new();
+    for &(v0, v1, v2, v3) in &[(p0.0, p1.0, p2.0, p3.0), (p0.1, p1.1, p2.1, p3.1)] {
+        // The derivative is a quadratic in t: a t^2 + b t + c.
+        let (d0, d1, d2) = (v1 - v0, v2 - v1, v3 - v2);
+        let (a, b, c) = (d0 - 2.0 * d1 + d2, 2.0 * (d1 - d0), d0);
+        if a.abs() < 1e-12 {
+            if b.abs() > 1e-12 {
+                extremes.push(-c / b);
+            }
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                extremes.push((-b + root) / (2.0 * a));
+                extremes.push((-b - root) / (2.0 * a));
+            }
+        }
+    }
+    extremes.retain(|&t| t > 1e-6 && t < 1.0 - 1e-6);
+    extremes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    extremes.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    extremes
+}
+
+pub(crate) fn cubic_point(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32), t: f32) -> (f32, f32) {
+    let u = 1.0 - t;
+    let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    (
+        w0 * p0.0 + w1 * p1.0 + w2 * p2.0 + w3 * p3.0,
+        w0 * p0.1 + w1 * p1.1 + w2 * p2.1 + w3 * p3.1,
+    )
+}
+
+pub(crate) type Cubic = ((f32, f32), (f32, f32), (f32, f32), (f32, f32));
+
+/// Split a cubic bezier curve at `t` with de Casteljau's algorithm.
+pub(crate) fn split_cubic((p0, p1, p2, p3): Cubic, t: f32) -> (Cubic, Cubic) {
+    let lerp = |a: (f32, f32), b: (f32, f32)| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+    let (p01, p12, p23) = (lerp(p0, p1), lerp(p1, p2), lerp(p2, p3));
+    let (p012, p123) = (lerp(p01, p12), lerp(p12, p23));
+    let p0123 = lerp(p012, p123);
+    ((p0, p01, p012, p0123), (p0123, p123, p23, p3))
+}
+
+/// The winding number of the closed polygon `points` around `point`.
+pub(crate) fn winding_number(points: &[(f32, f32)], (x, y): (f32, f32)) -> i32 {
+    let mut winding = 0;
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        let side = (x2 - x1) * (y - y1) - (x - x1) * (y2 - y1);
+        if y1 <= y {
+            if y2 > y && side > 0.0 {
+                winding += 1;
+            }
+        } else if y2 <= y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// Twice the signed area of the closed polygon `points`, positive if it turns clockwise on screen.
+pub(crate) fn polygon_area(points: &[(f32, f32)]) -> f32 {
+    let mut area = 0.0;
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    area
+}
+
+fn is_hole(winding: Winding) -> bool {
+    matches!(
+        winding,
+        Winding::Direction(Direction::Clockwise) | Winding::Solidity(Solidity::Hole)
+    )
+}
+
+/// A convex part of a stroke outline.
+enum StrokePiece {
+    Polygon(Vec<(f32, f32)>),
+    /// A disk of the stroke width, for round caps and joins.
+    Disk((f32, f32)),
+}
+
+/// Split the stroke of a polyline into the rectangles along its lines, the caps and the outer side of the joins,
+/// like `nvg__expandStroke`. Round joins that are within `round_tolerance` of a bevel are beveled.
+fn stroke_pieces(
+    polyline: &Polyline,
+    half_width: f32,
+    options: &StrokeOptions,
+    round_tolerance: f32,
+) -> Vec<StrokePiece> {
+    let mut pieces = Vec::new();
+    let points = &polyline.points;
+    let count = points.len();
+    if count < 2 {
+        return pieces;
+    }
+    let open = !polyline.closed;
+    let segments = if open { count - 1 } else { count };
+    let cap_length = if open && options.line_cap == LineCap::Square {
+        half_width
+    } else {
+        0.0
+    };
+    for i in 0..segments {
+        let (a, b) = (points[i], points[(i + 1) % count]);
+        let (ux, uy) = normalize((b.0 - a.0, b.1 - a.1));
+        let start = if i == 0 { cap_length } else { 0.0 };
+        let end = if i == segments - 1 { cap_length } else { 0.0 };
+        let (a, b) = ((a.0 - ux * start, a.1 - uy * start), (b.0 + ux * end, b.1 + uy * end));
+        let (nx, ny) = (-uy * half_width, ux * half_width);
+        pieces.push(StrokePiece::Polygon(vec![
+            (a.0 + nx, a.1 + ny),
+            (b.0 + nx, b.1 + ny),
+            (b.0 - nx, b.1 - ny),
+            (a.0 - nx, a.1 - ny),
+        ]));
+    }
+    if open && options.line_cap == LineCap::Round {
+        pieces.push(StrokePiece::Disk(points[0]));
+        pieces.push(StrokePiece::Disk(points[count - 1]));
+    }
+    let joins = if open { 1..count - 1 } else { 0..count };
+    for i in joins {
+        let vertex = points[i];
+        let d0 = normalize((
+            vertex.0 - points[(i + count - 1) % count].0,
+            vertex.1 - points[(i + count - 1) % count].1,
+        ));
+        let d1 = normalize((
+            points[(i + 1) % count].0 - vertex.0,
+            points[(i + 1) % count].1 - vertex.1,
+        ));
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        if options.line_join == LineJoin::Round {
+            // How far the arc bulges past the bevel.
+            let cos_half_turn = ((1.0 + d0.0 * d1.0 + d0.1 * d1.1) * 0.5).max(0.0).sqrt();
+            if half_width * (1.0 - cos_half_turn) > round_tolerance {
+                pieces.push(StrokePiece::Disk(vertex));
+                continue;
+            }
+        }
+        if cross.abs() < 1e-6 {
+            continue;
+        }
+        // The outer side is away from the turn.
+        let side = if cross > 0.0 { -half_width } else { half_width };
+        let offset0 = (vertex.0 - d0.1 * side, vertex.1 + d0.0 * side);
+        let offset1 = (vertex.0 - d1.1 * side, vertex.1 + d1.0 * side);
+        let miter = ((-d0.1 - d1.1) * 0.5, (d0.0 + d1.0) * 0.5);
+        let miter_squared = miter.0 * miter.0 + miter.1 * miter.1;
+        if options.line_join == LineJoin::Miter && miter_squared * options.miter_limit * options.miter_limit >= 1.0 {
+            let tip = (
+                vertex.0 + miter.0 * side / miter_squared,
+                vertex.1 + miter.1 * side / miter_squared,
+            );
+            pieces.push(StrokePiece::Polygon(vec![vertex, offset0, tip, offset1]));
+        } else {
+            pieces.push(StrokePiece::Polygon(vec![vertex, offset0, offset1]));
+        }
+    }
+    pieces
+}
+
+/// The stroke width NanoVG uses with `transform`, halved.
+fn half_stroke_width(options: &StrokeOptions, transform: &Transform) -> f32 {
+    let [a, b, c, d, _, _] = transform.matrix;
+    let scale = ((a * a + b * b).sqrt() + (c * c + d * d).sqrt()) * 0.5;
+    (options.width * scale).clamp(0.0, 200.0) * 0.5
+}
+
+fn convex_polygon_contains(polygon: &[(f32, f32)], (x, y): (f32, f32)) -> bool {
+    let mut sign = 0.0;
+    for (i, &(x1, y1)) in polygon.iter().enumerate() {
+        let (x2, y2) = polygon[(i + 1) % polygon.len()];
+        let side = (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+        if side != 0.0 {
+            if sign == 0.0 {
+                sign = side.signum();
+            } else if side.signum() != sign {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+pub(crate) fn distance((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> f32 {
+    (x2 - x1).hypot(y2 - y1)
+}
+
+fn segment_point(segment: &PathSegment) -> Option<(f32, f32)> {
+    match *segment {
+        PathSegment::MoveTo(point) | PathSegment::LineTo(point) | PathSegment::CubicBezierTo { point, .. } => {
+            Some(point)
+        }
+        PathSegment::Winding(_) | PathSegment::Close => None,
+    }
+}
+
+fn sign(value: f32) -> f32 {
+    if value >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+pub(crate) fn normalize((x, y): (f32, f32)) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length > 1e-6 {
+        (x / length, y / length)
+    } else {
+        (x, y)
+    }
+}
+
+pub(crate) fn points_equal((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> bool {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    dx * dx + dy * dy < DIST_TOL * DIST_TOL
+}
+
+fn distance_to_segment_squared((x, y): (f32, f32), (px, py): (f32, f32), (qx, qy): (f32, f32)) -> f32 {
+    let (pqx, pqy) = (qx - px, qy - py);
+    let (dx, dy) = (x - px, y - py);
+    let d = pqx * pqx + pqy * pqy;
+    let mut t = pqx * dx + pqy * dy;
+    if d > 0.0 {
+        t /= d;
+    }
+    let t = t.clamp(0.0, 1.0);
+    let (dx, dy) = (px + t * pqx - x, py + t * pqy - y);
+    dx * dx + dy * dy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction;
+
+    #[test]
+    fn test_shapes_match_nanovg() {
+        let mut path = PathData::new();
+        path.rect((1.0, 2.0), (3.0, 4.0));
+        assert_eq!(
+            path.segments(),
+            &[
+                PathSegment::MoveTo((1.0, 2.0)),
+                PathSegment::LineTo((1.0, 6.0)),
+                PathSegment::LineTo((4.0, 6.0)),
+                PathSegment::LineTo((4.0, 2.0)),
+                PathSegment::Close,
+            ]
+        );
+
+        // A full circle arc is 4 quarter curves, ending where it started.
+        let mut arc = PathData::new();
+        arc.arc(
+            (0.0, 0.0),
+            10.0,
+            0.0,
+            PI * 2.0,
+            Winding::Direction(Direction::Clockwise),
+        );
+        assert_eq!(arc.segments().len(), 5);
+        let (x, y) = arc.current_point().unwrap();
+        assert!((x - 10.0).abs() < 1e-4 && y.abs() < 1e-4);
+
+        // The corner of a tangent arc is replaced by a quarter circle.
+        let mut corner = PathData::new();
+        corner.move_to((0.0, 0.0));
+        corner.arc_to((10.0, 0.0), (10.0, 10.0), 5.0);
+        match corner.segments()[1] {
+            PathSegment::LineTo((x, y)) => assert!((x - 5.0).abs() < 1e-4 && y.abs() < 1e-4),
+            other => panic!("expected a line, got {:?}", other),
+        }
+        let (x, y) = corner.current_point().unwrap();
+        assert!((x - 10.0).abs() < 1e-4 && (y - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut path = PathData::new();
+        path.circle((10.0, 10.0), 100.0);
+        path.move_to((0.0, 0.0));
+        path.line_to((0.0, 0.0));
+        path.line_to((5.0, 0.0));
+        let polylines = path.flatten(0.1);
+        assert_eq!(polylines.len(), 2);
+        assert!(polylines[0].closed);
+        assert!(polylines[0].points.len() > 16);
+        for &(x, y) in &polylines[0].points {
+            let radius = ((x - 10.0).powi(2) + (y - 10.0).powi(2)).sqrt();
+            assert!((radius - 100.0).abs() < 0.1);
+        }
+        assert_eq!(
+            polylines[1],
+            Polyline {
+                points: vec![(0.0, 0.0), (5.0, 0.0)],
+                closed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_flatten_without_tolerance() {
+        let mut path = PathData::new();
+        path.circle((0.0, 0.0), 100.0);
+        for &tolerance in &[0.0, -1.0, 1e-9] {
+            let polylines = path.flatten(tolerance);
+            assert_eq!(polylines.len(), 1);
+            // Four quarter curves split into 1024 lines each, less the repeated start point.
+            assert_eq!(polylines[0].points.len(), 4 * 1024);
+            for &(x, y) in &polylines[0].points {
+                assert!(((x * x + y * y).sqrt() - 100.0).abs() < 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let mut path = PathData::new();
+        path.rect((0.0, 0.0), (10.0, 10.0));
+        path.rect((3.0, 3.0), (4.0, 4.0));
+        let identity = Transform::new();
+        // Subpaths are solid unless made holes, whatever their direction.
+        assert!(path.contains_point((5.0, 5.0), FillRule::NonZero, &identity));
+        assert!(!path.contains_point((5.0, 5.0), FillRule::EvenOdd, &identity));
+        path.winding(Winding::Solidity(Solidity::Hole));
+        assert!(!path.contains_point((5.0, 5.0), FillRule::NonZero, &identity));
+        assert!(path.contains_point((1.0, 5.0), FillRule::NonZero, &identity));
+        assert!(!path.contains_point((11.0, 5.0), FillRule::NonZero, &identity));
+
+        let moved = Transform::new().with_translation(100.0, 0.0);
+        assert!(path.contains_point((101.0, 5.0), FillRule::NonZero, &moved));
+        assert!(!path.contains_point((1.0, 5.0), FillRule::NonZero, &moved));
+    }
+
+    #[test]
+    fn test_stroke_contains_point() {
+        let mut path = PathData::new();
+        path.move_to((0.0, 0.0));
+        path.line_to((10.0, 0.0));
+        path.line_to((10.0, 10.0));
+        let identity = Transform::new();
+        let mut options = StrokeOptions {
+            width: 2.0,
+            ..Default::default()
+        };
+        assert!(path.stroke_contains_point((5.0, 0.9), &options, &identity));
+        assert!(!path.stroke_contains_point((5.0, 1.1), &options, &identity));
+        assert!(!path.stroke_contains_point((-0.5, 0.0), &options, &identity));
+        assert!(path.stroke_contains_point((10.9, -0.9), &options, &identity));
+        assert!(path.stroke_contains_point((10.0, 20.0), &options, &Transform::new().scale(1.0, 2.0)));
+
+        options.line_cap = LineCap::Square;
+        assert!(path.stroke_contains_point((-0.5, 0.5), &options, &identity));
+        options.line_cap = LineCap::Round;
+        assert!(path.stroke_contains_point((-0.6, 0.6), &options, &identity));
+        assert!(!path.stroke_contains_point((-0.9, 0.9), &options, &identity));
+        options.line_join = LineJoin::Bevel;
+        assert!(!path.stroke_contains_point((10.9, -0.9), &options, &identity));
+        assert!(path.stroke_contains_point((10.4, -0.4), &options, &identity));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let identity = Transform::new();
+        assert_eq!(PathData::new().bounds(&identity), None);
+
+        let mut path = PathData::new();
+        path.move_to((0.0, 0.0));
+        path.cubic_bezier_to((10.0, 0.0), (0.0, 10.0), (10.0, 10.0));
+        let bounds = path.bounds(&identity).unwrap();
+        assert_eq!((bounds.min_x, bounds.min_y, bounds.max_x), (0.0, 0.0, 10.0));
+        assert!((bounds.max_y - 7.5).abs() < 1e-5);
+        let scaled = path.bounds(&Transform::new().scale(2.0, 2.0)).unwrap();
+        assert!((scaled.height() - 15.0).abs() < 1e-5);
+
+        let mut circle = PathData::new();
+        circle.circle((5.0, 5.0), 5.0);
+        assert_eq!(
+            circle.bounds(&identity),
+            Some(Bounds {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 10.0,
+                max_y: 10.0,
+            })
+        );
+
+        let mut corner = PathData::new();
+        corner.move_to((0.0, 0.0));
+        corner.line_to((10.0, 0.0));
+        corner.line_to((10.0, 10.0));
+        let mut options = StrokeOptions {
+            width: 2.0,
+            ..Default::default()
+        };
+        let stroke = |options: &StrokeOptions| {
+            let bounds = corner.stroke_bounds(options, &identity).unwrap();
+            (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y)
+        };
+        assert_eq!(stroke(&options), (0.0, -1.0, 11.0, 10.0));
+        options.line_cap = LineCap::Square;
+        assert_eq!(stroke(&options), (-1.0, -1.0, 11.0, 11.0));
+
+        // The stroke of a curve reaches past its extremes, a little more at the corners of the flattened curve.
+        let bounds = circle.stroke_bounds(&options, &identity).unwrap();
+        assert!((bounds.min_x + 1.0).abs() < 0.01 && (bounds.max_y - 11.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_stroke_outline() {
+        let mut corner = PathData::new();
+        corner.move_to((0.0, 0.0));
+        corner.line_to((10.0, 0.0));
+        corner.line_to((10.0, 10.0));
+        let options = StrokeOptions {
+            width: 2.0,
+            line_cap: LineCap::Round,
+            ..Default::default()
+        };
+        let identity = Transform::new();
+        let outline = corner.stroke_outline(&options, 0.01);
+        assert_eq!(outline.flatten(0.01).len(), 1);
+        for &point in &[(10.9, -0.9), (9.5, 0.5), (-0.9, 0.0), (5.0, 0.9), (10.0, 10.9)] {
+            assert!(outline.contains_point(point, FillRule::NonZero, &identity));
+        }
+        for &point in &[(11.1, -0.5), (8.5, 1.5), (-0.9, 0.9), (5.0, 1.1)] {
+            assert!(!outline.contains_point(point, FillRule::NonZero, &identity));
+        }
+        let bounds = outline.bounds(&identity).unwrap();
+        assert_eq!((bounds.min_y, bounds.max_x), (-1.0, 11.0));
+        assert!((bounds.min_x + 1.0).abs() < 0.01 && (bounds.max_y - 11.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_transformed() {
+        let mut path = PathData::new();
+        path.move_to((1.0, 0.0));
+        path.quad_bezier_to((3.0, 0.0), (2.0, 3.0));
+        path.close();
+        let moved = path.transformed(&Transform::new().with_translation(10.0, 20.0));
+        assert_eq!(moved.segments()[0], PathSegment::MoveTo((11.0, 20.0)));
+        assert_eq!(moved.segments()[2], PathSegment::Close);
+        match moved.segments()[1] {
+            PathSegment::CubicBezierTo {
+                point,
+                control1,
+                control2,
+            } => {
+                let close =
+                    |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| (x1 - x2).abs() < 1e-4 && (y1 - y2).abs() < 1e-4;
+                assert!(close(point, (13.0, 20.0)));
+                assert!(close(control1, (11.0 + 2.0 / 3.0, 22.0)));
+                assert!(close(control2, (13.0 - 2.0 / 3.0, 22.0)));
+            }
+            other => panic!("expected a curve, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! A minimal PNG encoder for 8-bit RGBA images.
+//!
+//! Used to embed textures into exported documents and to save screenshots without pulling in
+//! an image crate. Compression uses fixed Huffman codes with a small LZ77 matcher, which is
+//! far from optimal but keeps flat UI graphics reasonably small.
+
+use std::io::{self, Write};
+
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+const WINDOW: usize = 32768;
+const HASH_BITS: usize = 15;
+const MAX_CHAIN: usize = 32;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    /// Write `count` bits of `value`, least significant bit first.
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which is stored most significant bit first.
+    fn write_code(&mut self, code: u32, length: u32) {
+        let mut reversed = 0;
+        for i in 0..length {
+            reversed |= ((code >> i) & 1) << (length - 1 - i);
+        }
+        self.write(reversed, length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+fn write_literal(out: &mut BitWriter, value: u16) {
+    match value {
+        0..=143 => out.write_code(0x30 + value as u32, 8),
+        144..=255 => out.write_code(0x190 + (value as u32 - 144), 9),
+        256..=279 => out.write_code(value as u32 - 256, 7),
+        _ => out.write_code(0xc0 + (value as u32 - 280), 8),
+    }
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap();
+    write_literal(out, 257 + code as u16);
+    out.write((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+
+    let code = DISTANCE_BASE
+        .iter()
+        .rposition(|&base| base as usize <= distance)
+        .unwrap();
+    out.write_code(code as u32, 5);
+    out.write(
+        (distance - DISTANCE_BASE[code] as usize) as u32,
+        DISTANCE_EXTRA[code] as u32,
+    );
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+    let value = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+    (value.wrapping_mul(2_654_435_761) >> 8) & ((1 << HASH_BITS) - 1)
+}
+
+/// Compress `data` into a single fixed Huffman deflate block.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::new();
+    out.write(1, 1); // final block
+    out.write(1, 2); // fixed Huffman codes
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let mut i = 0;
+    while i < data.len() {
+        let mut best = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(data, i);
+            let mut candidate = head[h];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let limit = MAX_MATCH.min(data.len() - i);
+                let mut length = 0;
+                while length < limit && data[candidate + length] == data[i + length] {
+                    length += 1;
+                }
+                if length > best.0 {
+                    best = (length, i - candidate);
+                    if length == limit {
+                        break;
+                    }
+                }
+                candidate = prev[candidate];
+                chain += 1;
+            }
+            prev[i] = head[h];
+            head[h] = i;
+        }
+
+        if best.0 >= MIN_MATCH {
+            write_match(&mut out, best.0, best.1);
+            let end = (i + best.0).min(data.len().saturating_sub(MIN_MATCH - 1));
+            for (j, slot) in prev.iter_mut().enumerate().take(end).skip(i + 1) {
+                let h = hash(data, j);
+                *slot = head[h];
+                head[h] = j;
+            }
+            i += best.0;
+        } else {
+            write_literal(&mut out, data[i] as u16);
+            i += 1;
+        }
+    }
+    write_literal(&mut out, 256);
+    out.finish()
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Wrap `data` in a zlib stream.
+pub(crate) fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filter every scanline with the filter type producing the smallest sum of absolute values.
+fn filter(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let stride = width * 4;
+    let mut out = Vec::with_capacity((stride + 1) * height);
+    let zero = vec![0; stride];
+    let mut candidates = vec![vec![0u8; stride]; 5];
+    for y in 0..height {
+        let row = &rgba[y * stride..(y + 1) * stride];
+        let above = if y > 0 {
+            &rgba[(y - 1) * stride..y * stride]
+        } else {
+            &zero[..]
+        };
+        for x in 0..stride {
+            let left = if x >= 4 { row[x - 4] } else { 0 };
+            let upper_left = if x >= 4 { above[x - 4] } else { 0 };
+            candidates[0][x] = row[x];
+            candidates[1][x] = row[x].wrapping_sub(left);
+            candidates[2][x] = row[x].wrapping_sub(above[x]);
+            candidates[3][x] = row[x].wrapping_sub(((left as u16 + above[x] as u16) / 2) as u8);
+            candidates[4][x] = row[x].wrapping_sub(paeth(left, above[x], upper_left));
+        }
+        let cost = |line: &Vec<u8>| line.iter().map(|&v| (v as i8).unsigned_abs() as u32).sum::<u32>();
+        let best = (0..5).min_by_key(|&f| cost(&candidates[f])).unwrap();
+        out.push(best as u8);
+        out.extend_from_slice(&candidates[best]);
+    }
+    out
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut crc_data = kind.to_vec();
+    crc_data.extend_from_slice(data);
+    out.write_all(&crc_data)?;
+    out.write_all(&crc32(&crc_data).to_be_bytes())
+}
+
+/// Encode straight alpha RGBA pixels, row by row from the top, as PNG.
+pub(crate) fn write_rgba<W: Write>(out: &mut W, width: usize, height: usize, rgba: &[u8]) -> io::Result<()> {
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bit depth, RGBA, deflate, no interlace
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib(&filter(width, height, &rgba[..width * height * 4])))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+/// Encode straight alpha RGBA pixels as PNG into memory.
+pub(crate) fn encode_rgba(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_rgba(&mut out, width, height, rgba).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
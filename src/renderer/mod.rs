@@ -0,0 +1,693 @@
+//! Pluggable rendering backends.
+//!
+//! NanoVG itself only tessellates paths; the actual drawing is done by a backend which is handed
+//! the resulting vertex data. The OpenGL backends selected by cargo feature are written in C,
+//! but any type implementing [Renderer] can be plugged in with [ContextBuilder::build_with_renderer].
+//!
+//! [ContextBuilder::build_with_renderer]: ../struct.ContextBuilder.html#method.build_with_renderer
+
+use ffi;
+use std::any::{Any, TypeId};
+use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
+use std::os::raw::{c_float, c_int, c_uchar, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use {BlendFactor, Color, Direction, PathData, StrokeOptions, Transform};
+
+mod pdf;
+mod recording;
+mod software;
+mod svg;
+mod truetype;
+
+pub use self::pdf::PdfRenderer;
+pub use self::recording::{
+    DrawCall, RecordedFont, RecordedFrame, RecordedPath, RecordedShape, RecordedText, RecordedTexture,
+    RecordingRenderer,
+};
+pub use self::software::SoftwareRenderer;
+pub use self::svg::{frame_to_svg, SvgRenderer};
+
+/// Pixel format of a texture requested by NanoVG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureType {
+    /// One byte per pixel, used for the font atlas.
+    Alpha,
+    /// Four bytes per pixel in RGBA order, used for images.
+    Rgba,
+}
+
+impl TextureType {
+    fn from_raw(raw: c_int) -> Self {
+        if raw == ffi::NVGtexture::NVG_TEXTURE_ALPHA as c_int {
+            TextureType::Alpha
+        } else {
+            TextureType::Rgba
+        }
+    }
+
+    /// The number of bytes a single pixel of this type occupies.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            TextureType::Alpha => 1,
+            TextureType::Rgba => 4,
+        }
+    }
+}
+
+/// Flags a texture was created with. Mirrors the flags of [ImageBuilder](../struct.ImageBuilder.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageFlags {
+    pub generate_mipmaps: bool,
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    pub flip_y: bool,
+    pub premultiplied: bool,
+    pub nearest: bool,
+}
+
+impl ImageFlags {
+    pub(crate) fn from_raw(raw: c_int) -> Self {
+        let flags = ffi::NVGimageFlags::from_bits_truncate(raw);
+        ImageFlags {
+            generate_mipmaps: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_GENERATE_MIPMAPS),
+            repeat_x: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_REPEATX),
+            repeat_y: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_REPEATY),
+            flip_y: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_FLIPY),
+            premultiplied: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_PREMULTIPLIED),
+            nearest: flags.contains(ffi::NVGimageFlags::NVG_IMAGE_NEAREST),
+        }
+    }
+}
+
+/// A single vertex produced by the NanoVG tessellator.
+/// `(u, v)` is used for antialiasing in fills and strokes and as texture coordinate for triangles.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+    pub x: f32,
+    pub y: f32,
+    pub u: f32,
+    pub v: f32,
+}
+
+/// The tessellated form of a single sub-path.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderPath<'a> {
+    /// Triangle fan covering the interior of the sub-path.
+    pub fill: &'a [Vertex],
+    /// Triangle strip covering the outline (or the antialiased fringe when filling).
+    pub stroke: &'a [Vertex],
+    /// Whether the sub-path is closed.
+    pub closed: bool,
+    /// Whether the sub-path is convex, which allows filling it without the stencil buffer.
+    pub convex: bool,
+    /// The winding direction of the sub-path.
+    pub winding: Direction,
+    /// Number of bevel joins in the sub-path.
+    pub bevel_count: usize,
+}
+
+/// Paint used by a draw call, already evaluated into the form NanoVG uses internally.
+///
+/// Colors are not premultiplied, their alpha already includes the global alpha of the path.
+/// `transform` maps paint space into path space, `extent`, `radius` and `feather`
+/// describe the gradient box as in the NanoVG shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderPaint {
+    pub transform: Transform,
+    pub extent: (f32, f32),
+    pub radius: f32,
+    pub feather: f32,
+    pub inner_color: Color,
+    pub outer_color: Color,
+    /// Texture to sample from, as returned by [Renderer::create_texture].
+    pub image: Option<usize>,
+}
+
+impl RenderPaint {
+    fn from_raw(raw: &ffi::NVGpaint) -> Self {
+        RenderPaint {
+            transform: Transform {
+                matrix: raw.xform,
+                absolute: false,
+            },
+            extent: (raw.extent[0], raw.extent[1]),
+            radius: raw.radius,
+            feather: raw.feather,
+            inner_color: Color(raw.innerColor),
+            outer_color: Color(raw.outerColor),
+            image: if raw.image > 0 { Some(raw.image as usize) } else { None },
+        }
+    }
+}
+
+/// Scissor rectangle applied to a draw call.
+/// `transform` maps scissor space to path space, the scissor rectangle is centered
+/// at the origin of scissor space and reaches `extent` in each direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderScissor {
+    pub transform: Transform,
+    pub extent: (f32, f32),
+}
+
+impl RenderScissor {
+    fn from_raw(raw: &ffi::NVGscissor) -> Option<Self> {
+        if raw.extent[0] < -0.5 || raw.extent[1] < -0.5 {
+            None
+        } else {
+            Some(RenderScissor {
+                transform: Transform {
+                    matrix: raw.xform,
+                    absolute: false,
+                },
+                extent: (raw.extent[0], raw.extent[1]),
+            })
+        }
+    }
+}
+
+/// Blend factors of a draw call, resolved from the active [CompositeOperation](../enum.CompositeOperation.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompositeState {
+    pub rgb_source: BlendFactor,
+    pub rgb_destination: BlendFactor,
+    pub alpha_source: BlendFactor,
+    pub alpha_destination: BlendFactor,
+}
+
+impl CompositeState {
+    fn from_raw(raw: ffi::NVGcompositeOperationState) -> Self {
+        CompositeState {
+            rgb_source: BlendFactor::from_raw(raw.srcRGB),
+            rgb_destination: BlendFactor::from_raw(raw.dstRGB),
+            alpha_source: BlendFactor::from_raw(raw.srcAlpha),
+            alpha_destination: BlendFactor::from_raw(raw.dstAlpha),
+        }
+    }
+}
+
+/// A line of text about to be drawn, see [Renderer::begin_text].
+#[derive(Clone, Copy, Debug)]
+pub struct RenderText<'a> {
+    /// The font of the text, as passed to [Renderer::create_font].
+    pub font: usize,
+    /// The font size in text space.
+    pub size: f32,
+    /// The blur of the text, in pixels.
+    pub blur: f32,
+    /// Maps text space into path space.
+    pub transform: Transform,
+    /// Every glyph with the origin of its baseline in text space.
+    pub glyphs: &'a [(&'a str, (f32, f32))],
+}
+
+/// The path a fill or stroke is about to be tessellated from, see [Renderer::begin_shape].
+#[derive(Clone, Copy, Debug)]
+pub struct RenderShape<'a> {
+    /// The commands of the path, dashed already for dashed strokes.
+    pub path: &'a PathData,
+    /// Maps the commands into path space.
+    pub transform: Transform,
+    /// How the path is stroked, `None` for fills.
+    pub stroke: Option<&'a StrokeOptions>,
+}
+
+/// A rendering backend driven by NanoVG.
+///
+/// NanoVG calls into the renderer while a frame is drawn: `viewport` once at the start of a frame,
+/// `fill`, `stroke` and `triangles` for every draw call and `flush` (or `cancel`) once at the end.
+/// Draw calls may be buffered until `flush`.
+///
+/// A call that panics fails like a call made while the renderer is borrowed through
+/// [Context::renderer](../struct.Context.html#method.renderer), and the panic is resumed once the frame ends.
+pub trait Renderer {
+    /// Create a texture of `width` * `height` pixels.
+    /// `data` contains the initial pixels, if any, tightly packed row by row.
+    /// Returns a non-zero handle for the texture, or `None` if it couldn't be created.
+    fn create_texture(
+        &mut self,
+        texture_type: TextureType,
+        width: usize,
+        height: usize,
+        flags: ImageFlags,
+        data: Option<&[u8]>,
+    ) -> Option<usize>;
+
+    /// Delete the texture with handle `image`. Returns whether the texture existed.
+    fn delete_texture(&mut self, image: usize) -> bool;
+
+    /// Update the region at `origin` with `size` of a texture.
+    /// `data` always contains the pixels of the whole texture, not only of the updated region.
+    fn update_texture(&mut self, image: usize, origin: (usize, usize), size: (usize, usize), data: &[u8]) -> bool;
+
+    /// Get the size of a texture, or `None` if the texture doesn't exist.
+    fn texture_size(&self, image: usize) -> Option<(usize, usize)>;
+
+    /// Called when a frame begins with the arguments passed to [Context::frame](../struct.Context.html#method.frame).
+    fn viewport(&mut self, width: f32, height: f32, device_pixel_ratio: f32);
+
+    /// Discard all draw calls buffered during the current frame.
+    fn cancel(&mut self);
+
+    /// Execute all draw calls buffered during the current frame.
+    fn flush(&mut self);
+
+    /// Fill `paths`. `bounds` is the bounding box `[min_x, min_y, max_x, max_y]` of all paths,
+    /// `fringe` the width of the antialiasing fringe.
+    fn fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        bounds: [f32; 4],
+        paths: &[RenderPath],
+    );
+
+    /// Stroke `paths` with a stroke of `stroke_width`.
+    fn stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[RenderPath],
+    );
+
+    /// Draw a list of triangles, three vertices each. Used for text.
+    fn triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        vertices: &[Vertex],
+        fringe: f32,
+    );
+
+    /// Called when a font is created on the context, with its name and the contents of its font file.
+    /// `font` identifies the font in [RenderText].
+    fn create_font(&mut self, _font: usize, _name: &str, _data: &[u8]) {}
+
+    /// Called before a line of text is drawn. Return `true` to draw `text` from its font instead of the font atlas:
+    /// the `triangles` calls until `end_text` then only provide the paint and scissor of the text, their glyph
+    /// quads are not meant to be drawn. The default returns `false`.
+    fn begin_text(&mut self, _text: &RenderText) -> bool {
+        false
+    }
+
+    /// Called after the text of a `begin_text` call returning `true` was drawn.
+    fn end_text(&mut self) {}
+
+    /// Called before a path is filled or stroked, with the commands it was built from, for renderers which
+    /// rather keep curves and transforms than the tessellation. The `fill` or `stroke` call until `end_shape`
+    /// draws `shape`, NanoVG may skip it for paths without any area.
+    fn begin_shape(&mut self, _shape: &RenderShape) {}
+
+    /// Called after the path of a `begin_shape` call was drawn.
+    fn end_shape(&mut self) {}
+}
+
+/// Convert premultiplied RGBA pixels to straight alpha in place.
+pub(crate) fn unpremultiply(rgba: &mut [u8]) {
+    for p in rgba.chunks_mut(4) {
+        let a = p[3] as u32;
+        if a < 255 {
+            for c in p.iter_mut().take(3) {
+                *c = (*c as u32 * 255 + a / 2).checked_div(a).unwrap_or(0).min(255) as u8;
+            }
+        }
+    }
+}
+
+/// Owned by the NanoVG context through `NVGparams::userPtr`.
+struct RendererState<R> {
+    renderer: RefCell<R>,
+    texture_types: RefCell<HashMap<usize, TextureType>>,
+}
+
+/// The renderer of a context created by `create_internal`, for the calls that don't go through NanoVG.
+/// Only valid as long as the NanoVG context.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CustomRenderer {
+    type_id: TypeId,
+    renderer: *const RefCell<dyn Renderer>,
+}
+
+impl CustomRenderer {
+    /// Borrow the renderer as an `R`, or `None` if it is of a different type or already borrowed.
+    pub(crate) fn borrow<'a, R: Renderer + 'static>(&self) -> Option<RefMut<'a, R>> {
+        if self.type_id != TypeId::of::<R>() {
+            return None;
+        }
+        unsafe { (*(self.renderer as *const RefCell<R>)).try_borrow_mut().ok() }
+    }
+
+    /// Call `handler` with the renderer like NanoVG does, see `with_renderer`.
+    pub(crate) fn call<T, F: FnOnce(&mut (dyn Renderer + 'static)) -> T>(&self, failed: T, handler: F) -> T {
+        with_renderer(unsafe { &*self.renderer }, failed, handler)
+    }
+}
+
+/// Create a NanoVG context which renders through `renderer`.
+pub(crate) fn create_internal<R: Renderer + 'static>(
+    renderer: R,
+    antialias: bool,
+) -> Option<(*mut ffi::NVGcontext, CustomRenderer)> {
+    let state = Box::into_raw(Box::new(RendererState {
+        renderer: RefCell::new(renderer),
+        texture_types: RefCell::new(HashMap::new()),
+    }));
+    let custom = CustomRenderer {
+        type_id: TypeId::of::<R>(),
+        renderer: unsafe { &(*state).renderer as *const RefCell<R> as *const RefCell<dyn Renderer> },
+    };
+
+    let mut params = ffi::NVGparams {
+        userPtr: state as *mut c_void,
+        edgeAntiAlias: antialias as c_int,
+        renderCreate: Some(render_create),
+        renderCreateTexture: Some(render_create_texture::<R>),
+        renderDeleteTexture: Some(render_delete_texture::<R>),
+        renderUpdateTexture: Some(render_update_texture::<R>),
+        renderGetTextureSize: Some(render_get_texture_size::<R>),
+        renderViewport: Some(render_viewport::<R>),
+        renderCancel: Some(render_cancel::<R>),
+        renderFlush: Some(render_flush::<R>),
+        renderFill: Some(render_fill::<R>),
+        renderStroke: Some(render_stroke::<R>),
+        renderTriangles: Some(render_triangles::<R>),
+        renderDelete: Some(render_delete::<R>),
+    };
+
+    // On failure NanoVG already released the renderer through `renderDelete`.
+    let raw = unsafe { ffi::nvgCreateInternal(&mut params) };
+    if raw.is_null() {
+        None
+    } else {
+        Some((raw, custom))
+    }
+}
+
+unsafe fn state<'a, R>(uptr: *mut c_void) -> &'a RendererState<R> {
+    &*(uptr as *const RendererState<R>)
+}
+
+thread_local! {
+    /// The first panic of a renderer called by NanoVG, to resume once the frame ends.
+    static PANIC: RefCell<Option<Box<dyn Any + Send>>> = RefCell::new(None);
+}
+
+/// Call `handler` with the renderer from a NanoVG callback, or return `failed` if the renderer is borrowed
+/// through `Context::renderer` or panics. Unwinding into the C code of NanoVG would abort the process.
+fn with_renderer<R: ?Sized, T, F: FnOnce(&mut R) -> T>(renderer: &RefCell<R>, failed: T, handler: F) -> T {
+    let mut renderer = match renderer.try_borrow_mut() {
+        Ok(renderer) => renderer,
+        Err(_) => return failed,
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| handler(&mut renderer))) {
+        Ok(result) => result,
+        Err(payload) => {
+            PANIC.with(|panic| {
+                let mut panic = panic.borrow_mut();
+                if panic.is_none() {
+                    *panic = Some(payload);
+                }
+            });
+            failed
+        }
+    }
+}
+
+/// Resume the panic of a renderer caught by the callbacks since the last call, if any.
+pub(crate) fn resume_panic() {
+    if let Some(payload) = PANIC.with(|panic| panic.borrow_mut().take()) {
+        panic::resume_unwind(payload);
+    }
+}
+
+unsafe fn vertices<'a>(verts: *const ffi::NVGvertex, count: c_int) -> &'a [Vertex] {
+    if verts.is_null() || count <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(verts as *const Vertex, count as usize)
+    }
+}
+
+unsafe fn paths<'a>(paths: *const ffi::NVGpath, npaths: c_int) -> Vec<RenderPath<'a>> {
+    if paths.is_null() || npaths <= 0 {
+        return Vec::new();
+    }
+    slice::from_raw_parts(paths, npaths as usize)
+        .iter()
+        .map(|path| RenderPath {
+            fill: vertices(path.fill, path.nfill),
+            stroke: vertices(path.stroke, path.nstroke),
+            closed: path.closed != 0,
+            convex: path.convex != 0,
+            winding: Direction::from_raw(path.winding),
+            bevel_count: path.nbevel.max(0) as usize,
+        })
+        .collect()
+}
+
+unsafe extern "C" fn render_create(_uptr: *mut c_void) -> c_int {
+    1
+}
+
+unsafe extern "C" fn render_create_texture<R: Renderer>(
+    uptr: *mut c_void,
+    type_: c_int,
+    w: c_int,
+    h: c_int,
+    image_flags: c_int,
+    data: *const c_uchar,
+) -> c_int {
+    let state = state::<R>(uptr);
+    let texture_type = TextureType::from_raw(type_);
+    let (width, height) = (w.max(0) as usize, h.max(0) as usize);
+    let data = if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(
+            data,
+            width * height * texture_type.bytes_per_pixel(),
+        ))
+    };
+
+    let image = with_renderer(&state.renderer, None, |renderer| {
+        renderer.create_texture(texture_type, width, height, ImageFlags::from_raw(image_flags), data)
+    });
+    match image {
+        Some(image) if image > 0 => {
+            state.texture_types.borrow_mut().insert(image, texture_type);
+            image as c_int
+        }
+        _ => 0,
+    }
+}
+
+unsafe extern "C" fn render_delete_texture<R: Renderer>(uptr: *mut c_void, image: c_int) -> c_int {
+    let state = state::<R>(uptr);
+    let deleted = with_renderer(&state.renderer, false, |renderer| {
+        renderer.delete_texture(image as usize)
+    });
+    if deleted {
+        state.texture_types.borrow_mut().remove(&(image as usize));
+    }
+    deleted as c_int
+}
+
+unsafe extern "C" fn render_update_texture<R: Renderer>(
+    uptr: *mut c_void,
+    image: c_int,
+    x: c_int,
+    y: c_int,
+    w: c_int,
+    h: c_int,
+    data: *const c_uchar,
+) -> c_int {
+    let state = state::<R>(uptr);
+    let image = image as usize;
+    let texture_type = match state.texture_types.borrow().get(&image) {
+        Some(texture_type) => *texture_type,
+        None => return 0,
+    };
+    if data.is_null() {
+        return 0;
+    }
+    let origin = (x.max(0) as usize, y.max(0) as usize);
+    let size = (w.max(0) as usize, h.max(0) as usize);
+    with_renderer(&state.renderer, false, |renderer| {
+        let (tex_w, tex_h) = match renderer.texture_size(image) {
+            Some(size) => size,
+            None => return false,
+        };
+        let data = slice::from_raw_parts(data, tex_w * tex_h * texture_type.bytes_per_pixel());
+        renderer.update_texture(image, origin, size, data)
+    }) as c_int
+}
+
+unsafe extern "C" fn render_get_texture_size<R: Renderer>(
+    uptr: *mut c_void,
+    image: c_int,
+    w: *mut c_int,
+    h: *mut c_int,
+) -> c_int {
+    match with_renderer(&state::<R>(uptr).renderer, None, |renderer| {
+        renderer.texture_size(image as usize)
+    }) {
+        Some((width, height)) => {
+            *w = width as c_int;
+            *h = height as c_int;
+            1
+        }
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn render_viewport<R: Renderer>(
+    uptr: *mut c_void,
+    width: c_float,
+    height: c_float,
+    device_pixel_ratio: c_float,
+) {
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| {
+        renderer.viewport(width, height, device_pixel_ratio)
+    });
+}
+
+unsafe extern "C" fn render_cancel<R: Renderer>(uptr: *mut c_void) {
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| renderer.cancel());
+}
+
+unsafe extern "C" fn render_flush<R: Renderer>(uptr: *mut c_void) {
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| renderer.flush());
+}
+
+unsafe extern "C" fn render_fill<R: Renderer>(
+    uptr: *mut c_void,
+    paint: *mut ffi::NVGpaint,
+    composite_operation: ffi::NVGcompositeOperationState,
+    scissor: *mut ffi::NVGscissor,
+    fringe: c_float,
+    bounds: *const c_float,
+    raw_paths: *const ffi::NVGpath,
+    npaths: c_int,
+) {
+    let mut rect = [0.0f32; 4];
+    ptr::copy_nonoverlapping(bounds, rect.as_mut_ptr(), 4);
+    let (paint, scissor, paths) = (
+        RenderPaint::from_raw(&*paint),
+        RenderScissor::from_raw(&*scissor),
+        paths(raw_paths, npaths),
+    );
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| {
+        renderer.fill(
+            &paint,
+            CompositeState::from_raw(composite_operation),
+            scissor,
+            fringe,
+            rect,
+            &paths,
+        )
+    });
+}
+
+unsafe extern "C" fn render_stroke<R: Renderer>(
+    uptr: *mut c_void,
+    paint: *mut ffi::NVGpaint,
+    composite_operation: ffi::NVGcompositeOperationState,
+    scissor: *mut ffi::NVGscissor,
+    fringe: c_float,
+    stroke_width: c_float,
+    raw_paths: *const ffi::NVGpath,
+    npaths: c_int,
+) {
+    let (paint, scissor, paths) = (
+        RenderPaint::from_raw(&*paint),
+        RenderScissor::from_raw(&*scissor),
+        paths(raw_paths, npaths),
+    );
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| {
+        renderer.stroke(
+            &paint,
+            CompositeState::from_raw(composite_operation),
+            scissor,
+            fringe,
+            stroke_width,
+            &paths,
+        )
+    });
+}
+
+unsafe extern "C" fn render_triangles<R: Renderer>(
+    uptr: *mut c_void,
+    paint: *mut ffi::NVGpaint,
+    composite_operation: ffi::NVGcompositeOperationState,
+    scissor: *mut ffi::NVGscissor,
+    verts: *const ffi::NVGvertex,
+    nverts: c_int,
+    fringe: c_float,
+) {
+    let (paint, scissor, vertices) = (
+        RenderPaint::from_raw(&*paint),
+        RenderScissor::from_raw(&*scissor),
+        vertices(verts, nverts),
+    );
+    with_renderer(&state::<R>(uptr).renderer, (), |renderer| {
+        renderer.triangles(
+            &paint,
+            CompositeState::from_raw(composite_operation),
+            scissor,
+            vertices,
+            fringe,
+        )
+    });
+}
+
+unsafe extern "C" fn render_delete<R: Renderer>(uptr: *mut c_void) {
+    if !uptr.is_null() {
+        drop(Box::from_raw(uptr as *mut RendererState<R>));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callbacks_fail_safely() {
+        let state = RendererState {
+            renderer: RefCell::new(RecordingRenderer::new()),
+            texture_types: RefCell::new(HashMap::new()),
+        };
+        let uptr = &state as *const RendererState<RecordingRenderer> as *mut c_void;
+        let alpha = ffi::NVGtexture::NVG_TEXTURE_ALPHA as c_int;
+        unsafe {
+            // Borrowed like through `Context::renderer`.
+            let borrowed = state.renderer.borrow_mut();
+            assert_eq!(
+                render_create_texture::<RecordingRenderer>(uptr, alpha, 2, 2, 0, ptr::null()),
+                0
+            );
+            drop(borrowed);
+            assert_eq!(
+                render_create_texture::<RecordingRenderer>(uptr, alpha, 2, 2, 0, ptr::null()),
+                1
+            );
+        }
+
+        let failed = with_renderer(&state.renderer, 0, |_: &mut RecordingRenderer| -> i32 {
+            panic!("renderer failed")
+        });
+        assert_eq!(failed, 0);
+        let resumed = panic::catch_unwind(resume_panic).unwrap_err();
+        assert_eq!(resumed.downcast_ref::<&str>(), Some(&"renderer failed"));
+        // Only resumed once.
+        resume_panic();
+    }
+}
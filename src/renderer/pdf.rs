@@ -0,0 +1,1002 @@
+//! Export of recorded frames as PDF documents, one page per frame.
+//!
+//! Paths are converted the same way as for SVG export. Linear and radial gradients become PDF
+//! shadings, with a soft mask when their alpha varies. Box gradients and images are embedded as
+//! compressed image objects, once per document unless they change between pages.
+//!
+//! Text in a TrueType font is written as text, with the font file embedded as a `FontFile2`, so it
+//! prints sharp and can be searched and copied. Other text, blurred text and text with glyphs
+//! NanoVG takes from a fallback font is drawn from the font atlas instead: every glyph is an image
+//! masked by the atlas, so it prints at the resolution the atlas was rendered at.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path as IoPath;
+use std::rc::Rc;
+
+use super::svg::{
+    fill_outline, gradient_image, gradient_kind, linear_gradient_transform, num, stroke_outline, texture_transform,
+    vertex_bounds, GradientKind,
+};
+use super::truetype::TrueType;
+use super::{
+    CompositeState, DrawCall, ImageFlags, RecordedFrame, RecordedTexture, RecordingRenderer, RenderPaint, RenderPath,
+    RenderScissor, RenderText, Renderer, TextureType, Vertex,
+};
+use png;
+use Color;
+
+/// A recorded frame together with the textures it used at the time it was drawn.
+#[derive(Clone, Debug)]
+struct Page {
+    frame: RecordedFrame,
+    textures: HashMap<usize, Rc<RecordedTexture>>,
+}
+
+fn matrix(m: &[f32; 6]) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        num(m[0]),
+        num(m[1]),
+        num(m[2]),
+        num(m[3]),
+        num(m[4]),
+        num(m[5])
+    )
+}
+
+fn rgb(color: Color) -> String {
+    let c = |v: f32| num(v.clamp(0.0, 1.0));
+    format!("{} {} {}", c(color.red()), c(color.green()), c(color.blue()))
+}
+
+fn polygon(out: &mut String, points: &[(f32, f32)]) {
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let operator = if i == 0 { "m" } else { "l" };
+        let _ = writeln!(out, "{} {} {}", num(x), num(y), operator);
+    }
+    if !points.is_empty() {
+        out.push_str("h\n");
+    }
+}
+
+/// Identifies a texture snapshot, pages sharing one embed the image once.
+fn texture_key(texture: &Rc<RecordedTexture>) -> *const RecordedTexture {
+    &**texture
+}
+
+/// A font used by the document, written once all pages are.
+struct PdfFont {
+    name: String,
+    id: usize,
+    glyphs: BTreeMap<u16, char>,
+}
+
+/// Builds the objects of a document and keeps track of the resources pages refer to.
+struct PdfWriter {
+    objects: Vec<Vec<u8>>,
+    resources: HashMap<&'static str, Vec<(String, usize)>>,
+    alphas: HashMap<u32, String>,
+    textures: HashMap<*const RecordedTexture, String>,
+    masks: HashMap<*const RecordedTexture, usize>,
+    glyphs: HashMap<(usize, [u8; 3]), String>,
+    fonts: HashMap<usize, PdfFont>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        PdfWriter {
+            objects: Vec::new(),
+            resources: HashMap::new(),
+            alphas: HashMap::new(),
+            textures: HashMap::new(),
+            masks: HashMap::new(),
+            glyphs: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// Reserve an object number, to be filled in later with `set`.
+    fn reserve(&mut self) -> usize {
+        self.objects.push(Vec::new());
+        self.objects.len()
+    }
+
+    fn set(&mut self, id: usize, object: Vec<u8>) {
+        self.objects[id - 1] = object;
+    }
+
+    fn add(&mut self, object: Vec<u8>) -> usize {
+        self.objects.push(object);
+        self.objects.len()
+    }
+
+    fn add_dictionary(&mut self, dictionary: String) -> usize {
+        self.add(dictionary.into_bytes())
+    }
+
+    /// Add a stream compressed with deflate. `dictionary` holds the entries besides its length and filter.
+    fn add_stream(&mut self, dictionary: &str, data: &[u8]) -> usize {
+        let data = png::zlib(data);
+        let separator = if dictionary.is_empty() { "" } else { " " };
+        let mut object = format!(
+            "<< {}{}/Length {} /Filter /FlateDecode >>\nstream\n",
+            dictionary,
+            separator,
+            data.len()
+        )
+        .into_bytes();
+        object.extend_from_slice(&data);
+        object.extend_from_slice(b"\nendstream");
+        self.add(object)
+    }
+
+    /// Register an object in the shared resource dictionary and return its name.
+    fn resource(&mut self, kind: &'static str, prefix: &str, id: usize) -> String {
+        let name = format!("{}{}", prefix, id);
+        self.resources.entry(kind).or_default().push((name.clone(), id));
+        name
+    }
+
+    /// An image object with its alpha channel as soft mask.
+    fn image(&mut self, width: usize, height: usize, rgba: &[u8]) -> String {
+        let alpha: Vec<u8> = rgba.chunks(4).map(|p| p[3]).collect();
+        let rgb: Vec<u8> = rgba.chunks(4).flat_map(|p| p[..3].to_vec()).collect();
+        let mask = self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8",
+                width, height
+            ),
+            &alpha,
+        );
+        let image = self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask {} 0 R",
+                width, height, mask
+            ),
+            &rgb,
+        );
+        self.resource("XObject", "Im", image)
+    }
+
+    /// The graphics state setting a constant fill alpha.
+    fn alpha(&mut self, alpha: f32) -> String {
+        let key = alpha.to_bits();
+        if let Some(name) = self.alphas.get(&key) {
+            return name.clone();
+        }
+        let id = self.add_dictionary(format!(
+            "<< /Type /ExtGState /ca {a} /CA {a} >>",
+            a = num(alpha.clamp(0.0, 1.0))
+        ));
+        let name = self.resource("ExtGState", "GS", id);
+        self.alphas.insert(key, name.clone());
+        name
+    }
+
+    fn texture(&mut self, textures: &HashMap<usize, Rc<RecordedTexture>>, image: usize) -> Option<String> {
+        let texture = textures.get(&image)?;
+        let key = texture_key(texture);
+        if let Some(name) = self.textures.get(&key) {
+            return Some(name.clone());
+        }
+        let name = self.image(texture.width, texture.height, &texture.to_rgba());
+        self.textures.insert(key, name.clone());
+        Some(name)
+    }
+
+    /// A single pixel image of `color` masked by the font atlas `image`.
+    fn glyph_image(
+        &mut self,
+        textures: &HashMap<usize, Rc<RecordedTexture>>,
+        image: usize,
+        color: Color,
+    ) -> Option<String> {
+        let texture = textures.get(&image)?;
+        let key = texture_key(texture);
+        let mask = match self.masks.get(&key) {
+            Some(&mask) => mask,
+            None => {
+                let alpha: Vec<u8> = texture.to_rgba().chunks(4).map(|p| p[3]).collect();
+                let mask = self.add_stream(
+                    &format!(
+                        "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8",
+                        texture.width, texture.height
+                    ),
+                    &alpha,
+                );
+                self.masks.insert(key, mask);
+                mask
+            }
+        };
+        let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+        let key = (mask, [c(color.red()), c(color.green()), c(color.blue())]);
+        if let Some(name) = self.glyphs.get(&key) {
+            return Some(name.clone());
+        }
+        let id = self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask {} 0 R",
+                mask
+            ),
+            &key.1,
+        );
+        let name = self.resource("XObject", "Im", id);
+        self.glyphs.insert(key, name.clone());
+        Some(name)
+    }
+
+    /// The resource name of the font created as `handle`, written with its glyphs by `write_fonts`.
+    fn font(&mut self, handle: usize) -> String {
+        if let Some(font) = self.fonts.get(&handle) {
+            return font.name.clone();
+        }
+        let id = self.reserve();
+        let name = self.resource("Font", "F", id);
+        self.fonts.insert(
+            handle,
+            PdfFont {
+                name: name.clone(),
+                id,
+                glyphs: BTreeMap::new(),
+            },
+        );
+        name
+    }
+
+    /// Write the fonts used by the pages as Type 0 fonts with CID and glyph index equal, embedding the
+    /// whole font file. Glyph widths and the mapping back to Unicode only cover the glyphs used.
+    fn write_fonts(&mut self, fonts: &HashMap<usize, TrueType>) {
+        for (handle, used) in mem::take(&mut self.fonts) {
+            let font = &fonts[&handle];
+            let scale = 1000.0 / font.units_per_em as f32;
+            let units = |value: i16| num(value as f32 * scale);
+            let base = font
+                .postscript_name
+                .clone()
+                .unwrap_or_else(|| format!("Font{}", handle));
+
+            let file = self.add_stream(&format!("/Length1 {}", font.data().len()), font.data());
+            let descriptor = self.add_dictionary(format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [{} {} {} {}] /ItalicAngle {} /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 {} 0 R >>",
+                base,
+                units(font.bounds[0]),
+                units(font.bounds[1]),
+                units(font.bounds[2]),
+                units(font.bounds[3]),
+                num(font.italic_angle),
+                units(font.ascender),
+                units(font.descender),
+                units(font.cap_height),
+                file
+            ));
+            let widths: Vec<_> = used
+                .glyphs
+                .keys()
+                .map(|&glyph| format!("{} [{}]", glyph, num(font.advance(glyph) as f32 * scale)))
+                .collect();
+            let descendant = self.add_dictionary(format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {} 0 R /CIDToGIDMap /Identity /W [{}] >>",
+                base,
+                descriptor,
+                widths.join(" ")
+            ));
+
+            let mut cmap = String::from(
+                "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n/CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+            );
+            let glyphs: Vec<_> = used.glyphs.iter().collect();
+            for chunk in glyphs.chunks(100) {
+                let _ = writeln!(cmap, "{} beginbfchar", chunk.len());
+                for &(&glyph, &c) in chunk {
+                    let mut units = [0u16; 2];
+                    let text: String = c
+                        .encode_utf16(&mut units)
+                        .iter()
+                        .map(|u| format!("{:04X}", u))
+                        .collect();
+                    let _ = writeln!(cmap, "<{:04X}> <{}>", glyph, text);
+                }
+                cmap.push_str("endbfchar\n");
+            }
+            cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+            let to_unicode = self.add_stream("", cmap.as_bytes());
+
+            self.set(
+                used.id,
+                format!(
+                    "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                    base, descendant, to_unicode
+                )
+                .into_bytes(),
+            );
+        }
+    }
+
+    /// A shading for a linear or radial gradient, returning its name and object number.
+    fn shading(&mut self, paint: &RenderPaint, gray: bool) -> (String, usize) {
+        let (shading_type, coords) = match gradient_kind(paint) {
+            GradientKind::Radial => {
+                let outer = paint.radius + paint.feather * 0.5;
+                let inner = (paint.radius - paint.feather * 0.5).max(0.0);
+                (3, format!("0 0 {} 0 0 {}", num(inner), num(outer)))
+            }
+            _ => {
+                let half = paint.feather * 0.5;
+                (2, format!("0 {} 0 {}", num(-half), num(half)))
+            }
+        };
+        let (color_space, c0, c1) = if gray {
+            (
+                "/DeviceGray",
+                num(paint.inner_color.alpha()),
+                num(paint.outer_color.alpha()),
+            )
+        } else {
+            ("/DeviceRGB", rgb(paint.inner_color), rgb(paint.outer_color))
+        };
+        let id = self.add_dictionary(format!(
+            "<< /ShadingType {} /ColorSpace {} /Coords [{}] /Extend [true true] /Function << /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >> >>",
+            shading_type, color_space, coords, c0, c1
+        ));
+        (self.resource("Shading", "Sh", id), id)
+    }
+
+    /// The transform of a linear or radial gradient in page space.
+    fn shading_matrix(paint: &RenderPaint) -> [f32; 6] {
+        match gradient_kind(paint) {
+            GradientKind::Linear => linear_gradient_transform(paint),
+            _ => paint.transform.matrix,
+        }
+    }
+
+    /// A graphics state with a soft mask holding the alpha of a gradient.
+    fn shading_mask(&mut self, paint: &RenderPaint, size: (f32, f32)) -> String {
+        let (shading, shading_id) = self.shading(paint, true);
+        let content = format!("{} cm\n/{} sh\n", matrix(&Self::shading_matrix(paint)), shading);
+        let group = self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Form /BBox [0 0 {} {}] /Group << /S /Transparency /CS /DeviceGray >> /Resources << /Shading << /{} {} 0 R >> >>",
+                num(size.0),
+                num(size.1),
+                shading,
+                shading_id
+            ),
+            content.as_bytes(),
+        );
+        let id = self.add_dictionary(format!(
+            "<< /Type /ExtGState /SMask << /Type /Mask /S /Luminosity /G {} 0 R >> >>",
+            group
+        ));
+        self.resource("ExtGState", "GS", id)
+    }
+
+    /// A tiling pattern repeating `image` over the paint extent.
+    fn image_pattern(&mut self, paint: &RenderPaint, image: &str, flip_y: bool, page_height: f32) -> String {
+        let (ex, ey) = paint.extent;
+        let image_id = self.resources["XObject"]
+            .iter()
+            .find(|&(name, _)| name == image)
+            .unwrap()
+            .1;
+        let placement = if flip_y {
+            [ex, 0.0, 0.0, ey, 0.0, 0.0]
+        } else {
+            [ex, 0.0, 0.0, -ey, 0.0, ey]
+        };
+        // Pattern space is relative to the default page space, so undo the flip of the page.
+        let m = &paint.transform.matrix;
+        let pattern_matrix = [m[0], -m[1], m[2], -m[3], m[4], page_height - m[5]];
+        let content = format!("{} cm\n/{} Do\n", matrix(&placement), image);
+        let id = self.add_stream(
+            &format!(
+                "/Type /Pattern /PatternType 1 /PaintType 1 /TilingType 1 /BBox [0 0 {w} {h}] /XStep {w} /YStep {h} /Matrix [{m}] /Resources << /XObject << /{i} {id} 0 R >> >>",
+                w = num(ex),
+                h = num(ey),
+                m = matrix(&pattern_matrix),
+                i = image,
+                id = image_id
+            ),
+            content.as_bytes(),
+        );
+        self.resource("Pattern", "P", id)
+    }
+
+    fn clip(content: &mut String, scissor: &Option<RenderScissor>) {
+        if let Some(ref scissor) = *scissor {
+            let (ex, ey) = scissor.extent;
+            let m = &scissor.transform.matrix;
+            let corners: Vec<_> = [(-ex, -ey), (ex, -ey), (ex, ey), (-ex, ey)]
+                .iter()
+                .map(|&(x, y)| (x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5]))
+                .collect();
+            polygon(content, &corners);
+            content.push_str("W n\n");
+        }
+    }
+
+    /// Fill `path` with `paint`. The path is drawn with the nonzero rule.
+    fn fill_path(&mut self, source: &Page, content: &mut String, paint: &RenderPaint, bounds: [f32; 4], path: &str) {
+        let size = (source.frame.width, source.frame.height);
+        match gradient_kind(paint) {
+            GradientKind::Solid => {
+                let alpha = self.alpha(paint.inner_color.alpha());
+                let _ = write!(content, "/{} gs\n{} rg\n{}f\n", alpha, rgb(paint.inner_color), path);
+            }
+            GradientKind::Linear | GradientKind::Radial => {
+                let state = if paint.inner_color.alpha() == paint.outer_color.alpha() {
+                    self.alpha(paint.inner_color.alpha())
+                } else {
+                    self.shading_mask(paint, size)
+                };
+                let (shading, _) = self.shading(paint, false);
+                let _ = write!(
+                    content,
+                    "/{} gs\n{}W n\n{} cm\n/{} sh\n",
+                    state,
+                    path,
+                    matrix(&Self::shading_matrix(paint)),
+                    shading
+                );
+            }
+            GradientKind::Box => {
+                let (width, height, rgba) = gradient_image(paint, bounds, source.frame.device_pixel_ratio.max(1.0));
+                let image = self.image(width, height, &rgba);
+                let (bw, bh) = (bounds[2] - bounds[0], bounds[3] - bounds[1]);
+                let placement = [bw, 0.0, 0.0, -bh, bounds[0], bounds[1] + bh];
+                let _ = write!(content, "{}W n\n{} cm\n/{} Do\n", path, matrix(&placement), image);
+            }
+            GradientKind::Image(image) => {
+                let name = match self.texture(&source.textures, image) {
+                    Some(name) => name,
+                    None => return,
+                };
+                let flip_y = source.textures.get(&image).map(|t| t.flags.flip_y).unwrap_or(false);
+                let pattern = self.image_pattern(paint, &name, flip_y, size.1);
+                let alpha = self.alpha(paint.inner_color.alpha());
+                let _ = write!(content, "/{} gs\n/Pattern cs\n/{} scn\n{}f\n", alpha, pattern, path);
+            }
+        }
+    }
+
+    fn call(&mut self, source: &Page, fonts: &HashMap<usize, TrueType>, content: &mut String, call: &DrawCall) {
+        content.push_str("q\n");
+        Self::clip(content, &call.scissor());
+        match *call {
+            DrawCall::Fill {
+                ref paint,
+                bounds,
+                ref paths,
+                ..
+            } => {
+                let convex = paths.len() == 1 && paths[0].convex;
+                let mut path = String::new();
+                for p in paths {
+                    polygon(&mut path, &fill_outline(p, convex));
+                }
+                if !path.is_empty() {
+                    self.fill_path(source, content, paint, bounds, &path);
+                }
+            }
+            DrawCall::Stroke {
+                ref paint,
+                fringe,
+                stroke_width,
+                ref paths,
+                ..
+            } => {
+                let mut path = String::new();
+                let mut bounds = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+                for p in paths {
+                    vertex_bounds(&p.stroke, &mut bounds);
+                    for outline in stroke_outline(p, stroke_width, fringe) {
+                        polygon(&mut path, &outline);
+                    }
+                }
+                if !path.is_empty() {
+                    self.fill_path(source, content, paint, bounds, &path);
+                }
+            }
+            DrawCall::Triangles {
+                ref paint,
+                ref vertices,
+                ..
+            } => {
+                let color = paint.inner_color;
+                let glyph = paint
+                    .image
+                    .and_then(|image| self.glyph_image(&source.textures, image, color));
+                let (image, width, height) = match (glyph, paint.image.and_then(|i| source.textures.get(&i))) {
+                    (Some(glyph), Some(texture)) => (glyph, texture.width as f32, texture.height as f32),
+                    _ => {
+                        content.push_str("Q\n");
+                        return;
+                    }
+                };
+                let alpha = self.alpha(color.alpha());
+                let _ = writeln!(content, "/{} gs", alpha);
+                let chunk = if vertices.len() % 6 == 0 { 6 } else { 3 };
+                for quad in vertices.chunks(chunk).filter(|quad| quad.len() >= 3) {
+                    let transform = match texture_transform(quad, width, height) {
+                        Some(transform) => transform,
+                        None => continue,
+                    };
+                    content.push_str("q\n");
+                    for triangle in quad.chunks(3).filter(|t| t.len() == 3) {
+                        let points: Vec<_> = triangle.iter().map(|v| (v.x, v.y)).collect();
+                        polygon(content, &points);
+                    }
+                    let _ = write!(
+                        content,
+                        "W n\n{} cm\n{} 0 0 {} 0 {} cm\n/{} Do\nQ\n",
+                        matrix(&transform),
+                        num(width),
+                        num(-height),
+                        num(height),
+                        image
+                    );
+                }
+            }
+            DrawCall::Text {
+                ref paint, ref text, ..
+            } => {
+                let font = match fonts.get(&text.font) {
+                    Some(font) => font,
+                    None => {
+                        content.push_str("Q\n");
+                        return;
+                    }
+                };
+                let name = self.font(text.font);
+                let color = paint.inner_color;
+                let alpha = self.alpha(color.alpha());
+                let _ = write!(
+                    content,
+                    "/{} gs\n{} rg\nBT\n/{} {} Tf\n",
+                    alpha,
+                    rgb(color),
+                    name,
+                    num(text.size)
+                );
+                // Place every glyph on its own, flipping it back upright on the flipped page.
+                let m = &text.transform.matrix;
+                let mut used = Vec::new();
+                for &(ref glyph, (x, y)) in &text.glyphs {
+                    let (c, id) = match glyph.chars().next().and_then(|c| Some((c, font.glyph(c)?))) {
+                        Some(glyph) => glyph,
+                        None => continue,
+                    };
+                    let placement = [
+                        m[0],
+                        m[1],
+                        -m[2],
+                        -m[3],
+                        m[0] * x + m[2] * y + m[4],
+                        m[1] * x + m[3] * y + m[5],
+                    ];
+                    let _ = writeln!(content, "{} Tm <{:04X}> Tj", matrix(&placement), id);
+                    used.push((id, c));
+                }
+                content.push_str("ET\n");
+                if let Some(font) = self.fonts.get_mut(&text.font) {
+                    font.glyphs.extend(used);
+                }
+            }
+        }
+        content.push_str("Q\n");
+    }
+
+    fn finish<W: Write>(mut self, out: &mut W, pages: &[Page], fonts: &HashMap<usize, TrueType>) -> io::Result<()> {
+        let catalog = self.reserve();
+        let tree = self.reserve();
+        let resources = self.reserve();
+
+        let mut kids = Vec::new();
+        for page in pages {
+            let (width, height) = (page.frame.width, page.frame.height);
+            // Flip the page so y points down like in NanoVG.
+            let mut content = format!("1 0 0 -1 0 {} cm\n", num(height));
+            for call in &page.frame.calls {
+                self.call(page, fonts, &mut content, call);
+            }
+            let contents = self.add_stream("", content.as_bytes());
+            kids.push(self.add_dictionary(format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} 0 R /Contents {} 0 R >>",
+                tree,
+                num(width),
+                num(height),
+                resources,
+                contents
+            )));
+        }
+
+        self.write_fonts(fonts);
+
+        let mut dictionary = String::from("<< /ProcSet [/PDF /Text /ImageC /ImageB]");
+        let mut kinds: Vec<_> = self.resources.keys().cloned().collect();
+        kinds.sort();
+        for kind in kinds {
+            let _ = write!(dictionary, " /{} <<", kind);
+            for &(ref name, id) in &self.resources[kind] {
+                let _ = write!(dictionary, " /{} {} 0 R", name, id);
+            }
+            dictionary.push_str(" >>");
+        }
+        dictionary.push_str(" >>");
+        self.set(resources, dictionary.into_bytes());
+
+        let kids: Vec<_> = kids.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.set(
+            tree,
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()).into_bytes(),
+        );
+        self.set(
+            catalog,
+            format!("<< /Type /Catalog /Pages {} 0 R >>", tree).into_bytes(),
+        );
+
+        let mut document = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (index, object) in self.objects.iter().enumerate() {
+            offsets.push(document.len());
+            document.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            document.extend_from_slice(object);
+            document.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = document.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            trailer,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            catalog,
+            xref
+        );
+        document.extend_from_slice(trailer.as_bytes());
+        out.write_all(&document)
+    }
+}
+
+/// A renderer collecting frames into a PDF document, one page per frame.
+///
+/// Pages are sized in points by the frame size passed to
+/// [Context::frame](../../struct.Context.html#method.frame). Raise the device pixel ratio to get
+/// sharper box gradients and text drawn from the font atlas, they are embedded as images at that resolution.
+/// Composite operations other than the default blending can't be expressed and are ignored.
+#[derive(Debug)]
+pub struct PdfRenderer {
+    recorder: RecordingRenderer,
+    pages: Vec<Page>,
+    /// The textures as the last pages saw them, dropped when they are updated or deleted.
+    snapshots: HashMap<usize, Rc<RecordedTexture>>,
+    fonts: HashMap<usize, TrueType>,
+}
+
+impl Default for PdfRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfRenderer {
+    /// Create a new PDF renderer without any pages.
+    pub fn new() -> Self {
+        PdfRenderer {
+            recorder: RecordingRenderer::new().record_text(),
+            pages: Vec::new(),
+            snapshots: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// The number of pages drawn so far.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Remove all pages drawn so far.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Write the PDF document with all pages drawn so far.
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        PdfWriter::new().finish(out, &self.pages, &self.fonts)
+    }
+
+    /// The PDF document with all pages drawn so far.
+    pub fn document(&self) -> Vec<u8> {
+        let mut document = Vec::new();
+        self.write(&mut document).unwrap();
+        document
+    }
+
+    /// Write the PDF document with all pages drawn so far to `path`.
+    pub fn save<P: AsRef<IoPath>>(&self, path: P) -> io::Result<()> {
+        self.write(&mut File::create(path)?)
+    }
+}
+
+impl Renderer for PdfRenderer {
+    fn create_texture(
+        &mut self,
+        texture_type: TextureType,
+        width: usize,
+        height: usize,
+        flags: ImageFlags,
+        data: Option<&[u8]>,
+    ) -> Option<usize> {
+        self.recorder.create_texture(texture_type, width, height, flags, data)
+    }
+
+    fn delete_texture(&mut self, image: usize) -> bool {
+        self.snapshots.remove(&image);
+        self.recorder.delete_texture(image)
+    }
+
+    fn update_texture(&mut self, image: usize, origin: (usize, usize), size: (usize, usize), data: &[u8]) -> bool {
+        self.snapshots.remove(&image);
+        self.recorder.update_texture(image, origin, size, data)
+    }
+
+    fn texture_size(&self, image: usize) -> Option<(usize, usize)> {
+        self.recorder.texture_size(image)
+    }
+
+    fn viewport(&mut self, width: f32, height: f32, device_pixel_ratio: f32) {
+        self.recorder.viewport(width, height, device_pixel_ratio);
+    }
+
+    fn cancel(&mut self) {
+        self.recorder.cancel();
+    }
+
+    fn flush(&mut self) {
+        self.recorder.flush();
+        // Textures may change or go away before the document is written, keep them as they are now.
+        // Pages share a snapshot until the texture is updated or deleted.
+        for frame in self.recorder.take_frames() {
+            let mut textures = HashMap::new();
+            let images = frame
+                .calls
+                .iter()
+                .filter(|call| !matches!(**call, DrawCall::Text { .. }))
+                .filter_map(|call| call.paint().image);
+            for image in images {
+                if !self.snapshots.contains_key(&image) {
+                    if let Some(texture) = self.recorder.texture(image) {
+                        self.snapshots.insert(image, Rc::new(texture.clone()));
+                    }
+                }
+                if let Some(snapshot) = self.snapshots.get(&image) {
+                    textures.insert(image, snapshot.clone());
+                }
+            }
+            self.pages.push(Page { frame, textures });
+        }
+    }
+
+    fn fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        bounds: [f32; 4],
+        paths: &[RenderPath],
+    ) {
+        self.recorder.fill(paint, composite, scissor, fringe, bounds, paths);
+    }
+
+    fn stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[RenderPath],
+    ) {
+        self.recorder
+            .stroke(paint, composite, scissor, fringe, stroke_width, paths);
+    }
+
+    fn triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        vertices: &[Vertex],
+        fringe: f32,
+    ) {
+        self.recorder.triangles(paint, composite, scissor, vertices, fringe);
+    }
+
+    fn create_font(&mut self, font: usize, _name: &str, data: &[u8]) {
+        if let Some(parsed) = TrueType::parse(data) {
+            self.fonts.insert(font, parsed);
+        }
+    }
+
+    fn begin_text(&mut self, text: &RenderText) -> bool {
+        let font = match self.fonts.get(&text.font) {
+            Some(font) if text.blur == 0.0 => font,
+            _ => return false,
+        };
+        // NanoVG takes glyphs the font lacks from its fallback fonts, keep those in the font atlas.
+        let covered = text
+            .glyphs
+            .iter()
+            .flat_map(|&(glyph, _)| glyph.chars())
+            .all(|c| c.is_control() || font.glyph(c).is_some());
+        covered && self.recorder.begin_text(text)
+    }
+
+    fn end_text(&mut self) {
+        self.recorder.end_text();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {BlendFactor, Direction, Transform};
+
+    #[test]
+    fn test_pages() {
+        let mut renderer = PdfRenderer::new();
+        let composite = CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        };
+        let color = Color::new(1.0, 0.0, 0.0, 0.5);
+        let paint = RenderPaint {
+            transform: Transform::new(),
+            extent: (0.0, 0.0),
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: color,
+            outer_color: color,
+            image: None,
+        };
+        let vertex = |x, y| Vertex { x, y, u: 0.5, v: 1.0 };
+        let fill = [
+            vertex(10.0, 10.0),
+            vertex(10.0, 90.0),
+            vertex(90.0, 90.0),
+            vertex(90.0, 10.0),
+        ];
+        let path = RenderPath {
+            fill: &fill,
+            stroke: &[],
+            closed: true,
+            convex: true,
+            winding: Direction::CounterClockwise,
+            bevel_count: 0,
+        };
+
+        for _ in 0..2 {
+            renderer.viewport(100.0, 50.0, 1.0);
+            renderer.fill(&paint, composite, None, 1.0, [10.0, 10.0, 90.0, 90.0], &[path]);
+            renderer.flush();
+        }
+        renderer.viewport(100.0, 50.0, 1.0);
+        renderer.cancel();
+        assert_eq!(renderer.page_count(), 2);
+
+        let document = String::from_utf8_lossy(&renderer.document()).into_owned();
+        assert!(document.starts_with("%PDF-1.4"));
+        assert!(document.ends_with("%%EOF\n"));
+        assert!(document.contains("/Count 2"));
+        assert!(document.contains("/MediaBox [0 0 100 50]"));
+        assert!(document.contains("/ca 0.5"));
+    }
+
+    #[test]
+    fn test_shares_textures() {
+        let mut renderer = PdfRenderer::new();
+        let composite = CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        };
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 255];
+        let image = renderer.create_texture(TextureType::Rgba, 2, 1, ImageFlags::default(), Some(&pixels));
+        let color = Color::new(1.0, 1.0, 1.0, 1.0);
+        let paint = RenderPaint {
+            transform: Transform::new(),
+            extent: (2.0, 1.0),
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: color,
+            outer_color: color,
+            image,
+        };
+        let vertex = |x, y| Vertex { x, y, u: 0.5, v: 1.0 };
+        let fill = [vertex(0.0, 0.0), vertex(0.0, 1.0), vertex(2.0, 1.0), vertex(2.0, 0.0)];
+        let path = RenderPath {
+            fill: &fill,
+            stroke: &[],
+            closed: true,
+            convex: true,
+            winding: Direction::CounterClockwise,
+            bevel_count: 0,
+        };
+        let page = |renderer: &mut PdfRenderer| {
+            renderer.viewport(2.0, 1.0, 1.0);
+            renderer.fill(&paint, composite, None, 1.0, [0.0, 0.0, 2.0, 1.0], &[path]);
+            renderer.flush();
+        };
+
+        page(&mut renderer);
+        page(&mut renderer);
+        assert!(Rc::ptr_eq(
+            &renderer.pages[0].textures[&image.unwrap()],
+            &renderer.pages[1].textures[&image.unwrap()]
+        ));
+        renderer.update_texture(image.unwrap(), (0, 0), (1, 1), &[0, 255, 0, 255]);
+        page(&mut renderer);
+
+        let document = String::from_utf8_lossy(&renderer.document()).into_owned();
+        assert_eq!(document.matches("/ColorSpace /DeviceRGB").count(), 2);
+    }
+
+    #[test]
+    fn test_embeds_fonts() {
+        let mut renderer = PdfRenderer::new();
+        renderer.create_font(1, "sans", include_bytes!("../../resources/Roboto-Regular.ttf"));
+        let composite = CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        };
+        let color = Color::new(0.0, 0.0, 0.0, 1.0);
+        let paint = RenderPaint {
+            transform: Transform::new(),
+            extent: (0.0, 0.0),
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: color,
+            outer_color: color,
+            image: None,
+        };
+        let glyphs = [("H", (10.0, 20.0)), ("i", (18.0, 20.0))];
+        let text = RenderText {
+            font: 1,
+            size: 12.0,
+            blur: 0.0,
+            transform: Transform::new(),
+            glyphs: &glyphs,
+        };
+
+        renderer.viewport(100.0, 50.0, 1.0);
+        assert!(!renderer.begin_text(&RenderText { blur: 1.0, ..text }));
+        assert!(!renderer.begin_text(&RenderText {
+            glyphs: &[("\u{1F600}", (0.0, 0.0))],
+            ..text
+        }));
+        assert!(renderer.begin_text(&text));
+        renderer.triangles(&paint, composite, None, &[], 1.0);
+        renderer.triangles(&paint, composite, None, &[], 1.0);
+        renderer.end_text();
+        renderer.flush();
+
+        let document = String::from_utf8_lossy(&renderer.document()).into_owned();
+        assert_eq!(document.matches("/FontFile2").count(), 1);
+        assert!(document.contains("/BaseFont /Roboto-Regular /Encoding /Identity-H"));
+        assert!(document.contains("/Length1 "));
+        // One width for each glyph used.
+        let widths = &document[document.find("/W [").unwrap()..];
+        assert_eq!(widths[..widths.find("]]").unwrap()].matches('[').count(), 3);
+    }
+}
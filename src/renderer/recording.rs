@@ -0,0 +1,842 @@
+//! A renderer which records draw calls instead of rasterizing them.
+//!
+//! Every frame is captured as a [RecordedFrame] holding the fills, strokes and triangles NanoVG
+//! produced, together with their paint, scissor and composite state. Text can be recorded as the
+//! glyphs of its font instead, see [RecordingRenderer::record_text], and fills and strokes can keep the
+//! path they were drawn from, see [RecordingRenderer::record_shapes]. This makes drawing code
+//! testable without a GL context and is the base for the vector exporters.
+
+use std::collections::HashMap;
+use std::mem;
+
+use super::{
+    unpremultiply, CompositeState, ImageFlags, RenderPaint, RenderPath, RenderScissor, RenderShape, RenderText,
+    Renderer, TextureType, Vertex,
+};
+use {Direction, PathData, StrokeOptions, Transform};
+
+/// An owned copy of a [RenderPath].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedPath {
+    pub fill: Vec<Vertex>,
+    pub stroke: Vec<Vertex>,
+    pub closed: bool,
+    pub convex: bool,
+    pub winding: Direction,
+    pub bevel_count: usize,
+}
+
+impl<'a> From<&'a RenderPath<'a>> for RecordedPath {
+    fn from(path: &'a RenderPath<'a>) -> Self {
+        RecordedPath {
+            fill: path.fill.to_vec(),
+            stroke: path.stroke.to_vec(),
+            closed: path.closed,
+            convex: path.convex,
+            winding: path.winding,
+            bevel_count: path.bevel_count,
+        }
+    }
+}
+
+/// An owned copy of a [RenderText].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedText {
+    pub font: usize,
+    pub size: f32,
+    pub blur: f32,
+    pub transform: Transform,
+    pub glyphs: Vec<(String, (f32, f32))>,
+}
+
+impl<'a> From<&'a RenderText<'a>> for RecordedText {
+    fn from(text: &'a RenderText<'a>) -> Self {
+        RecordedText {
+            font: text.font,
+            size: text.size,
+            blur: text.blur,
+            transform: text.transform,
+            glyphs: text
+                .glyphs
+                .iter()
+                .map(|&(glyph, origin)| (glyph.to_owned(), origin))
+                .collect(),
+        }
+    }
+}
+
+/// An owned copy of a [RenderShape].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedShape {
+    pub path: PathData,
+    pub transform: Transform,
+    pub stroke: Option<StrokeOptions>,
+}
+
+impl<'a> From<&'a RenderShape<'a>> for RecordedShape {
+    fn from(shape: &'a RenderShape<'a>) -> Self {
+        RecordedShape {
+            path: shape.path.clone(),
+            transform: shape.transform,
+            stroke: shape.stroke.cloned(),
+        }
+    }
+}
+
+/// A font passed to [Renderer::create_font].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFont {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// A single recorded call of [Renderer::fill], [Renderer::stroke] or [Renderer::triangles],
+/// or a line of text recorded with [RecordingRenderer::record_text].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    Fill {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        bounds: [f32; 4],
+        paths: Vec<RecordedPath>,
+        /// The path the fill was drawn from, when recorded with [RecordingRenderer::record_shapes].
+        shape: Option<RecordedShape>,
+    },
+    Stroke {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: Vec<RecordedPath>,
+        /// The path the stroke was drawn from, when recorded with [RecordingRenderer::record_shapes].
+        shape: Option<RecordedShape>,
+    },
+    Triangles {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        vertices: Vec<Vertex>,
+    },
+    Text {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        text: RecordedText,
+    },
+}
+
+impl DrawCall {
+    /// The paint the call was drawn with.
+    pub fn paint(&self) -> &RenderPaint {
+        match *self {
+            DrawCall::Fill { ref paint, .. } => paint,
+            DrawCall::Stroke { ref paint, .. } => paint,
+            DrawCall::Triangles { ref paint, .. } => paint,
+            DrawCall::Text { ref paint, .. } => paint,
+        }
+    }
+
+    /// The blend factors the call was drawn with.
+    pub fn composite(&self) -> CompositeState {
+        match *self {
+            DrawCall::Fill { composite, .. } => composite,
+            DrawCall::Stroke { composite, .. } => composite,
+            DrawCall::Triangles { composite, .. } => composite,
+            DrawCall::Text { composite, .. } => composite,
+        }
+    }
+
+    /// The scissor the call was clipped with, if any.
+    pub fn scissor(&self) -> Option<RenderScissor> {
+        match *self {
+            DrawCall::Fill { scissor, .. } => scissor,
+            DrawCall::Stroke { scissor, .. } => scissor,
+            DrawCall::Triangles { scissor, .. } => scissor,
+            DrawCall::Text { scissor, .. } => scissor,
+        }
+    }
+
+    /// The width of the antialiasing fringe of the call.
+    pub fn fringe(&self) -> f32 {
+        match *self {
+            DrawCall::Fill { fringe, .. } => fringe,
+            DrawCall::Stroke { fringe, .. } => fringe,
+            DrawCall::Triangles { fringe, .. } => fringe,
+            DrawCall::Text { fringe, .. } => fringe,
+        }
+    }
+
+    /// The recorded paths of a fill or stroke. Empty for triangles and text.
+    pub fn paths(&self) -> &[RecordedPath] {
+        match *self {
+            DrawCall::Fill { ref paths, .. } => paths,
+            DrawCall::Stroke { ref paths, .. } => paths,
+            DrawCall::Triangles { .. } | DrawCall::Text { .. } => &[],
+        }
+    }
+}
+
+/// All draw calls of a single frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    /// The width passed to [Context::frame](../../struct.Context.html#method.frame).
+    pub width: f32,
+    /// The height passed to [Context::frame](../../struct.Context.html#method.frame).
+    pub height: f32,
+    pub device_pixel_ratio: f32,
+    pub calls: Vec<DrawCall>,
+}
+
+/// A texture created by NanoVG, with its current pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedTexture {
+    pub texture_type: TextureType,
+    pub width: usize,
+    pub height: usize,
+    pub flags: ImageFlags,
+    pub data: Vec<u8>,
+}
+
+impl RecordedTexture {
+    /// The pixels of the texture as straight alpha RGBA, regardless of its type.
+    /// Alpha textures, like the font atlas, become white with the texture as alpha.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        match self.texture_type {
+            TextureType::Alpha => self.data.iter().flat_map(|&a| vec![255, 255, 255, a]).collect(),
+            TextureType::Rgba if self.flags.premultiplied => {
+                let mut rgba = self.data.clone();
+                unpremultiply(&mut rgba);
+                rgba
+            }
+            TextureType::Rgba => self.data.clone(),
+        }
+    }
+}
+
+/// A renderer which records every draw call.
+///
+/// Frames are recorded when NanoVG flushes them at the end of [Context::frame](../../struct.Context.html#method.frame),
+/// cancelled frames are discarded.
+#[derive(Debug, Default)]
+pub struct RecordingRenderer {
+    frames: Vec<RecordedFrame>,
+    current: Option<RecordedFrame>,
+    textures: HashMap<usize, RecordedTexture>,
+    next_texture: usize,
+    fonts: HashMap<usize, RecordedFont>,
+    record_text: bool,
+    /// The text between `begin_text` and `end_text`, until the first `triangles` call records it.
+    text: Option<Option<RecordedText>>,
+    record_shapes: bool,
+    /// The path between `begin_shape` and `end_shape`, until the first `fill` or `stroke` call records it.
+    shape: Option<RecordedShape>,
+}
+
+impl RecordingRenderer {
+    /// Create a new renderer without any recorded frames.
+    pub fn new() -> Self {
+        RecordingRenderer {
+            frames: Vec::new(),
+            current: None,
+            textures: HashMap::new(),
+            next_texture: 1,
+            fonts: HashMap::new(),
+            record_text: false,
+            text: None,
+            record_shapes: false,
+            shape: None,
+        }
+    }
+
+    /// Record text as [DrawCall::Text] with the glyphs of its font, instead of the glyph quads
+    /// from the font atlas.
+    pub fn record_text(mut self) -> Self {
+        self.record_text = true;
+        self
+    }
+
+    /// Record the path every fill and stroke was drawn from, with its transform and stroke options,
+    /// in [DrawCall::Fill] and [DrawCall::Stroke].
+    pub fn record_shapes(mut self) -> Self {
+        self.record_shapes = true;
+        self
+    }
+
+    /// All frames recorded so far, oldest first.
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    /// The most recently recorded frame.
+    pub fn last_frame(&self) -> Option<&RecordedFrame> {
+        self.frames.last()
+    }
+
+    /// Remove and return all recorded frames.
+    pub fn take_frames(&mut self) -> Vec<RecordedFrame> {
+        mem::take(&mut self.frames)
+    }
+
+    /// Get a texture by the handle stored in [RenderPaint::image].
+    pub fn texture(&self, image: usize) -> Option<&RecordedTexture> {
+        self.textures.get(&image)
+    }
+
+    /// Get a font by the handle stored in [RecordedText::font].
+    pub fn font(&self, font: usize) -> Option<&RecordedFont> {
+        self.fonts.get(&font)
+    }
+
+    fn record(&mut self, call: DrawCall) {
+        if let Some(ref mut frame) = self.current {
+            frame.calls.push(call);
+        }
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn create_texture(
+        &mut self,
+        texture_type: TextureType,
+        width: usize,
+        height: usize,
+        flags: ImageFlags,
+        data: Option<&[u8]>,
+    ) -> Option<usize> {
+        let size = width * height * texture_type.bytes_per_pixel();
+        let data = match data {
+            Some(data) => data[..size].to_vec(),
+            None => vec![0; size],
+        };
+        let image = self.next_texture.max(1);
+        self.next_texture = image + 1;
+        self.textures.insert(
+            image,
+            RecordedTexture {
+                texture_type,
+                width,
+                height,
+                flags,
+                data,
+            },
+        );
+        Some(image)
+    }
+
+    fn delete_texture(&mut self, image: usize) -> bool {
+        self.textures.remove(&image).is_some()
+    }
+
+    fn update_texture(&mut self, image: usize, (x, y): (usize, usize), (w, h): (usize, usize), data: &[u8]) -> bool {
+        let texture = match self.textures.get_mut(&image) {
+            Some(texture) => texture,
+            None => return false,
+        };
+        let bpp = texture.texture_type.bytes_per_pixel();
+        let stride = texture.width * bpp;
+        let w = w.min(texture.width.saturating_sub(x));
+        for row in y..(y + h).min(texture.height) {
+            let start = row * stride + x * bpp;
+            let end = start + w * bpp;
+            texture.data[start..end].copy_from_slice(&data[start..end]);
+        }
+        true
+    }
+
+    fn texture_size(&self, image: usize) -> Option<(usize, usize)> {
+        self.textures.get(&image).map(|texture| (texture.width, texture.height))
+    }
+
+    fn viewport(&mut self, width: f32, height: f32, device_pixel_ratio: f32) {
+        self.current = Some(RecordedFrame {
+            width,
+            height,
+            device_pixel_ratio,
+            calls: Vec::new(),
+        });
+    }
+
+    fn cancel(&mut self) {
+        self.current = None;
+    }
+
+    fn flush(&mut self) {
+        if let Some(frame) = self.current.take() {
+            self.frames.push(frame);
+        }
+    }
+
+    fn fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        bounds: [f32; 4],
+        paths: &[RenderPath],
+    ) {
+        let shape = self.shape.take();
+        self.record(DrawCall::Fill {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            bounds,
+            paths: paths.iter().map(RecordedPath::from).collect(),
+            shape,
+        });
+    }
+
+    fn stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[RenderPath],
+    ) {
+        let shape = self.shape.take();
+        self.record(DrawCall::Stroke {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            stroke_width,
+            paths: paths.iter().map(RecordedPath::from).collect(),
+            shape,
+        });
+    }
+
+    fn triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        vertices: &[Vertex],
+        fringe: f32,
+    ) {
+        if let Some(ref mut text) = self.text {
+            if let Some(text) = text.take() {
+                self.record(DrawCall::Text {
+                    paint: *paint,
+                    composite,
+                    scissor,
+                    fringe,
+                    text,
+                });
+            }
+            return;
+        }
+        self.record(DrawCall::Triangles {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            vertices: vertices.to_vec(),
+        });
+    }
+
+    fn create_font(&mut self, font: usize, name: &str, data: &[u8]) {
+        self.fonts.insert(
+            font,
+            RecordedFont {
+                name: name.to_owned(),
+                data: data.to_vec(),
+            },
+        );
+    }
+
+    fn begin_text(&mut self, text: &RenderText) -> bool {
+        if self.record_text {
+            self.text = Some(Some(RecordedText::from(text)));
+        }
+        self.record_text
+    }
+
+    fn end_text(&mut self) {
+        self.text = None;
+    }
+
+    fn begin_shape(&mut self, shape: &RenderShape) {
+        if self.record_shapes {
+            self.shape = Some(RecordedShape::from(shape));
+        }
+    }
+
+    fn end_shape(&mut self) {
+        self.shape = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+    use {
+        Alignment, BasicCompositeOperation, BlendFactor, Clip, Color, CompositeOperation, ContextBuilder, FillOptions,
+        Font, Frame, GlyphPosition, PathData, PathOptions, Scissor, StrokeOptions, TextOptions, Transform,
+    };
+
+    #[test]
+    fn test_records_text() {
+        let mut renderer = RecordingRenderer::new().record_text();
+        let composite = CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        };
+        let color = Color::new(0.0, 0.0, 0.0, 1.0);
+        let paint = RenderPaint {
+            transform: Transform::new(),
+            extent: (0.0, 0.0),
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: color,
+            outer_color: color,
+            image: Some(1),
+        };
+        let glyphs = [("a", (0.0, 10.0)), ("b", (6.0, 10.0))];
+        let vertex = Vertex {
+            x: 0.0,
+            y: 0.0,
+            u: 0.0,
+            v: 0.0,
+        };
+
+        renderer.create_font(1, "sans", &[1, 2, 3]);
+        renderer.viewport(100.0, 100.0, 1.0);
+        let began = renderer.begin_text(&RenderText {
+            font: 1,
+            size: 12.0,
+            blur: 0.0,
+            transform: Transform::new().translate(5.0, 0.0),
+            glyphs: &glyphs,
+        });
+        assert!(began);
+        // NanoVG may draw a line of text in several batches.
+        renderer.triangles(&paint, composite, None, &[vertex; 6], 1.0);
+        renderer.triangles(&paint, composite, None, &[vertex; 6], 1.0);
+        renderer.end_text();
+        renderer.triangles(&paint, composite, None, &[vertex; 6], 1.0);
+        renderer.flush();
+
+        assert_eq!(renderer.font(1).unwrap().name, "sans");
+        let calls = &renderer.last_frame().unwrap().calls;
+        assert_eq!(calls.len(), 2);
+        match calls[0] {
+            DrawCall::Text { ref text, .. } => {
+                assert_eq!(text.transform.matrix[4], 5.0);
+                assert_eq!(
+                    text.glyphs,
+                    vec![("a".to_owned(), (0.0, 10.0)), ("b".to_owned(), (6.0, 10.0))]
+                );
+            }
+            _ => panic!("expected text"),
+        }
+        assert!(!RecordingRenderer::new().begin_text(&RenderText {
+            font: 1,
+            size: 12.0,
+            blur: 0.0,
+            transform: Transform::new(),
+            glyphs: &glyphs,
+        }));
+    }
+
+    #[test]
+    fn test_context_records_fill() {
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new())
+            .unwrap();
+        let clip = Clip::Scissor(Scissor {
+            x: 10.0,
+            y: 20.0,
+            width: 30.0,
+            height: 40.0,
+            transform: None,
+        });
+
+        context.frame((100.0, 100.0), 2.0, |frame| {
+            frame.path(
+                |path| {
+                    path.rect((0.0, 0.0), (50.0, 50.0));
+                    path.fill(Color::new(1.0, 0.0, 0.0, 1.0), FillOptions { antialias: false });
+                },
+                PathOptions {
+                    clip,
+                    composite_operation: CompositeOperation::Basic(BasicCompositeOperation::SourceOver),
+                    ..Default::default()
+                },
+            );
+        });
+
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        let frame = recorder.last_frame().unwrap();
+        assert_eq!(
+            (frame.width, frame.height, frame.device_pixel_ratio),
+            (100.0, 100.0, 2.0)
+        );
+        assert_eq!(frame.calls.len(), 1);
+
+        let call = &frame.calls[0];
+        assert_eq!(call.composite().rgb_source, BlendFactor::One);
+        assert_eq!(call.composite().rgb_destination, BlendFactor::OneMinusSourceAlpha);
+        let scissor = call.scissor().unwrap();
+        assert_eq!(scissor.extent, (15.0, 20.0));
+        assert_eq!((scissor.transform.matrix[4], scissor.transform.matrix[5]), (25.0, 40.0));
+        match *call {
+            DrawCall::Fill { bounds, ref paths, .. } => {
+                assert_eq!(bounds, [0.0, 0.0, 50.0, 50.0]);
+                assert_eq!(paths.len(), 1);
+                assert_eq!(paths[0].fill.len(), 4);
+            }
+            _ => panic!("expected a fill"),
+        }
+    }
+
+    #[test]
+    fn test_context_cancels_failed_frames() {
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new())
+            .unwrap();
+        let fill_rect = |frame: &Frame| {
+            frame.path(
+                |path| {
+                    path.rect((0.0, 0.0), (50.0, 50.0));
+                    path.fill(Color::new(1.0, 0.0, 0.0, 1.0), Default::default());
+                },
+                Default::default(),
+            );
+        };
+
+        let result = context.try_frame((100.0, 100.0), 1.0, |frame| {
+            fill_rect(&frame);
+            Err::<(), _>("bad widget")
+        });
+        assert_eq!(result, Err("bad widget"));
+        let panicked = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            context.frame((100.0, 100.0), 1.0, |frame| {
+                fill_rect(&frame);
+                panic!("bad widget");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert!(context.renderer::<RecordingRenderer>().unwrap().frames().is_empty());
+        assert!(context.screenshot().is_none());
+
+        let result = context.try_frame((100.0, 100.0), 1.0, |frame| {
+            fill_rect(&frame);
+            Ok::<_, ()>(1)
+        });
+        assert_eq!(result, Ok(1));
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        assert_eq!(recorder.frames().len(), 1);
+        assert_eq!(recorder.last_frame().unwrap().calls.len(), 1);
+    }
+
+    #[test]
+    fn test_context_records_dashes() {
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new())
+            .unwrap();
+        let color = Color::new(0.0, 0.0, 0.0, 1.0);
+        context.frame((100.0, 100.0), 1.0, |frame| {
+            frame.path(
+                |path| {
+                    path.rect((0.0, 0.0), (50.0, 50.0));
+                    path.fill(color, Default::default());
+                },
+                Default::default(),
+            );
+            frame.path(
+                |path| {
+                    path.move_to((0.0, 60.0));
+                    path.line_to((10.0, 60.0));
+                    let options = StrokeOptions {
+                        dash_array: vec![2.0, 2.0],
+                        ..Default::default()
+                    };
+                    path.stroke(color, options);
+                    path.stroke(color, Default::default());
+                },
+                Default::default(),
+            );
+        });
+
+        // Only the commands of its own path are dashed, and the path is solid again after.
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        let subpaths: Vec<usize> = recorder
+            .last_frame()
+            .unwrap()
+            .calls
+            .iter()
+            .filter_map(|call| match *call {
+                DrawCall::Stroke { ref paths, .. } => Some(paths.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(subpaths, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_context_records_shapes() {
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new().record_shapes())
+            .unwrap();
+        let color = Color::new(0.0, 0.0, 0.0, 1.0);
+        let stroke = StrokeOptions {
+            width: 3.0,
+            ..Default::default()
+        };
+        context.frame((100.0, 100.0), 1.0, |frame| {
+            frame.path(
+                |path| {
+                    path.rect((0.0, 0.0), (50.0, 50.0));
+                    path.fill(color, Default::default());
+                    path.stroke(color, stroke.clone());
+                },
+                PathOptions {
+                    transform: Some(Transform::new().with_translation(5.0, 10.0)),
+                    ..Default::default()
+                },
+            );
+        });
+
+        let mut rect = PathData::new();
+        rect.rect((0.0, 0.0), (50.0, 50.0));
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        let calls = &recorder.last_frame().unwrap().calls;
+        assert_eq!(calls.len(), 2);
+        for (call, options) in calls.iter().zip(&[None, Some(stroke)]) {
+            let shape = match *call {
+                DrawCall::Fill { ref shape, .. } | DrawCall::Stroke { ref shape, .. } => shape.as_ref().unwrap(),
+                _ => panic!("expected a fill or stroke"),
+            };
+            assert_eq!(shape.path, rect);
+            assert_eq!(shape.transform.matrix, [1.0, 0.0, 0.0, 1.0, 5.0, 10.0]);
+            assert_eq!(&shape.stroke, options);
+        }
+    }
+
+    /// Draw `text` along `path`, returning the width of the text, the glyph centers of left aligned text
+    /// starting at zero, and the recorded glyphs.
+    fn record_text_on_path(
+        path: &PathData,
+        offset: f32,
+        text: &str,
+        align: Alignment,
+    ) -> (f32, Vec<f32>, Vec<RecordedText>) {
+        let context = ContextBuilder::new()
+            .build_with_renderer(RecordingRenderer::new().record_text())
+            .unwrap();
+        let font = Font::from_memory(&context, "sans", include_bytes!("../../resources/Roboto-Regular.ttf")).unwrap();
+        let options = TextOptions {
+            size: 20.0,
+            align,
+            ..Default::default()
+        };
+        let mut width = 0.0;
+        let mut centers = Vec::new();
+        context.frame((1000.0, 1000.0), 1.0, |frame| {
+            let left = TextOptions {
+                align: Alignment::new().left(),
+                ..options
+            };
+            width = frame.text_bounds(font, (0.0, 0.0), text, left).0;
+            let glyphs: Vec<GlyphPosition> = frame.text_glyph_positions((0.0, 0.0), text).collect();
+            for (i, glyph) in glyphs.iter().enumerate() {
+                let end = glyphs.get(i + 1).map_or(width, |next| next.x);
+                centers.push((glyph.x + end) * 0.5);
+            }
+            frame.text_on_path(font, path, offset, text, options);
+        });
+
+        let recorder = context.renderer::<RecordingRenderer>().unwrap();
+        let texts = recorder
+            .last_frame()
+            .unwrap()
+            .calls
+            .iter()
+            .filter_map(|call| match *call {
+                DrawCall::Text { ref text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        (width, centers, texts)
+    }
+
+    fn assert_close((x1, y1): (f32, f32), (x2, y2): (f32, f32)) {
+        assert!(
+            (x1 - x2).abs() < 1e-3 && (y1 - y2).abs() < 1e-3,
+            "{:?} != {:?}",
+            (x1, y1),
+            (x2, y2)
+        );
+    }
+
+    #[test]
+    fn test_context_records_text_on_path() {
+        let mut line = PathData::new();
+        line.move_to((0.0, 0.0));
+        line.line_to((1000.0, 0.0));
+
+        // Every glyph is drawn on its own, centered on the line where its center is.
+        let (_, centers, texts) = record_text_on_path(&line, 10.0, "Path", Alignment::new().left());
+        assert_eq!(texts.len(), 4);
+        for ((text, &center), c) in texts.iter().zip(&centers).zip("Path".chars()) {
+            assert_eq!(text.glyphs.len(), 1);
+            assert_eq!(text.glyphs[0].0, c.to_string());
+            let matrix = text.transform.matrix;
+            assert_close((matrix[4], matrix[5]), (10.0 + center, 0.0));
+            assert_close((matrix[0], matrix[1]), (1.0, 0.0));
+        }
+
+        // Right and center alignment end and center the text at the offset.
+        let (width, centers, texts) = record_text_on_path(&line, 500.0, "Path", Alignment::new().right());
+        assert_close((texts[0].transform.matrix[4], 0.0), (500.0 - width + centers[0], 0.0));
+        let (width, centers, texts) = record_text_on_path(&line, 500.0, "Path", Alignment::new().center());
+        assert_close(
+            (texts[0].transform.matrix[4], 0.0),
+            (500.0 - width * 0.5 + centers[0], 0.0),
+        );
+
+        // Glyphs are rotated to the tangent of curves.
+        let mut circle = PathData::new();
+        circle.circle((500.0, 500.0), 100.0);
+        let measure = circle.measure();
+        let (_, centers, texts) = record_text_on_path(&circle, 50.0, "Path", Alignment::new().left());
+        assert_eq!(texts.len(), 4);
+        for (text, &center) in texts.iter().zip(&centers) {
+            let matrix = text.transform.matrix;
+            assert_close((matrix[4], matrix[5]), measure.point_at(50.0 + center).unwrap());
+            assert_close((matrix[0], matrix[1]), measure.tangent_at(50.0 + center).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_context_drops_text_off_path() {
+        let mut line = PathData::new();
+        line.move_to((0.0, 0.0));
+        line.line_to((1000.0, 0.0));
+
+        // Centered on either end of the line, only the glyphs on the line are drawn.
+        for &(offset, before) in &[(0.0, false), (1000.0, true)] {
+            let (width, centers, texts) = record_text_on_path(&line, offset, "Path", Alignment::new().center());
+            let kept: Vec<String> = "Path"
+                .chars()
+                .zip(&centers)
+                .filter(|&(_, &center)| (center < width * 0.5) == before)
+                .map(|(c, _)| c.to_string())
+                .collect();
+            let drawn: Vec<String> = texts.iter().map(|text| text.glyphs[0].0.clone()).collect();
+            assert!(!kept.is_empty() && kept.len() < 4);
+            assert_eq!(drawn, kept);
+        }
+    }
+}
@@ -0,0 +1,1010 @@
+//! A renderer which rasterizes on the CPU into an in-memory RGBA framebuffer.
+//!
+//! The rasterizer follows the OpenGL backend closely: fills are resolved with a non-zero winding
+//! "stencil" followed by a cover pass, antialiasing comes from the fringe NanoVG generates around
+//! every shape, and paints, scissors and blend factors are evaluated with the same formulas the
+//! GL shaders use. Pixels are sampled at their centers, there is no multisampling.
+
+use std::collections::HashMap;
+use std::mem;
+
+use super::{
+    unpremultiply, CompositeState, ImageFlags, RenderPaint, RenderPath, RenderScissor, Renderer, TextureType, Vertex,
+};
+use {BlendFactor, Color, Screenshot, Transform};
+
+struct Texture {
+    texture_type: TextureType,
+    width: usize,
+    height: usize,
+    flags: ImageFlags,
+    data: Vec<u8>,
+}
+
+impl Texture {
+    fn texel(&self, x: isize, y: isize) -> [f32; 4] {
+        let x = wrap(x, self.width, self.flags.repeat_x);
+        let y = wrap(y, self.height, self.flags.repeat_y);
+        match self.texture_type {
+            TextureType::Alpha => {
+                let a = self.data[y * self.width + x] as f32 / 255.0;
+                [a, a, a, a]
+            }
+            TextureType::Rgba => {
+                let i = (y * self.width + x) * 4;
+                let p = &self.data[i..i + 4];
+                [
+                    p[0] as f32 / 255.0,
+                    p[1] as f32 / 255.0,
+                    p[2] as f32 / 255.0,
+                    p[3] as f32 / 255.0,
+                ]
+            }
+        }
+    }
+
+    /// Sample the texture at normalized coordinates, like `texture2D` does.
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.0; 4];
+        }
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        if self.flags.nearest {
+            return self.texel((x + 0.5).floor() as isize, (y + 0.5).floor() as isize);
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let t00 = self.texel(x0, y0);
+        let t10 = self.texel(x0 + 1, y0);
+        let t01 = self.texel(x0, y0 + 1);
+        let t11 = self.texel(x0 + 1, y0 + 1);
+        let mut result = [0.0; 4];
+        for i in 0..4 {
+            let top = t00[i] + (t10[i] - t00[i]) * fx;
+            let bottom = t01[i] + (t11[i] - t01[i]) * fx;
+            result[i] = top + (bottom - top) * fy;
+        }
+        result
+    }
+}
+
+fn wrap(i: isize, size: usize, repeat: bool) -> usize {
+    let size = size as isize;
+    if repeat {
+        (((i % size) + size) % size) as usize
+    } else {
+        i.max(0).min(size - 1) as usize
+    }
+}
+
+fn premultiply(color: Color) -> [f32; 4] {
+    let a = color.alpha();
+    [color.red() * a, color.green() * a, color.blue() * a, a]
+}
+
+fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    value.max(min).min(max)
+}
+
+fn apply(transform: &Transform, (x, y): (f32, f32)) -> (f32, f32) {
+    let m = &transform.matrix;
+    (x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5])
+}
+
+/// Signed distance to a rounded rectangle centered at the origin, `sdroundrect` in the GL shader.
+pub(super) fn rounded_rect_distance((x, y): (f32, f32), (ex, ey): (f32, f32), radius: f32) -> f32 {
+    let (dx, dy) = (x.abs() - (ex - radius), y.abs() - (ey - radius));
+    let outside = (dx.max(0.0) * dx.max(0.0) + dy.max(0.0) * dy.max(0.0)).sqrt();
+    dx.max(dy).min(0.0) + outside - radius
+}
+
+/// Inverse transform, extent and edge scale of a scissor.
+type ScissorState = (Transform, (f32, f32), (f32, f32));
+
+/// The per draw call uniforms of the GL shader.
+struct Shader<'a> {
+    inverse: Transform,
+    extent: (f32, f32),
+    radius: f32,
+    feather: f32,
+    inner: [f32; 4],
+    outer: [f32; 4],
+    texture: Option<&'a Texture>,
+    scissor: Option<ScissorState>,
+    stroke_mult: f32,
+}
+
+impl<'a> Shader<'a> {
+    fn new(
+        paint: &RenderPaint,
+        scissor: &Option<RenderScissor>,
+        textures: &'a HashMap<usize, Texture>,
+        fringe: f32,
+        width: f32,
+    ) -> Self {
+        let texture = paint.image.and_then(|image| textures.get(&image));
+        let transform = match texture {
+            Some(texture) if texture.flags.flip_y => {
+                let half = paint.extent.1 * 0.5;
+                Transform::new().with_translation(0.0, -half)
+                    * Transform::new().with_scale(1.0, -1.0)
+                    * Transform::new().with_translation(0.0, half)
+                    * paint.transform
+            }
+            _ => paint.transform,
+        };
+
+        let scissor = scissor.map(|scissor| {
+            let m = &scissor.transform.matrix;
+            let scale = if fringe > 0.0 {
+                (
+                    (m[0] * m[0] + m[2] * m[2]).sqrt() / fringe,
+                    (m[1] * m[1] + m[3] * m[3]).sqrt() / fringe,
+                )
+            } else {
+                (1.0e6, 1.0e6)
+            };
+            let inverse = scissor.transform.try_inverse().unwrap_or_else(Transform::new);
+            (inverse, scissor.extent, scale)
+        });
+
+        Shader {
+            inverse: transform.try_inverse().unwrap_or_else(Transform::new),
+            extent: paint.extent,
+            radius: paint.radius,
+            feather: paint.feather,
+            inner: premultiply(paint.inner_color),
+            outer: premultiply(paint.outer_color),
+            texture,
+            scissor,
+            stroke_mult: if fringe > 0.0 {
+                (width * 0.5 + fringe * 0.5) / fringe
+            } else {
+                1.0
+            },
+        }
+    }
+
+    fn scissor_mask(&self, p: (f32, f32)) -> f32 {
+        match self.scissor {
+            Some((ref inverse, (ex, ey), (sx, sy))) => {
+                let (x, y) = apply(inverse, p);
+                let mx = 0.5 - (x.abs() - ex) * sx;
+                let my = 0.5 - (y.abs() - ey) * sy;
+                clamp(mx, 0.0, 1.0) * clamp(my, 0.0, 1.0)
+            }
+            None => 1.0,
+        }
+    }
+
+    fn stroke_mask(&self, u: f32, v: f32) -> f32 {
+        ((1.0 - (u * 2.0 - 1.0).abs()) * self.stroke_mult).min(1.0) * v.min(1.0)
+    }
+
+    /// Premultiplied color of the paint at `p`, without any coverage applied.
+    fn paint(&self, p: (f32, f32)) -> [f32; 4] {
+        match self.texture {
+            Some(texture) => {
+                let (x, y) = apply(&self.inverse, p);
+                let mut color = texture.sample(x / self.extent.0, y / self.extent.1);
+                if texture.texture_type == TextureType::Rgba && !texture.flags.premultiplied {
+                    color = [color[0] * color[3], color[1] * color[3], color[2] * color[3], color[3]];
+                }
+                [
+                    color[0] * self.inner[0],
+                    color[1] * self.inner[1],
+                    color[2] * self.inner[2],
+                    color[3] * self.inner[3],
+                ]
+            }
+            None => {
+                let pt = apply(&self.inverse, p);
+                let feather = self.feather.max(1.0e-5);
+                let d = rounded_rect_distance(pt, self.extent, self.radius) + self.feather * 0.5;
+                let d = clamp(d / feather, 0.0, 1.0);
+                let mut color = self.inner;
+                for (c, outer) in color.iter_mut().zip(self.outer.iter()) {
+                    *c += (outer - *c) * d;
+                }
+                color
+            }
+        }
+    }
+
+    /// Color of a textured triangle at `p`, sampling the texture at `(u, v)`.
+    fn textured(&self, p: (f32, f32), u: f32, v: f32) -> [f32; 4] {
+        let mut color = match self.texture {
+            Some(texture) => {
+                let color = texture.sample(u, v);
+                if texture.texture_type == TextureType::Rgba && !texture.flags.premultiplied {
+                    [color[0] * color[3], color[1] * color[3], color[2] * color[3], color[3]]
+                } else {
+                    color
+                }
+            }
+            None => [1.0; 4],
+        };
+        let scissor = self.scissor_mask(p);
+        for (c, inner) in color.iter_mut().zip(self.inner.iter()) {
+            *c *= scissor * inner;
+        }
+        color
+    }
+}
+
+fn blend_factor(factor: BlendFactor, src: &[f32; 4], dst: &[f32; 4], channel: usize) -> f32 {
+    match factor {
+        BlendFactor::Zero => 0.0,
+        BlendFactor::One => 1.0,
+        BlendFactor::SourceColor => src[channel],
+        BlendFactor::OneMinusSourceColor => 1.0 - src[channel],
+        BlendFactor::DestinationColor => dst[channel],
+        BlendFactor::OneMinusDestinationColor => 1.0 - dst[channel],
+        BlendFactor::SourceAlpha => src[3],
+        BlendFactor::OneMinusSourceAlpha => 1.0 - src[3],
+        BlendFactor::DestinationAlpha => dst[3],
+        BlendFactor::OneMinusDestinationAlpha => 1.0 - dst[3],
+        BlendFactor::SourceAlphaSaturate => {
+            if channel == 3 {
+                1.0
+            } else {
+                src[3].min(1.0 - dst[3])
+            }
+        }
+    }
+}
+
+/// Draw calls buffered until the end of the frame, so a frame can be cancelled.
+enum Call {
+    Fill {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        paths: Vec<(Vec<Vertex>, Vec<Vertex>)>,
+    },
+    Stroke {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: Vec<Vec<Vertex>>,
+    },
+    Triangles {
+        paint: RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        vertices: Vec<Vertex>,
+    },
+}
+
+/// Pixel bounds touched by the current draw call.
+#[derive(Clone, Copy)]
+struct Dirty {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize,
+}
+
+impl Dirty {
+    fn empty() -> Self {
+        Dirty {
+            min_x: usize::MAX,
+            min_y: usize::MAX,
+            max_x: 0,
+            max_y: 0,
+        }
+    }
+
+    fn add(&mut self, x: usize, y: usize) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x + 1);
+        self.max_y = self.max_y.max(y + 1);
+    }
+}
+
+/// A renderer which draws into an RGBA framebuffer in memory.
+///
+/// The framebuffer is sized `width * device_pixel_ratio` by `height * device_pixel_ratio`
+/// of the last [Context::frame](../../struct.Context.html#method.frame) and holds premultiplied
+/// colors, just like a GL framebuffer drawn to by NanoVG.
+pub struct SoftwareRenderer {
+    width: usize,
+    height: usize,
+    scale: f32,
+    pixels: Vec<u8>,
+    clear_color: Option<Color>,
+    textures: HashMap<usize, Texture>,
+    next_texture: usize,
+    calls: Vec<Call>,
+    coverage: Vec<f32>,
+    winding: Vec<i32>,
+}
+
+impl SoftwareRenderer {
+    /// Create a new renderer with an empty framebuffer.
+    pub fn new() -> Self {
+        SoftwareRenderer {
+            width: 0,
+            height: 0,
+            scale: 1.0,
+            pixels: Vec::new(),
+            clear_color: None,
+            textures: HashMap::new(),
+            next_texture: 1,
+            calls: Vec::new(),
+            coverage: Vec::new(),
+            winding: Vec::new(),
+        }
+    }
+
+    /// Clear the framebuffer with `color` at the start of every frame.
+    pub fn with_clear_color(mut self, color: Color) -> Self {
+        self.clear_color = Some(color);
+        self
+    }
+
+    /// Width of the framebuffer in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the framebuffer in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The framebuffer as premultiplied RGBA bytes, row by row from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get the color of a single pixel, with alpha no longer premultiplied.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y * self.width + x) * 4;
+        let p = &self.pixels[i..i + 4];
+        let a = p[3] as f32 / 255.0;
+        if a == 0.0 {
+            return Some(Color::new(0.0, 0.0, 0.0, 0.0));
+        }
+        Some(Color::new(
+            p[0] as f32 / 255.0 / a,
+            p[1] as f32 / 255.0 / a,
+            p[2] as f32 / 255.0 / a,
+            a,
+        ))
+    }
+
+    /// Copy the framebuffer into RGBA bytes with straight (not premultiplied) alpha.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = self.pixels.clone();
+        unpremultiply(&mut rgba);
+        rgba
+    }
+
+    /// The framebuffer as a [Screenshot](../struct.Screenshot.html).
+    pub fn screenshot(&self) -> Screenshot {
+        Screenshot::new(self.width, self.height, self.scale, self.to_rgba())
+    }
+
+    /// Fill the whole framebuffer with `color`.
+    pub fn clear(&mut self, color: Color) {
+        let c = premultiply(color);
+        let bytes = [to_byte(c[0]), to_byte(c[1]), to_byte(c[2]), to_byte(c[3])];
+        for p in self.pixels.chunks_mut(4) {
+            p.copy_from_slice(&bytes);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height * 4];
+        self.coverage = vec![0.0; width * height];
+        self.winding = vec![0; width * height];
+    }
+
+    /// Walk all pixels whose centers are covered by the triangle `(a, b, c)`.
+    /// `visit` receives the pixel, the interpolated `(u, v)` and the orientation of the triangle.
+    fn rasterize<F: FnMut(usize, usize, f32, f32, i32)>(&self, a: &Vertex, b: &Vertex, c: &Vertex, mut visit: F) {
+        let s = self.scale;
+        let (a, mut b, mut c) = (
+            (a.x * s, a.y * s, a.u, a.v),
+            (b.x * s, b.y * s, b.u, b.v),
+            (c.x * s, c.y * s, c.u, c.v),
+        );
+        let edge = |p: (f32, f32), a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)| {
+            (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+        };
+        let mut area = edge((c.0, c.1), a, b);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+        let sign = if area < 0.0 {
+            mem::swap(&mut b, &mut c);
+            area = -area;
+            -1
+        } else {
+            1
+        };
+
+        // Top-left fill rule, so pixels on edges shared by two triangles are only visited once.
+        let top_left = |a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)| {
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            dy > 0.0 || (dy == 0.0 && dx < 0.0)
+        };
+        let (tl0, tl1, tl2) = (top_left(b, c), top_left(c, a), top_left(a, b));
+
+        let min_x = a.0.min(b.0).min(c.0).floor().max(0.0) as usize;
+        let min_y = a.1.min(b.1).min(c.1).floor().max(0.0) as usize;
+        let max_x = (a.0.max(b.0).max(c.0).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (a.1.max(b.1).max(c.1).ceil().max(0.0) as usize).min(self.height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = edge(p, b, c);
+                let w1 = edge(p, c, a);
+                let w2 = edge(p, a, b);
+                let inside = |w: f32, tl: bool| w > 0.0 || (w == 0.0 && tl);
+                if inside(w0, tl0) && inside(w1, tl1) && inside(w2, tl2) {
+                    let (l0, l1, l2) = (w0 / area, w1 / area, w2 / area);
+                    let u = l0 * a.2 + l1 * b.2 + l2 * c.2;
+                    let v = l0 * a.3 + l1 * b.3 + l2 * c.3;
+                    visit(x, y, u, v, sign);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, src: [f32; 4], composite: CompositeState) {
+        let i = (y * self.width + x) * 4;
+        let dst = {
+            let p = &self.pixels[i..i + 4];
+            [
+                p[0] as f32 / 255.0,
+                p[1] as f32 / 255.0,
+                p[2] as f32 / 255.0,
+                p[3] as f32 / 255.0,
+            ]
+        };
+        for channel in 0..4 {
+            let (sf, df) = if channel == 3 {
+                (composite.alpha_source, composite.alpha_destination)
+            } else {
+                (composite.rgb_source, composite.rgb_destination)
+            };
+            let value = src[channel] * blend_factor(sf, &src, &dst, channel)
+                + dst[channel] * blend_factor(df, &src, &dst, channel);
+            self.pixels[i + channel] = to_byte(value);
+        }
+    }
+
+    /// Composite the accumulated coverage of a fill or stroke with its paint and reset it.
+    fn resolve(&mut self, shader: &Shader, composite: CompositeState, dirty: Dirty) {
+        if dirty.min_x >= dirty.max_x || dirty.min_y >= dirty.max_y {
+            return;
+        }
+        for y in dirty.min_y..dirty.max_y {
+            for x in dirty.min_x..dirty.max_x {
+                let i = y * self.width + x;
+                let mut coverage = self.coverage[i];
+                if self.winding[i] != 0 {
+                    coverage = 1.0;
+                }
+                self.coverage[i] = 0.0;
+                self.winding[i] = 0;
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let p = ((x as f32 + 0.5) / self.scale, (y as f32 + 0.5) / self.scale);
+                let alpha = coverage * shader.scissor_mask(p);
+                if alpha <= 0.0 {
+                    continue;
+                }
+                let mut color = shader.paint(p);
+                for c in color.iter_mut() {
+                    *c *= alpha;
+                }
+                self.blend(x, y, color, composite);
+            }
+        }
+    }
+
+    fn draw_fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: &Option<RenderScissor>,
+        fringe: f32,
+        paths: &[(Vec<Vertex>, Vec<Vertex>)],
+    ) {
+        let mut dirty = Dirty::empty();
+        let mut winding = mem::take(&mut self.winding);
+        let mut coverage = mem::take(&mut self.coverage);
+        let textures = mem::take(&mut self.textures);
+        let width = self.width;
+        {
+            let shader = Shader::new(paint, scissor, &textures, fringe, fringe);
+
+            // Stencil pass: count the non-zero winding of the fill fans.
+            for (fill, _) in paths {
+                for i in 2..fill.len() {
+                    self.rasterize(&fill[0], &fill[i - 1], &fill[i], |x, y, _, _, sign| {
+                        winding[y * width + x] += sign;
+                        dirty.add(x, y);
+                    });
+                }
+            }
+
+            // Antialiased fringe, only outside of the filled area.
+            for (_, stroke) in paths {
+                for i in 2..stroke.len() {
+                    self.rasterize(&stroke[i - 2], &stroke[i - 1], &stroke[i], |x, y, u, v, _| {
+                        let i = y * width + x;
+                        if winding[i] == 0 {
+                            coverage[i] = coverage[i].max(shader.stroke_mask(u, v));
+                            dirty.add(x, y);
+                        }
+                    });
+                }
+            }
+
+            self.winding = winding;
+            self.coverage = coverage;
+            self.resolve(&shader, composite, dirty);
+        }
+        self.textures = textures;
+    }
+
+    fn draw_stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: &Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[Vec<Vertex>],
+    ) {
+        let mut dirty = Dirty::empty();
+        let mut coverage = mem::take(&mut self.coverage);
+        let width = self.width;
+        let textures = mem::take(&mut self.textures);
+        {
+            let shader = Shader::new(paint, scissor, &textures, fringe, stroke_width);
+            for stroke in paths {
+                for i in 2..stroke.len() {
+                    self.rasterize(&stroke[i - 2], &stroke[i - 1], &stroke[i], |x, y, u, v, _| {
+                        let i = y * width + x;
+                        coverage[i] = coverage[i].max(shader.stroke_mask(u, v));
+                        dirty.add(x, y);
+                    });
+                }
+            }
+            self.coverage = coverage;
+            self.resolve(&shader, composite, dirty);
+        }
+        self.textures = textures;
+    }
+
+    fn draw_triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: &Option<RenderScissor>,
+        fringe: f32,
+        vertices: &[Vertex],
+    ) {
+        let textures = mem::take(&mut self.textures);
+        {
+            let shader = Shader::new(paint, scissor, &textures, fringe, 1.0);
+            let scale = self.scale;
+            let mut pixels = Vec::new();
+            for triangle in vertices.chunks(3) {
+                if triangle.len() < 3 {
+                    break;
+                }
+                self.rasterize(&triangle[0], &triangle[1], &triangle[2], |x, y, u, v, _| {
+                    let p = ((x as f32 + 0.5) / scale, (y as f32 + 0.5) / scale);
+                    pixels.push((x, y, shader.textured(p, u, v)));
+                });
+            }
+            for (x, y, color) in pixels {
+                self.blend(x, y, color, composite);
+            }
+        }
+        self.textures = textures;
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (clamp(value, 0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+impl Default for SoftwareRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn create_texture(
+        &mut self,
+        texture_type: TextureType,
+        width: usize,
+        height: usize,
+        flags: ImageFlags,
+        data: Option<&[u8]>,
+    ) -> Option<usize> {
+        let size = width * height * texture_type.bytes_per_pixel();
+        let data = match data {
+            Some(data) => data[..size].to_vec(),
+            None => vec![0; size],
+        };
+        let image = self.next_texture;
+        self.next_texture += 1;
+        self.textures.insert(
+            image,
+            Texture {
+                texture_type,
+                width,
+                height,
+                flags,
+                data,
+            },
+        );
+        Some(image)
+    }
+
+    fn delete_texture(&mut self, image: usize) -> bool {
+        self.textures.remove(&image).is_some()
+    }
+
+    fn update_texture(&mut self, image: usize, (x, y): (usize, usize), (w, h): (usize, usize), data: &[u8]) -> bool {
+        let texture = match self.textures.get_mut(&image) {
+            Some(texture) => texture,
+            None => return false,
+        };
+        let bpp = texture.texture_type.bytes_per_pixel();
+        let stride = texture.width * bpp;
+        let w = w.min(texture.width.saturating_sub(x));
+        for row in y..(y + h).min(texture.height) {
+            let start = row * stride + x * bpp;
+            let end = start + w * bpp;
+            texture.data[start..end].copy_from_slice(&data[start..end]);
+        }
+        true
+    }
+
+    fn texture_size(&self, image: usize) -> Option<(usize, usize)> {
+        self.textures.get(&image).map(|texture| (texture.width, texture.height))
+    }
+
+    fn viewport(&mut self, width: f32, height: f32, device_pixel_ratio: f32) {
+        let scale = if device_pixel_ratio > 0.0 {
+            device_pixel_ratio
+        } else {
+            1.0
+        };
+        self.scale = scale;
+        self.resize(
+            (width * scale).ceil().max(0.0) as usize,
+            (height * scale).ceil().max(0.0) as usize,
+        );
+        if let Some(color) = self.clear_color {
+            self.clear(color);
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.calls.clear();
+    }
+
+    fn flush(&mut self) {
+        let calls = mem::take(&mut self.calls);
+        for call in &calls {
+            match *call {
+                Call::Fill {
+                    ref paint,
+                    composite,
+                    ref scissor,
+                    fringe,
+                    ref paths,
+                } => self.draw_fill(paint, composite, scissor, fringe, paths),
+                Call::Stroke {
+                    ref paint,
+                    composite,
+                    ref scissor,
+                    fringe,
+                    stroke_width,
+                    ref paths,
+                } => self.draw_stroke(paint, composite, scissor, fringe, stroke_width, paths),
+                Call::Triangles {
+                    ref paint,
+                    composite,
+                    ref scissor,
+                    fringe,
+                    ref vertices,
+                } => self.draw_triangles(paint, composite, scissor, fringe, vertices),
+            }
+        }
+    }
+
+    fn fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        _bounds: [f32; 4],
+        paths: &[RenderPath],
+    ) {
+        self.calls.push(Call::Fill {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            paths: paths
+                .iter()
+                .map(|path| (path.fill.to_vec(), path.stroke.to_vec()))
+                .collect(),
+        });
+    }
+
+    fn stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[RenderPath],
+    ) {
+        self.calls.push(Call::Stroke {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            stroke_width,
+            paths: paths.iter().map(|path| path.stroke.to_vec()).collect(),
+        });
+    }
+
+    fn triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        vertices: &[Vertex],
+        fringe: f32,
+    ) {
+        self.calls.push(Call::Triangles {
+            paint: *paint,
+            composite,
+            scissor,
+            fringe,
+            vertices: vertices.to_vec(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(color: Color) -> RenderPaint {
+        RenderPaint {
+            transform: Transform::new(),
+            extent: (0.0, 0.0),
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: color,
+            outer_color: color,
+            image: None,
+        }
+    }
+
+    fn source_over() -> CompositeState {
+        CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        }
+    }
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Vec<Vertex> {
+        [(x, y), (x, y + h), (x + w, y + h), (x + w, y)]
+            .iter()
+            .map(|&(x, y)| Vertex { x, y, u: 0.5, v: 1.0 })
+            .collect()
+    }
+
+    fn fill(
+        renderer: &mut SoftwareRenderer,
+        paint: &RenderPaint,
+        fills: &[Vec<Vertex>],
+        scissor: Option<RenderScissor>,
+    ) {
+        let paths: Vec<RenderPath> = fills
+            .iter()
+            .map(|fill| RenderPath {
+                fill,
+                stroke: &[],
+                closed: true,
+                convex: true,
+                winding: ::Direction::CounterClockwise,
+                bevel_count: 0,
+            })
+            .collect();
+        renderer.fill(paint, source_over(), scissor, 1.0, [0.0; 4], &paths);
+    }
+
+    fn assert_color(color: Option<Color>, (r, g, b, a): (f32, f32, f32, f32)) {
+        let color = color.unwrap();
+        let (dr, dg, db, da) = (color.red() - r, color.green() - g, color.blue() - b, color.alpha() - a);
+        assert!(
+            dr.abs() < 0.01 && dg.abs() < 0.01 && db.abs() < 0.01 && da.abs() < 0.01,
+            "{:?} != {:?}",
+            color,
+            (r, g, b, a)
+        );
+    }
+
+    #[test]
+    fn test_fill_and_scissor() {
+        let mut renderer = SoftwareRenderer::new();
+        renderer.viewport(10.0, 10.0, 2.0);
+        assert_eq!((renderer.width(), renderer.height()), (20, 20));
+
+        let scissor = RenderScissor {
+            transform: Transform::new().with_translation(2.0, 5.0),
+            extent: (2.0, 5.0),
+        };
+        let red = solid(Color::new(1.0, 0.0, 0.0, 1.0));
+        fill(&mut renderer, &red, &[rect(1.0, 1.0, 8.0, 8.0)], Some(scissor));
+        renderer.flush();
+
+        assert_eq!(renderer.pixel(5, 5), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(renderer.pixel(1, 1).unwrap().alpha(), 0.0);
+        // Outside of the scissor, which ends at x = 4 (8 pixels).
+        assert_eq!(renderer.pixel(12, 5).unwrap().alpha(), 0.0);
+    }
+
+    #[test]
+    fn test_fill_non_zero_and_cancel() {
+        let mut renderer = SoftwareRenderer::new();
+        renderer.viewport(10.0, 10.0, 1.0);
+
+        let mut hole = rect(3.0, 3.0, 4.0, 4.0);
+        hole.reverse();
+        let red = solid(Color::new(1.0, 0.0, 0.0, 1.0));
+        fill(&mut renderer, &red, &[rect(1.0, 1.0, 8.0, 8.0), hole], None);
+        renderer.flush();
+        assert_eq!(renderer.pixel(2, 2).unwrap().alpha(), 1.0);
+        assert_eq!(renderer.pixel(5, 5).unwrap().alpha(), 0.0);
+
+        fill(&mut renderer, &red, &[rect(0.0, 0.0, 10.0, 10.0)], None);
+        renderer.cancel();
+        renderer.flush();
+        assert_eq!(renderer.pixel(5, 5).unwrap().alpha(), 0.0);
+    }
+
+    #[test]
+    fn test_linear_gradient() {
+        let mut renderer = SoftwareRenderer::new();
+        renderer.viewport(11.0, 1.0, 1.0);
+
+        // Black at x = 0.5 to white at x = 10.5, as nvgLinearGradient sets it up.
+        let large = 1.0e5;
+        let mut transform = Transform::new();
+        transform.matrix = [0.0, -1.0, 1.0, 0.0, 0.5 - large, 0.0];
+        let gradient = RenderPaint {
+            transform,
+            extent: (large, large + 5.0),
+            radius: 0.0,
+            feather: 10.0,
+            inner_color: Color::new(0.0, 0.0, 0.0, 1.0),
+            outer_color: Color::new(1.0, 1.0, 1.0, 1.0),
+            image: None,
+        };
+        fill(&mut renderer, &gradient, &[rect(0.0, 0.0, 11.0, 1.0)], None);
+        renderer.flush();
+
+        assert_color(renderer.pixel(0, 0), (0.0, 0.0, 0.0, 1.0));
+        assert_color(renderer.pixel(5, 0), (0.5, 0.5, 0.5, 1.0));
+        assert_color(renderer.pixel(10, 0), (1.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_image_pattern() {
+        let mut renderer = SoftwareRenderer::new();
+        renderer.viewport(10.0, 10.0, 1.0);
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 255];
+        let image = renderer.create_texture(TextureType::Rgba, 2, 1, ImageFlags::default(), Some(&pixels));
+
+        // The texture stretched over the framebuffer, as nvgImagePattern sets it up.
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let pattern = RenderPaint {
+            transform: Transform::new(),
+            extent: (10.0, 10.0),
+            radius: 0.0,
+            feather: 0.0,
+            inner_color: white,
+            outer_color: white,
+            image,
+        };
+        fill(&mut renderer, &pattern, &[rect(0.0, 0.0, 10.0, 10.0)], None);
+        renderer.flush();
+        assert_color(renderer.pixel(2, 5), (1.0, 0.0, 0.0, 1.0));
+        assert_color(renderer.pixel(7, 5), (0.0, 0.0, 1.0, 1.0));
+        // Filtered linearly in between.
+        assert_color(renderer.pixel(5, 5), (0.4, 0.0, 0.6, 1.0));
+
+        // Triangles sample the texture at their own coordinates, here mirrored.
+        let vertex = |x: f32, y: f32| Vertex {
+            x,
+            y,
+            u: 1.0 - x / 10.0,
+            v: y / 10.0,
+        };
+        let quad = [
+            vertex(0.0, 0.0),
+            vertex(0.0, 10.0),
+            vertex(10.0, 10.0),
+            vertex(0.0, 0.0),
+            vertex(10.0, 10.0),
+            vertex(10.0, 0.0),
+        ];
+        renderer.triangles(&pattern, source_over(), None, &quad, 1.0);
+        renderer.flush();
+        assert_color(renderer.pixel(2, 5), (0.0, 0.0, 1.0, 1.0));
+        assert_color(renderer.pixel(7, 5), (1.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_stroke_fringe() {
+        let mut renderer = SoftwareRenderer::new();
+        renderer.viewport(10.0, 10.0, 1.0);
+
+        // A line at y = 5.25 of width 2, widened by half the fringe on either side, as NanoVG tessellates it.
+        let vertex = |x: f32, u: f32| Vertex {
+            x,
+            y: 5.25 + (u - 0.5) * 3.0,
+            u,
+            v: 1.0,
+        };
+        let stroke = [vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(9.0, 0.0), vertex(9.0, 1.0)];
+        let path = RenderPath {
+            fill: &[],
+            stroke: &stroke,
+            closed: false,
+            convex: false,
+            winding: ::Direction::CounterClockwise,
+            bevel_count: 0,
+        };
+        let black = solid(Color::new(0.0, 0.0, 0.0, 1.0));
+        renderer.stroke(&black, source_over(), None, 1.0, 2.0, &[path]);
+        renderer.flush();
+
+        assert_color(renderer.pixel(5, 5), (0.0, 0.0, 0.0, 1.0));
+        // Pixel centers within the fringe are partly covered, more the closer they are to the line.
+        assert_color(renderer.pixel(5, 4), (0.0, 0.0, 0.0, 0.75));
+        assert_color(renderer.pixel(5, 6), (0.0, 0.0, 0.0, 0.25));
+        assert_eq!(renderer.pixel(5, 3).unwrap().alpha(), 0.0);
+    }
+}
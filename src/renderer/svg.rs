@@ -0,0 +1,1126 @@
+//! Export of recorded frames as SVG documents.
+//!
+//! Fills and strokes recorded with the path they were drawn from become SVG paths with the same lines
+//! and curves, under the transform they were drawn with. Otherwise geometry is taken from the tessellated
+//! paths NanoVG hands to the renderer: fills become polygons following the original outline, strokes
+//! become the merged outline of their stroke triangles, and curves show as many short lines when zoomed
+//! in. Solid colors, linear and radial gradients and image patterns map to their SVG
+//! counterparts, box gradients have no SVG equivalent and are embedded as images.
+//! Scissors become clip paths. Text is drawn from the font atlas, which is embedded once and used
+//! as a mask for every glyph. Text recorded with its glyphs becomes `<text>` in the font family
+//! the font was created with.
+
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path as IoPath;
+
+use super::software::rounded_rect_distance;
+use super::{
+    CompositeState, DrawCall, ImageFlags, RecordedFrame, RecordedPath, RecordedShape, RecordingRenderer, RenderPaint,
+    RenderPath, RenderScissor, RenderShape, Renderer, TextureType, Vertex,
+};
+use path_data::polygon_area;
+use png;
+use {Color, LineCap, LineJoin, PathData, PathSegment, StrokeOptions, Transform};
+
+/// Largest edge, in pixels, of images generated for box gradients.
+const MAX_GRADIENT_IMAGE: f32 = 2048.0;
+
+/// Format a number with at most three decimals.
+pub(crate) fn num(value: f32) -> String {
+    let mut s = format!("{:.3}", value);
+    while s.ends_with('0') {
+        s.pop();
+    }
+    if s.ends_with('.') {
+        s.pop();
+    }
+    if s == "-0" {
+        s = "0".to_owned();
+    }
+    s
+}
+
+fn matrix(m: &[f32; 6]) -> String {
+    format!(
+        "matrix({} {} {} {} {} {})",
+        num(m[0]),
+        num(m[1]),
+        num(m[2]),
+        num(m[3]),
+        num(m[4]),
+        num(m[5])
+    )
+}
+
+fn rgb(color: Color) -> String {
+    let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    format!("#{:02x}{:02x}{:02x}", c(color.red()), c(color.green()), c(color.blue()))
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Escape the characters with special meaning in XML text and attributes.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn png_uri(width: usize, height: usize, rgba: &[u8]) -> String {
+    format!(
+        "data:image/png;base64,{}",
+        base64(&png::encode_rgba(width, height, rgba))
+    )
+}
+
+/// Recover the outline of a filled path.
+///
+/// With antialiasing the fill vertices are inset by half a fringe, but every pair of fringe
+/// vertices straddles the original outline at a known ratio, so use those when present.
+pub(crate) fn fill_outline(path: &RecordedPath, convex: bool) -> Vec<(f32, f32)> {
+    if path.stroke.len() < 4 {
+        return path.fill.iter().map(|v| (v.x, v.y)).collect();
+    }
+    // Inner vertices lie `lw` inside, outer vertices `rw` outside of the outline.
+    let (lw, rw) = if convex { (0.5, 0.5) } else { (1.5, 0.5) };
+    let pairs = &path.stroke[..path.stroke.len() - 2];
+    pairs
+        .chunks(2)
+        .filter(|pair| pair.len() == 2)
+        .map(|pair| {
+            let (a, b) = (&pair[0], &pair[1]);
+            ((rw * a.x + lw * b.x) / (lw + rw), (rw * a.y + lw * b.y) / (lw + rw))
+        })
+        .collect()
+}
+
+/// The outline of a stroke with the antialiasing fringe removed, as polygons to fill with the nonzero rule.
+///
+/// NanoVG strokes with a strip of vertex pairs across the stroke. The triangles of the strip are merged into
+/// one outline, so viewers don't antialias the seams between them.
+pub(crate) fn stroke_outline(path: &RecordedPath, stroke_width: f32, fringe: f32) -> Vec<Vec<(f32, f32)>> {
+    let antialiased = path.stroke.iter().any(|v| v.u != 0.5);
+    let scale = if antialiased && stroke_width + fringe > 0.0 {
+        stroke_width / (stroke_width + fringe)
+    } else {
+        1.0
+    };
+
+    // Vertices come in pairs across the stroke. Pairs with `v == 0` only extend caps by the fringe.
+    let mut strip = Vec::with_capacity(path.stroke.len());
+    for pair in path.stroke.chunks(2).filter(|pair| pair.len() == 2) {
+        if antialiased && pair[0].v == 0.0 && pair[1].v == 0.0 {
+            continue;
+        }
+        // Round caps and joins pair the rim with the center of the stroke, which has `u == 0.5`.
+        let (cx, cy) = match (pair[0].u == 0.5, pair[1].u == 0.5) {
+            (true, false) => (pair[0].x, pair[0].y),
+            (false, true) => (pair[1].x, pair[1].y),
+            _ => ((pair[0].x + pair[1].x) * 0.5, (pair[0].y + pair[1].y) * 0.5),
+        };
+        for v in pair {
+            strip.push((cx + (v.x - cx) * scale, cy + (v.y - cy) * scale));
+        }
+    }
+
+    let mut triangles = PathData::new();
+    for i in 2..strip.len() {
+        let (a, b, c) = (strip[i - 2], strip[i - 1], strip[i]);
+        if (b.0 - a.0) * (c.1 - a.1) != (b.1 - a.1) * (c.0 - a.0) {
+            triangles.move_to(a);
+            triangles.line_to(b);
+            triangles.line_to(c);
+            triangles.close();
+        }
+    }
+    // Only lines, so the tolerance doesn't matter.
+    let merged = triangles.union(&PathData::new(), 0.25);
+    let mut polygons: Vec<Vec<(f32, f32)>> = Vec::new();
+    for segment in merged.segments() {
+        match *segment {
+            PathSegment::MoveTo(point) => polygons.push(vec![point]),
+            PathSegment::LineTo(point) => polygons.last_mut().unwrap().push(point),
+            _ => {}
+        }
+    }
+    polygons
+}
+
+fn polygon_data(out: &mut String, points: &[(f32, f32)]) {
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        let _ = write!(out, "{}{} {}", command, num(x), num(y));
+    }
+    if !points.is_empty() {
+        out.push('Z');
+    }
+}
+
+/// Path data for the commands of `path`. For fills, subpaths are turned to their winding first like NanoVG does,
+/// so the nonzero rule of SVG leaves holes where NanoVG does.
+fn shape_data(out: &mut String, path: &PathData, fill: bool) {
+    // Split into subpaths starting with a move, the way PathData::flatten does.
+    let mut subpaths: Vec<((f32, f32), PathData)> = Vec::new();
+    let mut start = (0.0, 0.0);
+    let mut open = false;
+    for &segment in path.segments() {
+        let new = match segment {
+            PathSegment::MoveTo(point) => {
+                start = point;
+                open = true;
+                true
+            }
+            // Lines after a close start over from the start of the closed subpath.
+            PathSegment::LineTo(_) | PathSegment::CubicBezierTo { .. } => !mem::replace(&mut open, true),
+            PathSegment::Close => {
+                open = false;
+                false
+            }
+            PathSegment::Winding(_) => false,
+        };
+        if new {
+            let mut subpath = PathData::new();
+            subpath.move_to(start);
+            subpaths.push((start, subpath));
+        }
+        match (segment, subpaths.last_mut()) {
+            (PathSegment::MoveTo(_), _) | (_, None) => {}
+            (segment, Some(&mut (_, ref mut subpath))) => subpath.push(segment),
+        }
+    }
+
+    for (start, subpath) in subpaths {
+        let mut segments = subpath.segments().to_vec();
+        if segments.len() < 2 {
+            continue;
+        }
+        // Solid subpaths turn counterclockwise on screen, holes clockwise.
+        let reverse = fill
+            && match subpath.flatten_subpaths(0.25).first() {
+                Some(&(ref polyline, hole)) => {
+                    let area = polygon_area(&polyline.points);
+                    area != 0.0 && (area > 0.0) != hole
+                }
+                None => false,
+            };
+        if reverse {
+            segments = reversed(start, &segments);
+        }
+        for segment in segments {
+            let _ = match segment {
+                PathSegment::MoveTo((x, y)) => write!(out, "M{} {}", num(x), num(y)),
+                PathSegment::LineTo((x, y)) => write!(out, "L{} {}", num(x), num(y)),
+                PathSegment::CubicBezierTo {
+                    point,
+                    control1,
+                    control2,
+                } => write!(
+                    out,
+                    "C{} {} {} {} {} {}",
+                    num(control1.0),
+                    num(control1.1),
+                    num(control2.0),
+                    num(control2.1),
+                    num(point.0),
+                    num(point.1)
+                ),
+                PathSegment::Close => write!(out, "Z"),
+                PathSegment::Winding(_) => Ok(()),
+            };
+        }
+    }
+}
+
+/// The lines and curves of a subpath starting at `start`, in the opposite direction.
+fn reversed(start: (f32, f32), segments: &[PathSegment]) -> Vec<PathSegment> {
+    let mut reversed = Vec::with_capacity(segments.len());
+    let mut closed = false;
+    let mut previous = start;
+    for &segment in segments {
+        match segment {
+            PathSegment::LineTo(point) => {
+                reversed.push((PathSegment::LineTo(previous), point));
+                previous = point;
+            }
+            PathSegment::CubicBezierTo {
+                point,
+                control1,
+                control2,
+            } => {
+                let segment = PathSegment::CubicBezierTo {
+                    point: previous,
+                    control1: control2,
+                    control2: control1,
+                };
+                reversed.push((segment, point));
+                previous = point;
+            }
+            PathSegment::Close => closed = true,
+            PathSegment::MoveTo(_) | PathSegment::Winding(_) => {}
+        }
+    }
+    let mut segments = vec![PathSegment::MoveTo(previous)];
+    segments.extend(reversed.into_iter().rev().map(|(segment, _)| segment));
+    if closed {
+        segments.push(PathSegment::Close);
+    }
+    segments
+}
+
+fn stroke_attributes(options: &StrokeOptions) -> String {
+    let cap = match options.line_cap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::Square => "square",
+    };
+    let join = match options.line_join {
+        LineJoin::Miter => "miter",
+        LineJoin::Round => "round",
+        LineJoin::Bevel => "bevel",
+    };
+    format!(
+        r#"fill="none" stroke-width="{}" stroke-linecap="{}" stroke-linejoin="{}" stroke-miterlimit="{}""#,
+        num(options.width),
+        cap,
+        join,
+        num(options.miter_limit.max(1.0))
+    )
+}
+
+/// Map a paint space transform into the space of a shape drawn with the transform `inverse` undoes.
+fn local(matrix: [f32; 6], inverse: Option<&Transform>) -> [f32; 6] {
+    match inverse {
+        Some(inverse) => {
+            let mut transform = Transform::new();
+            transform.matrix = matrix;
+            (transform * *inverse).matrix
+        }
+        None => matrix,
+    }
+}
+
+pub(crate) fn vertex_bounds(vertices: &[Vertex], bounds: &mut [f32; 4]) {
+    for v in vertices {
+        bounds[0] = bounds[0].min(v.x);
+        bounds[1] = bounds[1].min(v.y);
+        bounds[2] = bounds[2].max(v.x);
+        bounds[3] = bounds[3].max(v.y);
+    }
+}
+
+/// The kind of gradient NanoVG encoded into a paint.
+pub(crate) enum GradientKind {
+    Solid,
+    Linear,
+    Radial,
+    Box,
+    Image(usize),
+}
+
+pub(crate) fn gradient_kind(paint: &RenderPaint) -> GradientKind {
+    if let Some(image) = paint.image {
+        GradientKind::Image(image)
+    } else if paint.inner_color == paint.outer_color {
+        GradientKind::Solid
+    } else if paint.radius == 0.0 && paint.extent.0 >= 1.0e4 {
+        // nvgLinearGradient uses a huge box with the gradient along one of its edges.
+        GradientKind::Linear
+    } else if paint.radius > 0.0 && paint.extent.0 == paint.radius && paint.extent.1 == paint.radius {
+        GradientKind::Radial
+    } else {
+        GradientKind::Box
+    }
+}
+
+/// The paint space transform of a linear gradient, moved so the gradient runs from
+/// `(0, -feather / 2)` to `(0, feather / 2)` instead of sitting at the edge of a huge box.
+pub(crate) fn linear_gradient_transform(paint: &RenderPaint) -> [f32; 6] {
+    let m = &paint.transform.matrix;
+    let ey = paint.extent.1 as f64;
+    [
+        m[0],
+        m[1],
+        m[2],
+        m[3],
+        (m[4] as f64 + m[2] as f64 * ey) as f32,
+        (m[5] as f64 + m[3] as f64 * ey) as f32,
+    ]
+}
+
+/// Evaluate a gradient paint at `p`, returning straight alpha RGBA.
+pub(crate) fn gradient_color(paint: &RenderPaint, inverse: &[f32; 6], (x, y): (f32, f32)) -> [f32; 4] {
+    let pt = (
+        x * inverse[0] + y * inverse[2] + inverse[4],
+        x * inverse[1] + y * inverse[3] + inverse[5],
+    );
+    let feather = paint.feather.max(1.0e-5);
+    let d = (rounded_rect_distance(pt, paint.extent, paint.radius) + paint.feather * 0.5) / feather;
+    let d = d.clamp(0.0, 1.0);
+    let (inner, outer) = (paint.inner_color, paint.outer_color);
+    let (ia, oa) = (inner.alpha(), outer.alpha());
+    let a = ia + (oa - ia) * d;
+    let channel = |i: f32, o: f32| {
+        if a > 0.0 {
+            (i * ia + (o * oa - i * ia) * d) / a
+        } else {
+            0.0
+        }
+    };
+    [
+        channel(inner.red(), outer.red()),
+        channel(inner.green(), outer.green()),
+        channel(inner.blue(), outer.blue()),
+        a,
+    ]
+}
+
+/// Rasterize a box gradient over `bounds` at `scale` pixels per unit.
+pub(crate) fn gradient_image(paint: &RenderPaint, bounds: [f32; 4], scale: f32) -> (usize, usize, Vec<u8>) {
+    let (bw, bh) = ((bounds[2] - bounds[0]).max(1.0), (bounds[3] - bounds[1]).max(1.0));
+    let scale = scale
+        .min(MAX_GRADIENT_IMAGE / bw)
+        .min(MAX_GRADIENT_IMAGE / bh)
+        .max(1.0e-3);
+    let (width, height) = (
+        (bw * scale).ceil().max(1.0) as usize,
+        (bh * scale).ceil().max(1.0) as usize,
+    );
+    let inverse = paint
+        .transform
+        .try_inverse()
+        .map(|t| t.matrix)
+        .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for py in 0..height {
+        for px in 0..width {
+            let p = (
+                bounds[0] + (px as f32 + 0.5) * bw / width as f32,
+                bounds[1] + (py as f32 + 0.5) * bh / height as f32,
+            );
+            for c in &gradient_color(paint, &inverse, p) {
+                rgba.push((c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8);
+            }
+        }
+    }
+    (width, height, rgba)
+}
+
+/// Solve the affine transform mapping texture space to path space for a textured triangle.
+pub(crate) fn texture_transform(t: &[Vertex], width: f32, height: f32) -> Option<[f32; 6]> {
+    let (u0, v0) = (t[0].u * width, t[0].v * height);
+    let (du1, dv1) = (t[1].u * width - u0, t[1].v * height - v0);
+    let (du2, dv2) = (t[2].u * width - u0, t[2].v * height - v0);
+    let det = du1 * dv2 - du2 * dv1;
+    if det.abs() < 1.0e-9 {
+        return None;
+    }
+    let (dx1, dy1) = (t[1].x - t[0].x, t[1].y - t[0].y);
+    let (dx2, dy2) = (t[2].x - t[0].x, t[2].y - t[0].y);
+    let a = (dx1 * dv2 - dx2 * dv1) / det;
+    let c = (dx2 * du1 - dx1 * du2) / det;
+    let b = (dy1 * dv2 - dy2 * dv1) / det;
+    let d = (dy2 * du1 - dy1 * du2) / det;
+    Some([a, b, c, d, t[0].x - a * u0 - c * v0, t[0].y - b * u0 - d * v0])
+}
+
+struct SvgWriter<'a> {
+    recorder: &'a RecordingRenderer,
+    scale: f32,
+    defs: String,
+    body: String,
+    next_id: usize,
+    images: HashMap<usize, String>,
+    masks: HashMap<usize, String>,
+    clips: HashMap<String, String>,
+}
+
+impl<'a> SvgWriter<'a> {
+    fn id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{}", prefix, self.next_id)
+    }
+
+    /// Embed a texture once and return the id of its `<image>`.
+    fn image(&mut self, image: usize) -> Option<(String, usize, usize)> {
+        let texture = self.recorder.texture(image)?;
+        if let Some(id) = self.images.get(&image) {
+            return Some((id.clone(), texture.width, texture.height));
+        }
+        let id = self.id("image");
+        let _ = writeln!(
+            self.defs,
+            r#"<image id="{}" width="{}" height="{}" xlink:href="{}"/>"#,
+            id,
+            texture.width,
+            texture.height,
+            png_uri(texture.width, texture.height, &texture.to_rgba())
+        );
+        self.images.insert(image, id.clone());
+        Some((id, texture.width, texture.height))
+    }
+
+    fn mask(&mut self, image: usize) -> Option<(String, usize, usize)> {
+        let (image_id, width, height) = self.image(image)?;
+        if let Some(id) = self.masks.get(&image) {
+            return Some((id.clone(), width, height));
+        }
+        let id = self.id("mask");
+        let _ = writeln!(
+            self.defs,
+            r##"<mask id="{}" maskUnits="userSpaceOnUse" x="0" y="0" width="{}" height="{}"><use xlink:href="#{}"/></mask>"##,
+            id, width, height, image_id
+        );
+        self.masks.insert(image, id.clone());
+        Some((id, width, height))
+    }
+
+    fn clip(&mut self, scissor: &Option<RenderScissor>) -> Option<String> {
+        let scissor = scissor.as_ref()?;
+        let (ex, ey) = scissor.extent;
+        let rect = format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" transform="{}"/>"#,
+            num(-ex),
+            num(-ey),
+            num(ex * 2.0),
+            num(ey * 2.0),
+            matrix(&scissor.transform.matrix)
+        );
+        if let Some(id) = self.clips.get(&rect) {
+            return Some(id.clone());
+        }
+        let id = self.id("clip");
+        let _ = writeln!(self.defs, r#"<clipPath id="{}">{}</clipPath>"#, id, rect);
+        self.clips.insert(rect, id.clone());
+        Some(id)
+    }
+
+    /// Define the paint and return the `fill` or `stroke` attributes referencing it, for `attribute`.
+    /// Shapes drawn with a transform of their own pass its `inverse`, to map the paint into their space.
+    fn paint(&mut self, attribute: &str, paint: &RenderPaint, bounds: [f32; 4], inverse: Option<&Transform>) -> String {
+        let stop = |offset: f32, color: Color| {
+            format!(
+                r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                num(offset),
+                rgb(color),
+                num(color.alpha())
+            )
+        };
+
+        match gradient_kind(paint) {
+            GradientKind::Solid => format!(
+                r#"{a}="{}" {a}-opacity="{}""#,
+                rgb(paint.inner_color),
+                num(paint.inner_color.alpha()),
+                a = attribute
+            ),
+            GradientKind::Linear => {
+                let id = self.id("gradient");
+                let half = paint.feather * 0.5;
+                let _ = writeln!(
+                    self.defs,
+                    r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="0" y1="{}" x2="0" y2="{}" gradientTransform="{}">{}{}</linearGradient>"#,
+                    id,
+                    num(-half),
+                    num(half),
+                    matrix(&local(linear_gradient_transform(paint), inverse)),
+                    stop(0.0, paint.inner_color),
+                    stop(1.0, paint.outer_color)
+                );
+                format!(r#"{}="url(#{})""#, attribute, id)
+            }
+            GradientKind::Radial => {
+                let id = self.id("gradient");
+                let outer = paint.radius + paint.feather * 0.5;
+                let inner = (paint.radius - paint.feather * 0.5).max(0.0);
+                let _ = writeln!(
+                    self.defs,
+                    r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="0" cy="0" r="{}" gradientTransform="{}">{}{}</radialGradient>"#,
+                    id,
+                    num(outer),
+                    matrix(&local(paint.transform.matrix, inverse)),
+                    stop(inner / outer, paint.inner_color),
+                    stop(1.0, paint.outer_color)
+                );
+                format!(r#"{}="url(#{})""#, attribute, id)
+            }
+            GradientKind::Box => {
+                let id = self.id("gradient");
+                let (width, height, rgba) = gradient_image(paint, bounds, self.scale);
+                let (bw, bh) = (bounds[2] - bounds[0], bounds[3] - bounds[1]);
+                let _ = writeln!(
+                    self.defs,
+                    r#"<pattern id="{}" patternUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}" patternTransform="{}"><image width="{}" height="{}" preserveAspectRatio="none" xlink:href="{}"/></pattern>"#,
+                    id,
+                    num(bounds[0]),
+                    num(bounds[1]),
+                    num(bw),
+                    num(bh),
+                    matrix(&local([1.0, 0.0, 0.0, 1.0, 0.0, 0.0], inverse)),
+                    num(bw),
+                    num(bh),
+                    png_uri(width, height, &rgba)
+                );
+                format!(r#"{}="url(#{})""#, attribute, id)
+            }
+            GradientKind::Image(image) => {
+                let (image_id, width, height) = match self.image(image) {
+                    Some(image) => image,
+                    None => return format!(r#"{}="none""#, attribute),
+                };
+                let flip_y = self.recorder.texture(image).map(|t| t.flags.flip_y).unwrap_or(false);
+                let (ex, ey) = paint.extent;
+                let mut transform = String::new();
+                if flip_y {
+                    let _ = write!(transform, "translate(0 {}) scale(1 -1) ", num(ey));
+                }
+                let _ = write!(
+                    transform,
+                    "scale({} {})",
+                    num(ex / width.max(1) as f32),
+                    num(ey / height.max(1) as f32)
+                );
+                let id = self.id("pattern");
+                let _ = writeln!(
+                    self.defs,
+                    r##"<pattern id="{}" patternUnits="userSpaceOnUse" x="0" y="0" width="{}" height="{}" patternTransform="{}"><use xlink:href="#{}" transform="{}"/></pattern>"##,
+                    id,
+                    num(ex),
+                    num(ey),
+                    matrix(&local(paint.transform.matrix, inverse)),
+                    image_id,
+                    transform
+                );
+                format!(
+                    r#"{a}="url(#{})" {a}-opacity="{}""#,
+                    id,
+                    num(paint.inner_color.alpha()),
+                    a = attribute
+                )
+            }
+        }
+    }
+
+    fn element(&mut self, scissor: &Option<RenderScissor>, element: String) {
+        match self.clip(scissor) {
+            Some(clip) => {
+                let _ = writeln!(self.body, r#"<g clip-path="url(#{})">{}</g>"#, clip, element);
+            }
+            None => {
+                let _ = writeln!(self.body, "{}", element);
+            }
+        }
+    }
+
+    /// A fill or stroke drawn with the commands of its path, under the transform they were given in.
+    fn shape(&mut self, paint: &RenderPaint, scissor: &Option<RenderScissor>, bounds: [f32; 4], shape: &RecordedShape) {
+        let mut d = String::new();
+        shape_data(&mut d, &shape.path, shape.stroke.is_none());
+        let inverse = shape.transform.try_inverse();
+        if d.is_empty() || inverse.is_none() {
+            return;
+        }
+        let attributes = match shape.stroke {
+            Some(ref options) => format!(
+                "{} {}",
+                stroke_attributes(options),
+                self.paint("stroke", paint, bounds, inverse.as_ref())
+            ),
+            None => self.paint("fill", paint, bounds, inverse.as_ref()),
+        };
+        let element = format!(
+            r#"<path d="{}" transform="{}" {}/>"#,
+            d,
+            matrix(&shape.transform.matrix),
+            attributes
+        );
+        self.element(scissor, element);
+    }
+
+    fn call(&mut self, call: &DrawCall) {
+        match *call {
+            DrawCall::Fill {
+                ref paint,
+                ref scissor,
+                bounds,
+                ref shape,
+                ..
+            } if shape.is_some() => self.shape(paint, scissor, bounds, shape.as_ref().unwrap()),
+            DrawCall::Stroke {
+                ref paint,
+                ref scissor,
+                ref paths,
+                ref shape,
+                ..
+            } if shape.is_some() => {
+                let mut bounds = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+                for path in paths {
+                    vertex_bounds(&path.stroke, &mut bounds);
+                }
+                self.shape(paint, scissor, bounds, shape.as_ref().unwrap());
+            }
+            DrawCall::Fill {
+                ref paint,
+                ref scissor,
+                bounds,
+                ref paths,
+                ..
+            } => {
+                let convex = paths.len() == 1 && paths[0].convex;
+                let mut d = String::new();
+                for path in paths {
+                    polygon_data(&mut d, &fill_outline(path, convex));
+                }
+                if d.is_empty() {
+                    return;
+                }
+                let fill = self.paint("fill", paint, bounds, None);
+                self.element(scissor, format!(r#"<path d="{}" {}/>"#, d, fill));
+            }
+            DrawCall::Stroke {
+                ref paint,
+                ref scissor,
+                fringe,
+                stroke_width,
+                ref paths,
+                ..
+            } => {
+                let mut d = String::new();
+                let mut bounds = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+                for path in paths {
+                    vertex_bounds(&path.stroke, &mut bounds);
+                    for polygon in stroke_outline(path, stroke_width, fringe) {
+                        polygon_data(&mut d, &polygon);
+                    }
+                }
+                if d.is_empty() {
+                    return;
+                }
+                let fill = self.paint("fill", paint, bounds, None);
+                self.element(scissor, format!(r#"<path d="{}" {}/>"#, d, fill));
+            }
+            DrawCall::Triangles {
+                ref paint,
+                ref scissor,
+                ref vertices,
+                ..
+            } => {
+                let (mask, width, height) = match paint.image.and_then(|image| self.mask(image)) {
+                    Some(mask) => mask,
+                    None => return,
+                };
+                // Glyphs are quads made of two triangles sharing one texture mapping.
+                let chunk = if vertices.len() % 6 == 0 { 6 } else { 3 };
+                let mut glyphs = String::new();
+                for quad in vertices.chunks(chunk).filter(|quad| quad.len() >= 3) {
+                    let transform = match texture_transform(quad, width as f32, height as f32) {
+                        Some(transform) => transform,
+                        None => continue,
+                    };
+                    let mut d = String::new();
+                    for triangle in quad.chunks(3).filter(|t| t.len() == 3) {
+                        let points: Vec<_> = triangle
+                            .iter()
+                            .map(|v| (v.u * width as f32, v.v * height as f32))
+                            .collect();
+                        polygon_data(&mut d, &points);
+                    }
+                    let _ = write!(
+                        glyphs,
+                        r#"<path d="{}" transform="{}" mask="url(#{})"/>"#,
+                        d,
+                        matrix(&transform),
+                        mask
+                    );
+                }
+                let color = paint.inner_color;
+                let group = format!(
+                    r#"<g fill="{}" fill-opacity="{}">{}</g>"#,
+                    rgb(color),
+                    num(color.alpha()),
+                    glyphs
+                );
+                self.element(scissor, group);
+            }
+            DrawCall::Text {
+                ref paint,
+                ref scissor,
+                ref text,
+                ..
+            } => {
+                let family = self.recorder.font(text.font).map_or("sans-serif", |font| &font.name);
+                let mut glyphs = String::new();
+                for &(ref glyph, (x, y)) in &text.glyphs {
+                    let _ = write!(
+                        glyphs,
+                        r#"<text x="{}" y="{}">{}</text>"#,
+                        num(x),
+                        num(y),
+                        escape(glyph)
+                    );
+                }
+                let color = paint.inner_color;
+                let group = format!(
+                    r#"<g transform="{}" font-family="{}" font-size="{}" fill="{}" fill-opacity="{}" xml:space="preserve">{}</g>"#,
+                    matrix(&text.transform.matrix),
+                    escape(family),
+                    num(text.size),
+                    rgb(color),
+                    num(color.alpha()),
+                    glyphs
+                );
+                self.element(scissor, group);
+            }
+        }
+    }
+}
+
+/// Convert a recorded frame into an SVG document.
+/// `recorder` provides the textures referenced by the frame.
+pub fn frame_to_svg(recorder: &RecordingRenderer, frame: &RecordedFrame) -> String {
+    let mut writer = SvgWriter {
+        recorder,
+        scale: frame.device_pixel_ratio.max(1.0),
+        defs: String::new(),
+        body: String::new(),
+        next_id: 0,
+        images: HashMap::new(),
+        masks: HashMap::new(),
+        clips: HashMap::new(),
+    };
+    for call in &frame.calls {
+        writer.call(call);
+    }
+
+    let mut document = String::new();
+    let _ = writeln!(document, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        document,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = num(frame.width),
+        h = num(frame.height)
+    );
+    if !writer.defs.is_empty() {
+        let _ = write!(document, "<defs>\n{}</defs>\n", writer.defs);
+    }
+    document.push_str(&writer.body);
+    document.push_str("</svg>\n");
+    document
+}
+
+/// A renderer producing an SVG document for every frame.
+///
+/// Fills and strokes become SVG paths with the lines, curves and transform of the path they were drawn from,
+/// see [Renderer::begin_shape]. Build the context without `antialias` or with it, both give the same geometry.
+/// Composite operations other than the default blending can't be expressed and are ignored.
+#[derive(Debug)]
+pub struct SvgRenderer {
+    recorder: RecordingRenderer,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SvgRenderer {
+    /// Create a new SVG renderer.
+    pub fn new() -> Self {
+        SvgRenderer {
+            recorder: RecordingRenderer::new().record_shapes(),
+        }
+    }
+
+    /// The recorder holding the frames drawn so far.
+    pub fn recorder(&self) -> &RecordingRenderer {
+        &self.recorder
+    }
+
+    /// The SVG document of the last frame drawn.
+    pub fn document(&self) -> Option<String> {
+        self.recorder
+            .last_frame()
+            .map(|frame| frame_to_svg(&self.recorder, frame))
+    }
+
+    /// Write the SVG document of the last frame drawn to `path`.
+    pub fn save<P: AsRef<IoPath>>(&self, path: P) -> io::Result<()> {
+        match self.document() {
+            Some(document) => File::create(path)?.write_all(document.as_bytes()),
+            None => Err(io::Error::other("no frame has been drawn")),
+        }
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn create_texture(
+        &mut self,
+        texture_type: TextureType,
+        width: usize,
+        height: usize,
+        flags: ImageFlags,
+        data: Option<&[u8]>,
+    ) -> Option<usize> {
+        self.recorder.create_texture(texture_type, width, height, flags, data)
+    }
+
+    fn delete_texture(&mut self, image: usize) -> bool {
+        self.recorder.delete_texture(image)
+    }
+
+    fn update_texture(&mut self, image: usize, origin: (usize, usize), size: (usize, usize), data: &[u8]) -> bool {
+        self.recorder.update_texture(image, origin, size, data)
+    }
+
+    fn texture_size(&self, image: usize) -> Option<(usize, usize)> {
+        self.recorder.texture_size(image)
+    }
+
+    fn viewport(&mut self, width: f32, height: f32, device_pixel_ratio: f32) {
+        // Only the last frame is kept, older ones would just pile up.
+        self.recorder.take_frames();
+        self.recorder.viewport(width, height, device_pixel_ratio);
+    }
+
+    fn cancel(&mut self) {
+        self.recorder.cancel();
+    }
+
+    fn flush(&mut self) {
+        self.recorder.flush();
+    }
+
+    fn fill(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        bounds: [f32; 4],
+        paths: &[RenderPath],
+    ) {
+        self.recorder.fill(paint, composite, scissor, fringe, bounds, paths);
+    }
+
+    fn stroke(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        fringe: f32,
+        stroke_width: f32,
+        paths: &[RenderPath],
+    ) {
+        self.recorder
+            .stroke(paint, composite, scissor, fringe, stroke_width, paths);
+    }
+
+    fn triangles(
+        &mut self,
+        paint: &RenderPaint,
+        composite: CompositeState,
+        scissor: Option<RenderScissor>,
+        vertices: &[Vertex],
+        fringe: f32,
+    ) {
+        self.recorder.triangles(paint, composite, scissor, vertices, fringe);
+    }
+
+    fn begin_shape(&mut self, shape: &RenderShape) {
+        self.recorder.begin_shape(shape);
+    }
+
+    fn end_shape(&mut self) {
+        self.recorder.end_shape();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {BlendFactor, Direction, LineCap, Solidity, Winding};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b"nanovg"), "bmFub3Zn");
+        assert_eq!(base64(b"svg"), "c3Zn");
+        assert_eq!(base64(b"sv"), "c3Y=");
+    }
+
+    #[test]
+    fn test_fill_outline_removes_fringe() {
+        // A convex square from (0, 0) to (10, 10) with a fringe of 1: fringe pairs straddle
+        // the outline by half a fringe in each direction.
+        let corners = [(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)];
+        let mut stroke = Vec::new();
+        for &(x, y) in corners.iter().chain(corners.iter().take(1)) {
+            let (dx, dy) = (if x > 5.0 { -0.5 } else { 0.5 }, if y > 5.0 { -0.5 } else { 0.5 });
+            stroke.push(Vertex {
+                x: x + dx,
+                y: y + dy,
+                u: 0.5,
+                v: 1.0,
+            });
+            stroke.push(Vertex {
+                x: x - dx,
+                y: y - dy,
+                u: 1.0,
+                v: 1.0,
+            });
+        }
+        let path = RecordedPath {
+            fill: Vec::new(),
+            stroke,
+            closed: true,
+            convex: true,
+            winding: Direction::CounterClockwise,
+            bevel_count: 0,
+        };
+        assert_eq!(fill_outline(&path, true), corners.to_vec());
+    }
+
+    #[test]
+    fn test_stroke_outline_merges_triangles() {
+        // A line from (0, 0) to (10, 0) with a width of 2 and a fringe of 1, with a round cap at the start and
+        // a butt cap at the end, like NanoVG strokes it.
+        let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex { x, y, u, v };
+        let mut stroke = Vec::new();
+        for i in 0..5 {
+            let angle = ::std::f32::consts::PI * (0.5 + i as f32 * 0.25);
+            stroke.push(vertex(angle.cos() * 1.5, angle.sin() * 1.5, 0.0, 1.0));
+            stroke.push(vertex(0.0, 0.0, 0.5, 1.0));
+        }
+        stroke.extend_from_slice(&[
+            vertex(0.0, 1.5, 0.0, 1.0),
+            vertex(0.0, -1.5, 1.0, 1.0),
+            vertex(10.0, 1.5, 0.0, 1.0),
+            vertex(10.0, -1.5, 1.0, 1.0),
+            vertex(10.5, 1.5, 0.0, 0.0),
+            vertex(10.5, -1.5, 1.0, 0.0),
+        ]);
+        let path = RecordedPath {
+            fill: Vec::new(),
+            stroke,
+            closed: false,
+            convex: false,
+            winding: Direction::CounterClockwise,
+            bevel_count: 0,
+        };
+        let outline = stroke_outline(&path, 2.0, 1.0);
+        assert_eq!(outline.len(), 1);
+        for &(x, y) in &outline[0] {
+            let distance = if x < 0.0 { x.hypot(y) } else { y.abs() };
+            assert!((distance - 1.0).abs() < 1e-4 || x.abs() < 1e-4 && y.abs() < 1e-4);
+            assert!((-1.0 - 1e-4..=10.0 + 1e-4).contains(&x));
+        }
+        assert!(outline[0].contains(&(10.0, 1.0)) && outline[0].contains(&(10.0, -1.0)));
+        assert!(outline[0].iter().any(|&(x, _)| x < -0.99));
+    }
+
+    #[test]
+    fn test_shapes_keep_commands() {
+        let paint = |inner_color: Color, outer_color: Color, transform: Transform, radius: f32| RenderPaint {
+            transform,
+            extent: (radius, radius),
+            radius,
+            feather: 2.0,
+            inner_color,
+            outer_color,
+            image: None,
+        };
+        let composite = CompositeState {
+            rgb_source: BlendFactor::One,
+            rgb_destination: BlendFactor::OneMinusSourceAlpha,
+            alpha_source: BlendFactor::One,
+            alpha_destination: BlendFactor::OneMinusSourceAlpha,
+        };
+        let (red, blue) = (Color::new(1.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0));
+        let transform = Transform::new().with_scale(2.0, 2.0) * Transform::new().with_translation(10.0, 20.0);
+
+        // A square with a square hole added in the same direction.
+        let mut square = PathData::new();
+        square.rect((0.0, 0.0), (10.0, 10.0));
+        square.rect((2.0, 2.0), (4.0, 4.0));
+        square.winding(Winding::Solidity(Solidity::Hole));
+        let mut curve = PathData::new();
+        curve.move_to((0.0, 0.0));
+        curve.cubic_bezier_to((10.0, 0.0), (0.0, 5.0), (10.0, 5.0));
+        let frame = RecordedFrame {
+            width: 100.0,
+            height: 100.0,
+            device_pixel_ratio: 1.0,
+            calls: vec![
+                DrawCall::Fill {
+                    // A radial gradient centered on the square.
+                    paint: paint(red, blue, Transform::new().with_translation(20.0, 30.0), 5.0),
+                    composite,
+                    scissor: None,
+                    fringe: 1.0,
+                    bounds: [10.0, 20.0, 30.0, 40.0],
+                    paths: Vec::new(),
+                    shape: Some(RecordedShape {
+                        path: square,
+                        transform,
+                        stroke: None,
+                    }),
+                },
+                DrawCall::Stroke {
+                    paint: paint(red, red, Transform::new(), 0.0),
+                    composite,
+                    scissor: None,
+                    fringe: 1.0,
+                    stroke_width: 6.0,
+                    paths: Vec::new(),
+                    shape: Some(RecordedShape {
+                        path: curve,
+                        transform,
+                        stroke: Some(StrokeOptions {
+                            width: 3.0,
+                            line_cap: LineCap::Round,
+                            ..Default::default()
+                        }),
+                    }),
+                },
+            ],
+        };
+        let document = frame_to_svg(&RecordingRenderer::new(), &frame);
+
+        assert!(document.contains(
+            r#"<path d="M0 0L0 10L10 10L10 0ZM6 2L6 6L2 6L2 2Z" transform="matrix(2 0 0 2 10 20)" fill="url(#gradient1)"/>"#
+        ));
+        // The gradient is mapped into the space of the path.
+        assert!(document.contains(r#"gradientTransform="matrix(0.5 0 0 0.5 5 5)""#));
+        assert!(document.contains(
+            r##"<path d="M0 0C0 5 10 5 10 0" transform="matrix(2 0 0 2 10 20)" fill="none" stroke-width="3" stroke-linecap="round" stroke-linejoin="miter" stroke-miterlimit="10" stroke="#ff0000" stroke-opacity="1"/>"##
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+//! Just enough of a TrueType parser to embed fonts in documents.
+//!
+//! Reads the metrics, the horizontal advances and the character map of a font file. The glyph
+//! outlines themselves are left to the viewer of the document, so only fonts with TrueType outlines
+//! (a `glyf` table) are accepted.
+
+use std::collections::HashMap;
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|value| value as i16)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some((u16_at(data, offset)? as u32) << 16 | u16_at(data, offset + 2)? as u32)
+}
+
+/// A parsed TrueType font.
+#[derive(Clone, Debug)]
+pub(crate) struct TrueType {
+    data: Vec<u8>,
+    /// The PostScript name of the font, if it has a usable one.
+    pub(crate) postscript_name: Option<String>,
+    pub(crate) units_per_em: u16,
+    /// The bounding box of all glyphs, `[min_x, min_y, max_x, max_y]` in font units.
+    pub(crate) bounds: [i16; 4],
+    pub(crate) ascender: i16,
+    pub(crate) descender: i16,
+    pub(crate) cap_height: i16,
+    pub(crate) italic_angle: f32,
+    advances: Vec<u16>,
+    glyphs: HashMap<char, u16>,
+}
+
+impl TrueType {
+    /// Parse a font file. Returns `None` for font collections, fonts with other than TrueType outlines and
+    /// malformed files.
+    pub(crate) fn parse(data: &[u8]) -> Option<TrueType> {
+        match u32_at(data, 0)? {
+            0x0001_0000 | 0x7472_7565 => {}
+            _ => return None,
+        }
+        let mut tables = HashMap::new();
+        for i in 0..u16_at(data, 4)? as usize {
+            let record = 12 + i * 16;
+            let tag = data.get(record..record + 4)?;
+            let offset = u32_at(data, record + 8)? as usize;
+            let length = u32_at(data, record + 12)? as usize;
+            let table = data.get(offset..offset.checked_add(length)?)?;
+            tables.insert([tag[0], tag[1], tag[2], tag[3]], table);
+        }
+        tables.get(b"glyf")?;
+
+        let head = tables.get(b"head")?;
+        let hhea = tables.get(b"hhea")?;
+        let glyph_count = u16_at(tables.get(b"maxp")?, 4)? as usize;
+        let units_per_em = u16_at(head, 18)?;
+        if units_per_em == 0 {
+            return None;
+        }
+        let ascender = i16_at(hhea, 4)?;
+
+        // The last advance repeats for the remaining glyphs.
+        let hmtx = tables.get(b"hmtx")?;
+        let metric_count = (u16_at(hhea, 34)? as usize).clamp(1, glyph_count.max(1));
+        let mut advances = Vec::with_capacity(glyph_count);
+        for glyph in 0..glyph_count {
+            advances.push(u16_at(hmtx, glyph.min(metric_count - 1) * 4)?);
+        }
+
+        let glyphs = Self::parse_cmap(tables.get(b"cmap")?, glyph_count)?;
+        let cap_height = tables
+            .get(b"OS/2")
+            .and_then(|os2| if u16_at(os2, 0)? >= 2 { i16_at(os2, 88) } else { None })
+            .unwrap_or(ascender);
+        let italic_angle = tables
+            .get(b"post")
+            .and_then(|post| u32_at(post, 4))
+            .map(|angle| angle as i32 as f32 / 65536.0)
+            .unwrap_or(0.0);
+
+        Some(TrueType {
+            data: data.to_vec(),
+            postscript_name: tables.get(b"name").and_then(|name| Self::parse_postscript_name(name)),
+            units_per_em,
+            bounds: [
+                i16_at(head, 36)?,
+                i16_at(head, 38)?,
+                i16_at(head, 40)?,
+                i16_at(head, 42)?,
+            ],
+            ascender,
+            descender: i16_at(hhea, 6)?,
+            cap_height,
+            italic_angle,
+            advances,
+            glyphs,
+        })
+    }
+
+    /// Map characters to glyphs with the best Unicode subtable, preferring the full range of format 12.
+    fn parse_cmap(cmap: &[u8], glyph_count: usize) -> Option<HashMap<char, u16>> {
+        let mut best: Option<(u16, usize)> = None;
+        for i in 0..u16_at(cmap, 2)? as usize {
+            let record = 4 + i * 8;
+            let (platform, encoding) = (u16_at(cmap, record)?, u16_at(cmap, record + 2)?);
+            if platform != 0 && !(platform == 3 && (encoding == 1 || encoding == 10)) {
+                continue;
+            }
+            let offset = u32_at(cmap, record + 4)? as usize;
+            let format = match u16_at(cmap, offset) {
+                Some(format) if format == 4 || format == 12 => format,
+                _ => continue,
+            };
+            let better = match best {
+                Some((best, _)) => format > best,
+                None => true,
+            };
+            if better {
+                best = Some((format, offset));
+            }
+        }
+
+        let mut glyphs = HashMap::new();
+        let mut insert = |code: u32, glyph: u32| {
+            if let Some(c) = ::std::char::from_u32(code) {
+                if glyph != 0 && (glyph as usize) < glyph_count {
+                    glyphs.insert(c, glyph as u16);
+                }
+            }
+        };
+        match best? {
+            (4, table) => {
+                let segments = u16_at(cmap, table + 6)? as usize / 2;
+                let ends = table + 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+                for segment in 0..segments {
+                    let (start, end) = (u16_at(cmap, starts + segment * 2)?, u16_at(cmap, ends + segment * 2)?);
+                    let delta = u16_at(cmap, deltas + segment * 2)?;
+                    let range_offset = u16_at(cmap, range_offsets + segment * 2)? as usize;
+                    for code in start..=end.min(0xfffe) {
+                        let glyph = if range_offset == 0 {
+                            code.wrapping_add(delta)
+                        } else {
+                            let address = range_offsets + segment * 2 + range_offset + (code - start) as usize * 2;
+                            match u16_at(cmap, address)? {
+                                0 => 0,
+                                glyph => glyph.wrapping_add(delta),
+                            }
+                        };
+                        insert(code as u32, glyph as u32);
+                    }
+                }
+            }
+            (_, table) => {
+                for group in 0..u32_at(cmap, table + 12)? as usize {
+                    let record = table + 16 + group * 12;
+                    let (start, end) = (u32_at(cmap, record)?, u32_at(cmap, record + 4)?.min(0x10ffff));
+                    let first = u32_at(cmap, record + 8)?;
+                    for code in start..=end {
+                        insert(code, first.wrapping_add(code - start));
+                    }
+                }
+            }
+        }
+        Some(glyphs)
+    }
+
+    /// The PostScript name (name id 6), restricted to the characters allowed in PDF names.
+    fn parse_postscript_name(name: &[u8]) -> Option<String> {
+        let strings = u16_at(name, 4)? as usize;
+        for i in 0..u16_at(name, 2)? as usize {
+            let record = 6 + i * 12;
+            if u16_at(name, record + 6)? != 6 {
+                continue;
+            }
+            let platform = u16_at(name, record)?;
+            let length = u16_at(name, record + 8)? as usize;
+            let offset = strings + u16_at(name, record + 10)? as usize;
+            let bytes = name.get(offset..offset + length)?;
+            let decoded: String = match platform {
+                0 | 3 => {
+                    let units: Vec<u16> = bytes.chunks(2).filter_map(|unit| u16_at(unit, 0)).collect();
+                    String::from_utf16_lossy(&units)
+                }
+                1 => bytes.iter().map(|&b| b as char).collect(),
+                _ => continue,
+            };
+            let decoded: String = decoded
+                .chars()
+                .filter(|&c| c > ' ' && c <= '~' && !"()<>[]{}/%#".contains(c))
+                .collect();
+            if !decoded.is_empty() {
+                return Some(decoded);
+            }
+        }
+        None
+    }
+
+    /// The font file the font was parsed from.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The glyph for `c`, or `None` if the font doesn't have one.
+    pub(crate) fn glyph(&self, c: char) -> Option<u16> {
+        self.glyphs.get(&c).cloned()
+    }
+
+    /// The horizontal advance of `glyph` in font units.
+    pub(crate) fn advance(&self, glyph: u16) -> u16 {
+        self.advances.get(glyph as usize).cloned().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_font() {
+        let font = TrueType::parse(include_bytes!("../../resources/Roboto-Regular.ttf")).unwrap();
+        assert_eq!(font.units_per_em, 2048);
+        assert_eq!(font.postscript_name.as_deref(), Some("Roboto-Regular"));
+        assert!(font.ascender > 0 && font.descender < 0);
+        let (a, space) = (font.glyph('A').unwrap(), font.glyph(' ').unwrap());
+        assert_ne!(a, space);
+        assert!(font.advance(a) > font.advance(space));
+        assert_eq!(font.glyph('\u{10ffff}'), None);
+        assert!(TrueType::parse(&font.data()[..100]).is_none());
+    }
+}
@@ -0,0 +1,977 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path as IoPath;
+
+use super::path::{arc_to, parse_into, parse_numbers};
+use super::xml::{self, Element};
+use path_data::{polygon_area, winding_number};
+use renderer::SoftwareRenderer;
+use {
+    Color, ContextBuilder, DisplayPaint, FillOptions, Frame, Gradient, LineCap, LineJoin, PathData, PathOptions,
+    PathSegment, Solidity, StrokeOptions, Transform, Winding,
+};
+
+/// Tolerance for flattening curves when finding windings.
+const TOLERANCE: f32 = 0.25;
+
+/// Errors of loading a [Document].
+#[derive(Debug)]
+pub enum DocumentError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The document isn't well-formed XML in UTF-8, with the byte offset of the error.
+    InvalidXml(usize),
+    /// The root element isn't `<svg>`.
+    NotSvg,
+}
+
+impl From<io::Error> for DocumentError {
+    fn from(error: io::Error) -> Self {
+        DocumentError::Io(error)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Shape {
+    path: PathData,
+    transform: Transform,
+    fill: Option<DisplayPaint>,
+    stroke: Option<(DisplayPaint, StrokeOptions)>,
+    opacity: f32,
+}
+
+/// An SVG document, loaded into shapes which are drawn as NanoVG paths.
+///
+/// The supported subset covers most icons and illustrations: `rect`, `circle`, `ellipse`, `line`, `polyline`,
+/// `polygon` and `path` elements, grouped with `g` and placed with `transform`, filled and stroked with colors
+/// or gradients, with presentation attributes or `style` properties and `opacity`.
+///
+/// NanoVG gradients have two colors, so only the first and last stop of SVG gradients are used,
+/// and radial gradients are kept circular. Text, images, `use`, clipping and masking are not supported.
+#[derive(Clone, Debug)]
+pub struct Document {
+    size: (f32, f32),
+    shapes: Vec<Shape>,
+}
+
+impl Document {
+    /// Load a document from SVG text.
+    pub fn parse(text: &str) -> Result<Document, DocumentError> {
+        let root = xml::parse(text).map_err(DocumentError::InvalidXml)?;
+        if root.name != "svg" {
+            return Err(DocumentError::NotSvg);
+        }
+        Ok(Loader::load(&root))
+    }
+
+    /// Load a document from the bytes of an SVG file.
+    pub fn from_bytes(data: &[u8]) -> Result<Document, DocumentError> {
+        match ::std::str::from_utf8(data) {
+            Ok(text) => Document::parse(text),
+            Err(error) => Err(DocumentError::InvalidXml(error.valid_up_to())),
+        }
+    }
+
+    /// Load a document from an SVG file.
+    pub fn from_file<P: AsRef<IoPath>>(path: P) -> Result<Document, DocumentError> {
+        Document::from_bytes(&fs::read(path)?)
+    }
+
+    /// The size of the document in pixels, from the `width` and `height` of the root element or its `viewBox`.
+    pub fn size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    /// Draw the document into `frame`, with its top left corner at the origin and one unit per pixel.
+    ///
+    /// `options` apply to the whole document: its transform places and scales the document,
+    /// and its alpha is multiplied with the opacity of the shapes. Vectors stay sharp at any device pixel ratio.
+    pub fn draw(&self, frame: &Frame, options: PathOptions) {
+        for shape in &self.shapes {
+            let transform = match options.transform {
+                Some(transform) if transform.absolute => (shape.transform * transform).absolute(),
+                Some(transform) => shape.transform * transform,
+                None => shape.transform,
+            };
+            frame.path(
+                |path| {
+                    path.append(&shape.path);
+                    if let Some(fill) = shape.fill {
+                        path.fill(fill, FillOptions::default());
+                    }
+                    if let Some((stroke, ref stroke_options)) = shape.stroke {
+                        path.stroke(stroke, stroke_options.clone());
+                    }
+                },
+                PathOptions {
+                    alpha: options.alpha * shape.opacity,
+                    transform: Some(transform),
+                    ..options
+                },
+            );
+        }
+    }
+
+    /// Rasterize the document stretched to `width` x `height` pixels, as RGBA pixels in rows from the top,
+    /// with premultiplied or straight alpha. Uses a separate context, so it works while a frame is being drawn.
+    pub(crate) fn rasterize(&self, (width, height): (usize, usize), premultiplied: bool) -> Option<Vec<u32>> {
+        if width == 0 || height == 0 || self.size.0 <= 0.0 || self.size.1 <= 0.0 {
+            return None;
+        }
+        let context = ContextBuilder::new()
+            .antialias()
+            .build_with_renderer(SoftwareRenderer::new())
+            .ok()?;
+        context.frame((width as f32, height as f32), 1.0, |frame| {
+            let scale = Transform::new().scale(width as f32 / self.size.0, height as f32 / self.size.1);
+            self.draw(
+                &frame,
+                PathOptions {
+                    transform: Some(scale),
+                    ..Default::default()
+                },
+            );
+        });
+        let renderer = context.renderer::<SoftwareRenderer>()?;
+        let bytes = if premultiplied {
+            renderer.pixels().to_vec()
+        } else {
+            renderer.to_rgba()
+        };
+        Some(
+            bytes
+                .chunks(4)
+                .map(|p| u32::from_ne_bytes([p[0], p[1], p[2], p[3]]))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum SvgPaint {
+    None,
+    Color(Color),
+    CurrentColor,
+    Url(String, Box<SvgPaint>),
+}
+
+/// The inherited properties of an element.
+#[derive(Clone, Debug)]
+struct Style {
+    fill: SvgPaint,
+    fill_opacity: f32,
+    even_odd: bool,
+    stroke: SvgPaint,
+    stroke_opacity: f32,
+    stroke_width: f32,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f32,
+    dash_array: Vec<f32>,
+    dash_offset: f32,
+    color: Color,
+    visible: bool,
+    /// The product of the `opacity` of the element and its ancestors.
+    opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: SvgPaint::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+            fill_opacity: 1.0,
+            even_odd: false,
+            stroke: SvgPaint::None,
+            stroke_opacity: 1.0,
+            stroke_width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dash_array: Vec::new(),
+            dash_offset: 0.0,
+            color: Color::new(0.0, 0.0, 0.0, 1.0),
+            visible: true,
+            opacity: 1.0,
+        }
+    }
+}
+
+struct Loader<'a> {
+    gradients: HashMap<&'a str, &'a Element>,
+    /// The size of the viewport, which percentages refer to.
+    viewport: (f32, f32),
+    shapes: Vec<Shape>,
+}
+
+impl<'a> Loader<'a> {
+    fn load(root: &'a Element) -> Document {
+        let view_box = root.attribute("viewBox").map(parse_numbers).and_then(|numbers| {
+            if numbers.len() == 4 && numbers[2] > 0.0 && numbers[3] > 0.0 {
+                Some((numbers[0], numbers[1], numbers[2], numbers[3]))
+            } else {
+                None
+            }
+        });
+        let default_size = view_box.map_or((100.0, 100.0), |(_, _, w, h)| (w, h));
+        let width = root
+            .attribute("width")
+            .filter(|width| !width.trim().ends_with('%'))
+            .and_then(|width| length(width, 0.0))
+            .unwrap_or(default_size.0);
+        let height = root
+            .attribute("height")
+            .filter(|height| !height.trim().ends_with('%'))
+            .and_then(|height| length(height, 0.0))
+            .unwrap_or(default_size.1);
+
+        // Fit the view box into the viewport, centered and keeping its aspect ratio.
+        let transform = match view_box {
+            Some((x, y, w, h)) => {
+                let scale = (width / w).min(height / h);
+                let tx = (width - w * scale) / 2.0 - x * scale;
+                let ty = (height - h * scale) / 2.0 - y * scale;
+                matrix([scale, 0.0, 0.0, scale, tx, ty])
+            }
+            None => Transform::new(),
+        };
+
+        let mut loader = Loader {
+            gradients: HashMap::new(),
+            viewport: default_size,
+            shapes: Vec::new(),
+        };
+        loader.find_gradients(root);
+        let style = loader.style(root, &Style::default());
+        let transform = element_transform(root) * transform;
+        loader.children(root, &style, transform);
+        Document {
+            size: (width, height),
+            shapes: loader.shapes,
+        }
+    }
+
+    fn find_gradients(&mut self, element: &'a Element) {
+        if element.name == "linearGradient" || element.name == "radialGradient" {
+            if let Some(id) = element.attribute("id") {
+                self.gradients.insert(id, element);
+            }
+        }
+        for child in &element.children {
+            self.find_gradients(child);
+        }
+    }
+
+    fn children(&mut self, element: &'a Element, style: &Style, transform: Transform) {
+        for child in &element.children {
+            if property(child, "display") == Some("none") {
+                continue;
+            }
+            let style = self.style(child, style);
+            let transform = element_transform(child) * transform;
+            match child.name.as_str() {
+                "g" | "a" | "svg" | "switch" => self.children(child, &style, transform),
+                _ => {
+                    if let Some(path) = self.shape_path(child) {
+                        self.add_shape(path, &style, transform);
+                    }
+                }
+            }
+        }
+    }
+
+    fn style(&self, element: &Element, parent: &Style) -> Style {
+        let mut style = parent.clone();
+        // Stroke lengths in percent are relative to the normalized diagonal of the viewport.
+        let diagonal = ((self.viewport.0.powi(2) + self.viewport.1.powi(2)) / 2.0).sqrt();
+        for (name, value) in properties(element) {
+            let value = value.trim();
+            if value == "inherit" {
+                continue;
+            }
+            match name {
+                "fill" => style.fill = paint(value).unwrap_or(style.fill),
+                "fill-opacity" => style.fill_opacity = opacity(value).unwrap_or(style.fill_opacity),
+                "fill-rule" => style.even_odd = value == "evenodd",
+                "stroke" => style.stroke = paint(value).unwrap_or(style.stroke),
+                "stroke-opacity" => style.stroke_opacity = opacity(value).unwrap_or(style.stroke_opacity),
+                "stroke-width" => style.stroke_width = length(value, diagonal).unwrap_or(style.stroke_width),
+                "stroke-linecap" => {
+                    style.line_cap = match value {
+                        "round" => LineCap::Round,
+                        "square" => LineCap::Square,
+                        _ => LineCap::Butt,
+                    }
+                }
+                "stroke-linejoin" => {
+                    style.line_join = match value {
+                        "round" => LineJoin::Round,
+                        "bevel" => LineJoin::Bevel,
+                        _ => LineJoin::Miter,
+                    }
+                }
+                "stroke-miterlimit" => style.miter_limit = number(value).unwrap_or(style.miter_limit),
+                "stroke-dasharray" => {
+                    // Invalid lists draw solid strokes, like "none".
+                    style.dash_array = value
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|item| !item.is_empty())
+                        .map(|item| length(item, diagonal))
+                        .collect::<Option<Vec<f32>>>()
+                        .unwrap_or_default();
+                }
+                "stroke-dashoffset" => style.dash_offset = length(value, diagonal).unwrap_or(style.dash_offset),
+                "color" => style.color = color(value).unwrap_or(style.color),
+                "visibility" => style.visible = value == "visible",
+                "opacity" => style.opacity *= opacity(value).unwrap_or(1.0),
+                _ => {}
+            }
+        }
+        style
+    }
+
+    fn shape_path(&self, element: &Element) -> Option<PathData> {
+        let (width, height) = self.viewport;
+        let diagonal = ((width * width + height * height) / 2.0).sqrt();
+        let x = |name| {
+            element
+                .attribute(name)
+                .and_then(|value| length(value, width))
+                .unwrap_or(0.0)
+        };
+        let y = |name| {
+            element
+                .attribute(name)
+                .and_then(|value| length(value, height))
+                .unwrap_or(0.0)
+        };
+        let r = |name| {
+            element
+                .attribute(name)
+                .and_then(|value| length(value, diagonal))
+                .unwrap_or(0.0)
+        };
+
+        let mut path = PathData::new();
+        match element.name.as_str() {
+            "rect" => {
+                let (w, h) = (x("width"), y("height"));
+                if w <= 0.0 || h <= 0.0 {
+                    return None;
+                }
+                let (rx, ry) = match (element.attribute("rx"), element.attribute("ry")) {
+                    (None, None) => (0.0, 0.0),
+                    (Some(_), None) => (x("rx"), x("rx")),
+                    (None, Some(_)) => (y("ry"), y("ry")),
+                    (Some(_), Some(_)) => (x("rx"), y("ry")),
+                };
+                rounded_rect(&mut path, (x("x"), y("y")), (w, h), (rx.min(w / 2.0), ry.min(h / 2.0)));
+            }
+            "circle" => {
+                let radius = r("r");
+                if radius <= 0.0 {
+                    return None;
+                }
+                path.circle((x("cx"), y("cy")), radius);
+            }
+            "ellipse" => {
+                let (rx, ry) = (x("rx"), y("ry"));
+                if rx <= 0.0 || ry <= 0.0 {
+                    return None;
+                }
+                path.ellipse((x("cx"), y("cy")), rx, ry);
+            }
+            "line" => {
+                path.move_to((x("x1"), y("y1")));
+                path.line_to((x("x2"), y("y2")));
+            }
+            "polyline" | "polygon" => {
+                let numbers = parse_numbers(element.attribute("points").unwrap_or(""));
+                if numbers.len() < 4 {
+                    return None;
+                }
+                path.move_to((numbers[0], numbers[1]));
+                for point in numbers[2..].chunks(2).filter(|point| point.len() == 2) {
+                    path.line_to((point[0], point[1]));
+                }
+                if element.name == "polygon" {
+                    path.close();
+                }
+            }
+            "path" => {
+                // Like browsers, draw the path up to the first error.
+                let _ = parse_into(element.attribute("d").unwrap_or(""), &mut path);
+            }
+            _ => return None,
+        }
+        if path.is_empty() {
+            None
+        } else {
+            Some(path)
+        }
+    }
+
+    fn add_shape(&mut self, path: PathData, style: &Style, transform: Transform) {
+        if !style.visible {
+            return;
+        }
+        let bounds = bounds(&path);
+        let fill = self.resolve(&style.fill, style.fill_opacity, style, bounds);
+        let stroke = if style.stroke_width > 0.0 {
+            self.resolve(&style.stroke, style.stroke_opacity, style, bounds)
+        } else {
+            None
+        };
+        if fill.is_none() && stroke.is_none() {
+            return;
+        }
+
+        self.shapes.push(Shape {
+            path: if fill.is_some() {
+                with_windings(&path, style.even_odd)
+            } else {
+                path
+            },
+            transform,
+            fill,
+            stroke: stroke.map(|stroke| {
+                (
+                    stroke,
+                    StrokeOptions {
+                        width: style.stroke_width,
+                        line_cap: style.line_cap,
+                        line_join: style.line_join,
+                        miter_limit: style.miter_limit,
+                        dash_array: style.dash_array.clone(),
+                        dash_offset: style.dash_offset,
+                        ..Default::default()
+                    },
+                )
+            }),
+            opacity: style.opacity,
+        });
+    }
+
+    fn resolve(&self, paint: &SvgPaint, opacity: f32, style: &Style, bounds: [f32; 4]) -> Option<DisplayPaint> {
+        match *paint {
+            SvgPaint::None => None,
+            SvgPaint::Color(color) => Some(DisplayPaint::Color(with_opacity(color, opacity))),
+            SvgPaint::CurrentColor => Some(DisplayPaint::Color(with_opacity(style.color, opacity))),
+            SvgPaint::Url(ref id, ref fallback) => match self.gradients.get(id.as_str()) {
+                Some(gradient) => self.gradient(gradient, opacity, bounds),
+                None => self.resolve(fallback, opacity, style, bounds),
+            },
+        }
+    }
+
+    /// Look up an attribute of a gradient, or of the gradients it references with `href`.
+    fn gradient_attribute(&self, gradient: &'a Element, name: &str) -> Option<&'a str> {
+        let mut gradient = gradient;
+        for _ in 0..16 {
+            if let Some(value) = gradient.attribute(name) {
+                return Some(value);
+            }
+            gradient = self.href(gradient)?;
+        }
+        None
+    }
+
+    fn href(&self, gradient: &Element) -> Option<&'a Element> {
+        let href = gradient
+            .attribute("href")
+            .or_else(|| gradient.attribute("xlink:href"))?;
+        href.trim()
+            .strip_prefix('#')
+            .and_then(|id| self.gradients.get(id))
+            .cloned()
+    }
+
+    fn gradient(&self, element: &'a Element, opacity: f32, [x, y, w, h]: [f32; 4]) -> Option<DisplayPaint> {
+        // The stops of the first gradient in the chain of references which has any.
+        let mut stops = Vec::new();
+        let mut current = element;
+        for _ in 0..16 {
+            stops = gradient_stops(current);
+            if !stops.is_empty() {
+                break;
+            }
+            current = match self.href(current) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        let (&(start_offset, start_color), &(end_offset, end_color)) = match (stops.first(), stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return None,
+        };
+        let start_color = with_opacity(start_color, opacity);
+        let end_color = with_opacity(end_color, opacity);
+        if stops.len() == 1 {
+            return Some(DisplayPaint::Color(end_color));
+        }
+
+        let user_space = self.gradient_attribute(element, "gradientUnits") == Some("userSpaceOnUse");
+        let units = if user_space {
+            Transform::new()
+        } else if w > 0.0 && h > 0.0 {
+            matrix([w, 0.0, 0.0, h, x, y])
+        } else {
+            return None;
+        };
+        let transform = self
+            .gradient_attribute(element, "gradientTransform")
+            .map_or_else(Transform::new, parse_transform)
+            * units;
+        let (width, height) = self.viewport;
+        let diagonal = ((width * width + height * height) / 2.0).sqrt();
+        let coordinate = |name: &str, default: &str, reference: f32| {
+            let value = self.gradient_attribute(element, name).unwrap_or(default);
+            if user_space {
+                length(value, reference)
+            } else {
+                length(value, 1.0)
+            }
+            .unwrap_or(0.0)
+        };
+
+        let gradient = if element.name == "linearGradient" {
+            let start = (coordinate("x1", "0%", width), coordinate("y1", "0%", height));
+            let end = (coordinate("x2", "100%", width), coordinate("y2", "0%", height));
+            let (vx, vy) = (end.0 - start.0, end.1 - start.1);
+            let length = (end_offset - start_offset) * (vx * vx + vy * vy).sqrt();
+            if length < 1e-6 {
+                return Some(DisplayPaint::Color(end_color));
+            }
+            let stop_start = (start.0 + vx * start_offset, start.1 + vy * start_offset);
+            let (ux, uy) = (vx * (end_offset - start_offset), vy * (end_offset - start_offset));
+
+            // Keep the colors constant along lines perpendicular to the gradient in gradient space,
+            // which aren't perpendicular in user space under skews and non-uniform scales.
+            let [a, b, c, d, _, _] = transform.matrix;
+            let determinant = a * d - b * c;
+            if determinant.abs() < 1e-12 {
+                return None;
+            }
+            let squared = ux * ux + uy * uy;
+            let wx = (d * ux - b * uy) / determinant / squared;
+            let wy = (-c * ux + a * uy) / determinant / squared;
+            let w_squared = wx * wx + wy * wy;
+            let start = transform.transform_point(stop_start);
+            Gradient::Linear {
+                start,
+                end: (start.0 + wx / w_squared, start.1 + wy / w_squared),
+                start_color,
+                end_color,
+            }
+        } else {
+            let center = (coordinate("cx", "50%", width), coordinate("cy", "50%", height));
+            let radius = coordinate("r", "50%", diagonal);
+            let [a, b, c, d, _, _] = transform.matrix;
+            let scale = (a * d - b * c).abs().sqrt();
+            Gradient::Radial {
+                center: transform.transform_point(center),
+                inner_radius: radius * start_offset * scale,
+                outer_radius: radius * end_offset * scale,
+                start_color,
+                end_color,
+            }
+        };
+        Some(DisplayPaint::Gradient(gradient))
+    }
+}
+
+/// The presentation attributes of an element, followed by the declarations of its `style`, which take precedence.
+fn properties(element: &Element) -> Vec<(&str, &str)> {
+    let mut properties: Vec<(&str, &str)> = element
+        .attributes
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    if let Some(style) = element.attribute("style") {
+        for declaration in style.split(';') {
+            if let Some(colon) = declaration.find(':') {
+                properties.push((declaration[..colon].trim(), declaration[colon + 1..].trim()));
+            }
+        }
+    }
+    properties
+}
+
+fn property<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    properties(element)
+        .into_iter()
+        .rev()
+        .find(|&(key, _)| key == name)
+        .map(|(_, value)| value.trim())
+}
+
+fn gradient_stops(gradient: &Element) -> Vec<(f32, Color)> {
+    let mut stops: Vec<(f32, Color)> = Vec::new();
+    for stop in gradient.children.iter().filter(|child| child.name == "stop") {
+        let offset = stop.attribute("offset").and_then(opacity).unwrap_or(0.0);
+        // Offsets never decrease.
+        let offset = stops.last().map_or(offset, |&(last, _)| offset.max(last));
+        let color = property(stop, "stop-color")
+            .and_then(color)
+            .unwrap_or(Color::new(0.0, 0.0, 0.0, 1.0));
+        let alpha = property(stop, "stop-opacity").and_then(opacity).unwrap_or(1.0);
+        stops.push((offset, with_opacity(color, alpha)));
+    }
+    stops
+}
+
+/// Mark every subpath as solid or hole, so NanoVG fills the same areas as the SVG fill rule.
+///
+/// NanoVG reorients each subpath to its winding before filling with nonzero, so a subpath is a hole
+/// when the area inside it isn't filled, but the area around it is.
+fn with_windings(path: &PathData, even_odd: bool) -> PathData {
+    let subpaths: Vec<Vec<(f32, f32)>> = path
+        .flatten(TOLERANCE)
+        .into_iter()
+        .map(|polyline| polyline.points)
+        .collect();
+    let moves = path
+        .segments()
+        .iter()
+        .filter(|segment| matches!(segment, PathSegment::MoveTo(_)))
+        .count();
+    if subpaths.len() < 2 || subpaths.len() != moves {
+        return path.clone();
+    }
+
+    let holes: Vec<bool> = subpaths
+        .iter()
+        .enumerate()
+        .map(|(i, points)| {
+            if points.len() < 3 {
+                return false;
+            }
+            let others = subpaths
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| j != i && other.len() > 2)
+                .map(|(_, other)| winding_number(other, points[0]));
+            if even_odd {
+                others.filter(|&winding| winding != 0).count() % 2 == 1
+            } else {
+                let around: i32 = others.sum();
+                let own = if polygon_area(points) > 0.0 { 1 } else { -1 };
+                around != 0 && around + own == 0
+            }
+        })
+        .collect();
+
+    let mut result = PathData::new();
+    let mut subpath = 0;
+    for segment in path.segments() {
+        if let PathSegment::MoveTo(_) = *segment {
+            if result.current_point().is_some() {
+                result.winding(solidity(holes[subpath]));
+                subpath += 1;
+            }
+        }
+        result.push(*segment);
+    }
+    result.winding(solidity(holes[subpath]));
+    result
+}
+
+fn solidity(hole: bool) -> Winding {
+    Winding::Solidity(if hole { Solidity::Hole } else { Solidity::Solid })
+}
+
+/// The bounding box of the path as `[x, y, width, height]`.
+fn bounds(path: &PathData) -> [f32; 4] {
+    match path.bounds(&Transform::new()) {
+        Some(bounds) => [bounds.min_x, bounds.min_y, bounds.width(), bounds.height()],
+        None => [0.0; 4],
+    }
+}
+
+/// A rectangle with elliptical corners, like NanoVG's rounded rectangles if they are circular.
+fn rounded_rect(path: &mut PathData, (x, y): (f32, f32), (w, h): (f32, f32), (rx, ry): (f32, f32)) {
+    if rx <= 0.0 || ry <= 0.0 {
+        path.rect((x, y), (w, h));
+    } else if rx == ry {
+        path.rounded_rect((x, y), (w, h), rx);
+    } else {
+        let radii = (rx, ry);
+        path.move_to((x + rx, y));
+        path.line_to((x + w - rx, y));
+        arc_to(path, (x + w - rx, y), radii, 0.0, false, true, (x + w, y + ry));
+        path.line_to((x + w, y + h - ry));
+        arc_to(path, (x + w, y + h - ry), radii, 0.0, false, true, (x + w - rx, y + h));
+        path.line_to((x + rx, y + h));
+        arc_to(path, (x + rx, y + h), radii, 0.0, false, true, (x, y + h - ry));
+        path.line_to((x, y + ry));
+        arc_to(path, (x, y + ry), radii, 0.0, false, true, (x + rx, y));
+        path.close();
+    }
+}
+
+fn matrix(matrix: [f32; 6]) -> Transform {
+    Transform {
+        matrix,
+        absolute: false,
+    }
+}
+
+fn element_transform(element: &Element) -> Transform {
+    element
+        .attribute("transform")
+        .map_or_else(Transform::new, parse_transform)
+}
+
+/// Parse an SVG transform list like `translate(10 20) rotate(45)`, up to the first invalid transform.
+fn parse_transform(text: &str) -> Transform {
+    let mut transform = Transform::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('(') {
+        let close = match rest[open..].find(')') {
+            Some(close) => open + close,
+            None => break,
+        };
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let args = parse_numbers(&rest[open + 1..close]);
+        let arg = |i: usize, default: f32| args.get(i).cloned().unwrap_or(default);
+        let item = match (name, args.len()) {
+            ("matrix", 6) => matrix([args[0], args[1], args[2], args[3], args[4], args[5]]),
+            ("translate", 1) | ("translate", 2) => matrix([1.0, 0.0, 0.0, 1.0, args[0], arg(1, 0.0)]),
+            ("scale", 1) | ("scale", 2) => matrix([args[0], 0.0, 0.0, arg(1, args[0]), 0.0, 0.0]),
+            ("rotate", 1) | ("rotate", 3) => {
+                let (sin, cos) = args[0].to_radians().sin_cos();
+                let (cx, cy) = (arg(1, 0.0), arg(2, 0.0));
+                matrix([1.0, 0.0, 0.0, 1.0, -cx, -cy])
+                    * matrix([cos, sin, -sin, cos, 0.0, 0.0])
+                    * matrix([1.0, 0.0, 0.0, 1.0, cx, cy])
+            }
+            ("skewX", 1) => matrix([1.0, 0.0, args[0].to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", 1) => matrix([1.0, args[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => break,
+        };
+        // The last transform of the list applies first.
+        transform = item * transform;
+        rest = &rest[close + 1..];
+    }
+    transform
+}
+
+fn number(text: &str) -> Option<f32> {
+    parse_numbers(text).first().cloned()
+}
+
+/// Parse a length with an optional unit, where percentages are relative to `reference`.
+fn length(text: &str, reference: f32) -> Option<f32> {
+    let text = text.trim();
+    let unit_start = text
+        .rfind(|c: char| c.is_ascii_digit() || c == '.')
+        .map_or(0, |i| i + 1);
+    let value = number(&text[..unit_start])?;
+    let scale = match &text[unit_start..] {
+        "" | "px" => 1.0,
+        "%" => return Some(value * reference / 100.0),
+        "pt" => 96.0 / 72.0,
+        "pc" => 16.0,
+        "mm" => 96.0 / 25.4,
+        "cm" => 96.0 / 2.54,
+        "in" => 96.0,
+        "em" => 16.0,
+        "ex" => 8.0,
+        _ => return None,
+    };
+    Some(value * scale)
+}
+
+/// Parse an opacity or stop offset, a number or percentage clamped to `0.0..=1.0`.
+fn opacity(text: &str) -> Option<f32> {
+    length(text, 1.0).map(|value| value.clamp(0.0, 1.0))
+}
+
+fn with_opacity(color: Color, opacity: f32) -> Color {
+    Color::new(color.red(), color.green(), color.blue(), color.alpha() * opacity)
+}
+
+fn paint(text: &str) -> Option<SvgPaint> {
+    let text = text.trim();
+    if text == "none" {
+        Some(SvgPaint::None)
+    } else if text == "currentColor" {
+        Some(SvgPaint::CurrentColor)
+    } else if text.starts_with("url(") {
+        let close = text.find(')')?;
+        let id = text[4..close].trim().trim_matches(|c| c == '\'' || c == '"');
+        let id = id.strip_prefix('#').unwrap_or(id).to_owned();
+        let fallback = paint(&text[close + 1..]).unwrap_or(SvgPaint::None);
+        Some(SvgPaint::Url(id, Box::new(fallback)))
+    } else {
+        color(text).map(SvgPaint::Color)
+    }
+}
+
+fn color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        if !hex.is_ascii() {
+            return None;
+        }
+        return match hex.len() {
+            3 => Some(Color::from_rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            4 => Some(Color::from_rgba(
+                digit(0)? * 17,
+                digit(1)? * 17,
+                digit(2)? * 17,
+                digit(3)? * 17,
+            )),
+            6 => Some(Color::from_rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Color::from_rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        };
+    }
+    if text.starts_with("rgb") {
+        let open = text.find('(')?;
+        let close = open + text[open..].find(')')?;
+        let channels: Vec<f32> = text[open + 1..close]
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|channel| !channel.is_empty())
+            .map(|channel| length(channel, 255.0))
+            .collect::<Option<_>>()?;
+        return match channels.len() {
+            3 | 4 => Some(Color::new(
+                (channels[0] / 255.0).clamp(0.0, 1.0),
+                (channels[1] / 255.0).clamp(0.0, 1.0),
+                (channels[2] / 255.0).clamp(0.0, 1.0),
+                channels.get(3).map_or(1.0, |&alpha| {
+                    // Percentages were scaled to 0..255 like the color channels.
+                    if text[open..close].trim_end().ends_with('%') {
+                        alpha / 255.0
+                    } else {
+                        alpha
+                    }
+                    .clamp(0.0, 1.0)
+                }),
+            )),
+            _ => None,
+        };
+    }
+    let (r, g, b) = match text.to_ascii_lowercase().as_str() {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "lime" => (0, 255, 0),
+        "green" => (0, 128, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "maroon" => (128, 0, 0),
+        "olive" => (128, 128, 0),
+        "purple" => (128, 0, 128),
+        "teal" => (0, 128, 128),
+        "navy" => (0, 0, 128),
+        "orange" => (255, 165, 0),
+        "brown" => (165, 42, 42),
+        "pink" => (255, 192, 203),
+        "gold" => (255, 215, 0),
+        "darkgray" | "darkgrey" => (169, 169, 169),
+        "lightgray" | "lightgrey" => (211, 211, 211),
+        "transparent" => return Some(Color::new(0.0, 0.0, 0.0, 0.0)),
+        _ => return None,
+    };
+    Some(Color::from_rgb(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_shapes() {
+        let document = Document::parse(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 20 10">
+                <defs>
+                    <linearGradient id="fade" x2="0" y2="1">
+                        <stop offset="0" stop-color="#f00"/>
+                        <stop offset="100%" style="stop-color: blue; stop-opacity: .5"/>
+                    </linearGradient>
+                </defs>
+                <g fill="url(#fade)" opacity="0.5" transform="translate(1, 1)">
+                    <rect width="4" height="2"/>
+                    <circle r="1" style="fill: none; stroke: rgb(0, 255, 0); stroke-dasharray: 1, 50%"/>
+                    <ellipse rx="0" ry="1"/>
+                </g>
+                <path d="M0 0h4v4h-4z M1 1v2h2v-2z" fill-rule="evenodd"/>
+            </svg>"##,
+        )
+        .unwrap();
+        assert_eq!(document.size(), (200.0, 100.0));
+        assert_eq!(document.shapes.len(), 3);
+
+        let rect = &document.shapes[0];
+        assert_eq!(rect.opacity, 0.5);
+        assert_eq!(rect.transform.matrix, [10.0, 0.0, 0.0, 10.0, 10.0, 10.0]);
+        match rect.fill {
+            Some(DisplayPaint::Gradient(Gradient::Linear {
+                start,
+                end,
+                start_color,
+                end_color,
+            })) => {
+                assert_eq!((start, end), ((0.0, 0.0), (0.0, 2.0)));
+                assert_eq!(start_color, Color::from_rgb(255, 0, 0));
+                assert_eq!(end_color, Color::new(0.0, 0.0, 1.0, 0.5));
+            }
+            ref other => panic!("expected a linear gradient, got {:?}", other),
+        }
+
+        let circle = &document.shapes[1];
+        assert!(circle.fill.is_none());
+        assert_eq!(
+            circle.stroke.as_ref().map(|stroke| stroke.0),
+            Some(DisplayPaint::Color(Color::new(0.0, 1.0, 0.0, 1.0)))
+        );
+        assert_eq!(circle.stroke.as_ref().unwrap().1.dash_array, vec![1.0, 7.905_694]);
+
+        // The inner square of the even-odd path is a hole.
+        let windings: Vec<Winding> = document.shapes[2]
+            .path
+            .segments()
+            .iter()
+            .filter_map(|segment| match *segment {
+                PathSegment::Winding(winding) => Some(winding),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(windings, vec![solidity(false), solidity(true)]);
+    }
+
+    #[test]
+    fn test_context_rasterizes() {
+        let document =
+            Document::parse(r#"<svg width="4" height="2"><rect x="-1" y="-1" width="3" height="4" fill="red"/></svg>"#)
+                .unwrap();
+        let pixels = document.rasterize((8, 4), false).unwrap();
+        assert_eq!(pixels.len(), 32);
+        assert_eq!(pixels[8], u32::from_ne_bytes([255, 0, 0, 255]));
+        assert_eq!(pixels[15], 0);
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let transform = parse_transform("translate(10) scale(2, 3)");
+        assert_eq!(transform.matrix, [2.0, 0.0, 0.0, 3.0, 10.0, 0.0]);
+        assert_eq!(length("1in", 0.0), Some(96.0));
+        assert_eq!(length("50%", 30.0), Some(15.0));
+        assert_eq!(length("1e1", 0.0), Some(10.0));
+        assert_eq!(color("#0f08"), Some(Color::from_rgba(0, 255, 0, 136)));
+        assert_eq!(color("rgb(100%, 0%, 0%)"), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(color("rgb)("), None);
+        assert_eq!(
+            paint("url(#a) red"),
+            Some(SvgPaint::Url("a".to_owned(), Box::new(paint("red").unwrap())))
+        );
+    }
+}
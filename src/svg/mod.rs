@@ -0,0 +1,8 @@
+//! Reading SVG data into NanoVG paths.
+
+mod document;
+mod path;
+mod xml;
+
+pub use self::document::{Document, DocumentError};
+pub use self::path::{parse_path, PathError};
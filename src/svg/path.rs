@@ -0,0 +1,402 @@
+use std::f64::consts::PI;
+
+use PathData;
+
+/// Errors of [parse_path], with the byte offset into the path data where they occurred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The path data doesn't start with a moveto command.
+    ExpectedMoveTo(usize),
+    /// A command was expected, but another character was found.
+    ExpectedCommand(usize),
+    /// A number, or an arc flag, was expected but not found.
+    ExpectedNumber(usize),
+}
+
+/// Parse SVG path data, the syntax of the `d` attribute of `<path>` elements.
+///
+/// All commands are supported in their absolute and relative forms: moveto (`M`), lineto (`L`, `H`, `V`),
+/// cubic (`C`, `S`) and quadratic (`Q`, `T`) bezier curves, elliptical arcs (`A`), which are converted
+/// to cubic bezier curves, and closepath (`Z`).
+pub fn parse_path(d: &str) -> Result<PathData, PathError> {
+    let mut path = PathData::new();
+    parse_into(d, &mut path)?;
+    Ok(path)
+}
+
+/// Parse SVG path data into `path`. On errors, `path` holds the segments before the error,
+/// which SVG renderers draw.
+pub(crate) fn parse_into(d: &str, path: &mut PathData) -> Result<(), PathError> {
+    let mut parser = Parser {
+        data: d.as_bytes(),
+        position: 0,
+    };
+    let mut current = (0.0f32, 0.0f32);
+    let mut start = (0.0f32, 0.0f32);
+    // The second control point of the last curve, for the reflection of `S` and `T`.
+    let mut last_cubic: Option<(f32, f32)> = None;
+    let mut last_quad: Option<(f32, f32)> = None;
+    let mut closed = false;
+    let mut command = None;
+
+    loop {
+        parser.skip_separators();
+        let position = parser.position;
+        let next = match parser.peek() {
+            Some(next) => next,
+            None => break,
+        };
+        let letter = if next.is_ascii_alphabetic() {
+            parser.position += 1;
+            next
+        } else {
+            // Repeated arguments reuse the last command, except that moveto becomes lineto.
+            match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(command) if command != b'Z' && command != b'z' => command,
+                _ => return Err(PathError::ExpectedCommand(position)),
+            }
+        };
+        if command.is_none() && letter != b'M' && letter != b'm' {
+            return Err(PathError::ExpectedMoveTo(position));
+        }
+        command = Some(letter);
+
+        let relative = letter.is_ascii_lowercase();
+        let offset = |(x, y): (f32, f32)| {
+            if relative {
+                (current.0 + x, current.1 + y)
+            } else {
+                (x, y)
+            }
+        };
+        // A subpath drawn right after closing one starts at the start of the closed one.
+        if closed && letter != b'M' && letter != b'm' {
+            path.move_to(start);
+        }
+        closed = false;
+
+        let mut cubic = None;
+        let mut quad = None;
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                current = offset(parser.point()?);
+                start = current;
+                path.move_to(current);
+            }
+            b'L' => {
+                current = offset(parser.point()?);
+                path.line_to(current);
+            }
+            b'H' => {
+                let x = parser.number()?;
+                current.0 = if relative { current.0 + x } else { x };
+                path.line_to(current);
+            }
+            b'V' => {
+                let y = parser.number()?;
+                current.1 = if relative { current.1 + y } else { y };
+                path.line_to(current);
+            }
+            b'C' => {
+                let control1 = offset(parser.point()?);
+                let control2 = offset(parser.point()?);
+                let point = offset(parser.point()?);
+                path.cubic_bezier_to(point, control1, control2);
+                cubic = Some(control2);
+                current = point;
+            }
+            b'S' => {
+                let control1 = reflect(last_cubic, current);
+                let control2 = offset(parser.point()?);
+                let point = offset(parser.point()?);
+                path.cubic_bezier_to(point, control1, control2);
+                cubic = Some(control2);
+                current = point;
+            }
+            b'Q' => {
+                let control = offset(parser.point()?);
+                let point = offset(parser.point()?);
+                path.quad_bezier_to(point, control);
+                quad = Some(control);
+                current = point;
+            }
+            b'T' => {
+                let control = reflect(last_quad, current);
+                let point = offset(parser.point()?);
+                path.quad_bezier_to(point, control);
+                quad = Some(control);
+                current = point;
+            }
+            b'A' => {
+                let radii = (parser.number()?.abs(), parser.number()?.abs());
+                let rotation = parser.number()?;
+                let large_arc = parser.flag()?;
+                let sweep = parser.flag()?;
+                let point = offset(parser.point()?);
+                arc_to(path, current, radii, rotation, large_arc, sweep, point);
+                current = point;
+            }
+            b'Z' => {
+                path.close();
+                current = start;
+                closed = true;
+            }
+            _ => return Err(PathError::ExpectedCommand(position)),
+        }
+        last_cubic = cubic;
+        last_quad = quad;
+    }
+
+    Ok(())
+}
+
+/// Parse a list of numbers separated by whitespace or commas, like the `points` of `<polygon>` elements,
+/// up to the first invalid one.
+pub(crate) fn parse_numbers(text: &str) -> Vec<f32> {
+    let mut parser = Parser {
+        data: text.as_bytes(),
+        position: 0,
+    };
+    let mut numbers = Vec::new();
+    while let Ok(number) = parser.number() {
+        numbers.push(number);
+    }
+    numbers
+}
+
+fn reflect(control: Option<(f32, f32)>, (x, y): (f32, f32)) -> (f32, f32) {
+    match control {
+        Some((cx, cy)) => (2.0 * x - cx, 2.0 * y - cy),
+        None => (x, y),
+    }
+}
+
+/// Add an SVG elliptical arc from `from` to `to` as cubic bezier curves,
+/// following the endpoint to center parameterization of the SVG specification.
+pub(crate) fn arc_to(
+    path: &mut PathData,
+    from: (f32, f32),
+    (rx, ry): (f32, f32),
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: (f32, f32),
+) {
+    if from == to {
+        return;
+    }
+    if rx == 0.0 || ry == 0.0 {
+        path.line_to(to);
+        return;
+    }
+
+    let (x1, y1) = (f64::from(from.0), f64::from(from.1));
+    let (x2, y2) = (f64::from(to.0), f64::from(to.1));
+    let (mut rx, mut ry) = (f64::from(rx).abs(), f64::from(ry).abs());
+    let phi = f64::from(rotation).to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+
+    let dx = (x1 - x2) / 2.0;
+    let dy = (y1 - y2) / 2.0;
+    let x1p = cos_phi * dx + sin_phi * dy;
+    let y1p = -sin_phi * dx + cos_phi * dy;
+
+    // Scale up radii which are too small to reach the end point.
+    let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+    let denominator = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+    let mut factor = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        factor = -factor;
+    }
+    let cxp = factor * rx * y1p / ry;
+    let cyp = -factor * ry * x1p / rx;
+    let cx = cos_phi * cxp - sin_phi * cyp + (x1 + x2) / 2.0;
+    let cy = sin_phi * cxp + cos_phi * cyp + (y1 + y2) / 2.0;
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| {
+        let angle = (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        if angle.is_nan() {
+            0.0
+        } else {
+            angle
+        }
+    };
+    let theta1 = angle(1.0, 0.0, (x1p - cxp) / rx, (y1p - cyp) / ry);
+    let mut delta = angle((x1p - cxp) / rx, (y1p - cyp) / ry, (-x1p - cxp) / rx, (-y1p - cyp) / ry);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    // Approximate each part of at most 90 degrees with one cubic bezier curve.
+    let segments = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = delta / segments as f64;
+    let kappa = 4.0 / 3.0 * (step / 4.0).tan();
+    let point = |theta: f64| {
+        let (sin, cos) = theta.sin_cos();
+        (
+            cx + rx * cos * cos_phi - ry * sin * sin_phi,
+            cy + rx * cos * sin_phi + ry * sin * cos_phi,
+        )
+    };
+    let derivative = |theta: f64| {
+        let (sin, cos) = theta.sin_cos();
+        (
+            -rx * sin * cos_phi - ry * cos * sin_phi,
+            -rx * sin * sin_phi + ry * cos * cos_phi,
+        )
+    };
+    for i in 0..segments {
+        let t1 = theta1 + step * i as f64;
+        let t2 = t1 + step;
+        let (p1, d1) = (point(t1), derivative(t1));
+        let (d2, mut p2) = (derivative(t2), point(t2));
+        if i + 1 == segments {
+            p2 = (x2, y2);
+        }
+        path.cubic_bezier_to(
+            (p2.0 as f32, p2.1 as f32),
+            ((p1.0 + kappa * d1.0) as f32, (p1.1 + kappa * d1.1) as f32),
+            ((p2.0 - kappa * d2.0) as f32, (p2.1 - kappa * d2.1) as f32),
+        );
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).cloned()
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == b',' || c.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn number(&mut self) -> Result<f32, PathError> {
+        self.skip_separators();
+        let start = self.position;
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.position = start;
+            return Err(PathError::ExpectedNumber(start));
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.position = mantissa_end;
+            }
+        }
+        // The slice only contains ASCII signs, digits, dots and exponents.
+        let text = ::std::str::from_utf8(&self.data[start..self.position]).unwrap();
+        text.parse().map_err(|_| PathError::ExpectedNumber(start))
+    }
+
+    fn point(&mut self) -> Result<(f32, f32), PathError> {
+        Ok((self.number()?, self.number()?))
+    }
+
+    /// Arc flags are single digits, which don't need to be separated from what follows.
+    fn flag(&mut self) -> Result<bool, PathError> {
+        self.skip_separators();
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(PathError::ExpectedNumber(self.position)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PathSegment;
+
+    #[test]
+    fn test_commands() {
+        let path = parse_path("M10,10 h10 v10 H10z m5-5 l1e1 0 L 30 30 Q40 30 40 40 T50 50").unwrap();
+        assert_eq!(
+            &path.segments()[..8],
+            &[
+                PathSegment::MoveTo((10.0, 10.0)),
+                PathSegment::LineTo((20.0, 10.0)),
+                PathSegment::LineTo((20.0, 20.0)),
+                PathSegment::LineTo((10.0, 20.0)),
+                PathSegment::Close,
+                PathSegment::MoveTo((15.0, 5.0)),
+                PathSegment::LineTo((25.0, 5.0)),
+                PathSegment::LineTo((30.0, 30.0)),
+            ]
+        );
+        assert_eq!(path.segments().len(), 10);
+        assert_eq!(path.current_point(), Some((50.0, 50.0)));
+
+        assert_eq!(parse_path("L10 10"), Err(PathError::ExpectedMoveTo(0)));
+        assert_eq!(parse_path("M10 10 L20"), Err(PathError::ExpectedNumber(10)));
+        assert_eq!(parse_path("M10 10 X"), Err(PathError::ExpectedCommand(7)));
+    }
+
+    #[test]
+    fn test_arcs() {
+        // A quarter circle with packed flags, in a single curve.
+        let path = parse_path("M0 10a10 10 0 0110 10").unwrap();
+        assert_eq!(path.segments().len(), 2);
+        // A half circle, in two quarter curves through (10, 10).
+        let path = parse_path("M0 0A10 10 0 1 0 20 0").unwrap();
+        assert_eq!(path.segments().len(), 3);
+        match path.segments()[1] {
+            PathSegment::CubicBezierTo { point, .. } => {
+                assert!(
+                    (point.0 - 10.0).abs() < 1e-4 && (point.1 - 10.0).abs() < 1e-4,
+                    "{:?}",
+                    point
+                )
+            }
+            other => panic!("expected a curve, got {:?}", other),
+        }
+        assert_eq!(path.current_point(), Some((20.0, 0.0)));
+    }
+}
@@ -0,0 +1,226 @@
+//! Just enough XML to read SVG documents: elements and attributes, without text content.
+
+/// An element with its attributes and child elements.
+#[derive(Debug, Default)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a document, returning its root element, or the byte offset of the first error.
+pub(crate) fn parse(text: &str) -> Result<Element, usize> {
+    let mut parser = Parser { text, position: 0 };
+    // The document node holds the root element and whatever else is at the top level.
+    let mut stack = vec![Element::default()];
+    loop {
+        let rest = &text[parser.position..];
+        if rest.is_empty() {
+            break;
+        }
+        if rest.starts_with("<!--") {
+            parser.skip_past(4, "-->")?;
+        } else if rest.starts_with("<![CDATA[") {
+            parser.skip_past(9, "]]>")?;
+        } else if rest.starts_with("<?") {
+            parser.skip_past(2, "?>")?;
+        } else if rest.starts_with("<!") {
+            parser.skip_declaration()?;
+        } else if rest.starts_with("</") {
+            parser.position += 2;
+            let name = parser.name()?;
+            parser.skip_whitespace();
+            parser.expect(">")?;
+            if stack.len() < 2 || stack[stack.len() - 1].name != local_name(name) {
+                return Err(parser.position);
+            }
+            let element = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(element);
+        } else if rest.starts_with('<') {
+            parser.position += 1;
+            let mut element = Element {
+                name: local_name(parser.name()?).to_owned(),
+                ..Default::default()
+            };
+            loop {
+                parser.skip_whitespace();
+                let rest = &text[parser.position..];
+                if rest.starts_with("/>") {
+                    parser.position += 2;
+                    stack.last_mut().unwrap().children.push(element);
+                    break;
+                } else if rest.starts_with('>') {
+                    parser.position += 1;
+                    stack.push(element);
+                    break;
+                }
+                let name = parser.name()?.to_owned();
+                parser.skip_whitespace();
+                parser.expect("=")?;
+                parser.skip_whitespace();
+                let value = parser.quoted()?;
+                element.attributes.push((name, value));
+            }
+        } else {
+            // Text content isn't needed, skip to the next tag.
+            parser.position += rest.find('<').unwrap_or(rest.len());
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(parser.position);
+    }
+    stack.pop().unwrap().children.into_iter().next().ok_or(parser.position)
+}
+
+/// The name without a namespace prefix, so `svg:rect` is read as `rect`.
+fn local_name(name: &str) -> &str {
+    match name.find(':') {
+        Some(colon) => &name[colon + 1..],
+        None => name,
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_past(&mut self, start: usize, end: &str) -> Result<(), usize> {
+        match self.text[self.position + start..].find(end) {
+            Some(index) => {
+                self.position += start + index + end.len();
+                Ok(())
+            }
+            None => Err(self.position),
+        }
+    }
+
+    /// Skip a declaration like `<!DOCTYPE ...>`, which may contain an internal subset in brackets.
+    fn skip_declaration(&mut self) -> Result<(), usize> {
+        let mut depth = 0;
+        for (index, c) in self.text[self.position..].char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => {
+                    self.position += index + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(self.position)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), usize> {
+        if self.text[self.position..].starts_with(token) {
+            self.position += token.len();
+            Ok(())
+        } else {
+            Err(self.position)
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, usize> {
+        let rest = &self.text[self.position..];
+        let length = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/' || c == '<')
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.position);
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn quoted(&mut self) -> Result<String, usize> {
+        let rest = &self.text[self.position..];
+        let quote = match rest.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => return Err(self.position),
+        };
+        match rest[1..].find(quote) {
+            Some(length) => {
+                self.position += length + 2;
+                Ok(unescape(&rest[1..length + 1]))
+            }
+            None => Err(self.position),
+        }
+    }
+}
+
+/// Replace the predefined entities and character references.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(::std::char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(::std::char::from_u32),
+            _ => None,
+        };
+        match character {
+            Some(character) => {
+                result.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let root = parse(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE svg [ <!ENTITY x \"y\"> ]>\n\
+             <svg:svg a='1 &amp; 2'><!-- <rect/> --><g><rect x=\"&#65;\"/>text</g></svg:svg>",
+        )
+        .unwrap();
+        assert_eq!(root.name, "svg");
+        assert_eq!(root.attribute("a"), Some("1 & 2"));
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].children[0].name, "rect");
+        assert_eq!(root.children[0].children[0].attribute("x"), Some("A"));
+
+        assert!(parse("<svg><g></svg>").is_err());
+    }
+}